@@ -0,0 +1,207 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::fmt;
+
+///
+/// The system allocator, counting every allocation made on the current thread so that
+/// [`measure`] can tell how allocation heavy a piece of code is. Binaries opt in by installing it
+/// example
+/// ```
+/// use advent_of_code_2020::alloc::CountingAllocator;
+///
+/// #[global_allocator]
+/// static ALLOCATOR: CountingAllocator = CountingAllocator;
+/// ```
+#[derive(Debug, Default, Copy, Clone)]
+pub struct CountingAllocator;
+
+#[derive(Copy, Clone)]
+struct Counters {
+    allocations: u64,
+    allocated_bytes: u64,
+    current_bytes: u64,
+    peak_bytes: u64,
+}
+
+thread_local! {
+    // const initialized without a destructor, so using it never allocates
+    static COUNTERS: Cell<Counters> = const {
+        Cell::new(Counters {
+            allocations: 0,
+            allocated_bytes: 0,
+            current_bytes: 0,
+            peak_bytes: 0,
+        })
+    };
+    static INSTALLED: Cell<bool> = const { Cell::new(false) };
+}
+
+fn update(f: impl FnOnce(&mut Counters)) {
+    // the thread locals are gone while the thread is shutting down, nothing to measure then
+    let _ = COUNTERS.try_with(|counters| {
+        let mut value = counters.get();
+        f(&mut value);
+        value.peak_bytes = value.peak_bytes.max(value.current_bytes);
+        counters.set(value);
+    });
+    let _ = INSTALLED.try_with(|installed| installed.set(true));
+}
+
+fn allocated(size: usize) {
+    update(|counters| {
+        counters.allocations += 1;
+        counters.allocated_bytes += size as u64;
+        counters.current_bytes += size as u64;
+    });
+}
+
+fn freed(size: usize) {
+    // memory allocated on another thread may be freed on this one
+    update(|counters| counters.current_bytes = counters.current_bytes.saturating_sub(size as u64));
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            allocated(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            allocated(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        freed(layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            freed(layout.size());
+            allocated(new_size);
+        }
+        new_ptr
+    }
+}
+
+/// Heap usage of a piece of code, on the thread it ran on
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct Stats {
+    /// Number of allocations, growing or shrinking an allocation counts as one
+    pub allocations: u64,
+    /// Total bytes asked for, including memory that was freed again
+    pub allocated_bytes: u64,
+    /// Most bytes that were allocated at the same time, on top of what was allocated before
+    pub peak_bytes: u64,
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} allocations, {} allocated, {} peak",
+            self.allocations,
+            bytes(self.allocated_bytes),
+            bytes(self.peak_bytes)
+        )
+    }
+}
+
+/// `bytes` in the largest unit that keeps it at least 1, e.g. "1.5 KiB"
+pub fn bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    #[allow(clippy::cast_precision_loss)]
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// Whether [`CountingAllocator`] is the global allocator, otherwise [`measure`] only sees zeros
+pub fn is_counting() -> bool {
+    // allocating is the only way to find out, the thread local is set by the allocator
+    drop(Box::new(0_u8));
+    INSTALLED.with(Cell::get)
+}
+
+///
+/// Runs `f` and counts the allocations it makes on the current thread
+/// example
+/// ```
+/// # use advent_of_code_2020::alloc::measure;
+/// let (numbers, stats) = measure(|| (0..1000_u32).collect::<Vec<_>>());
+///
+/// assert_eq!(numbers.len(), 1000);
+/// // only counted when `CountingAllocator` is the global allocator
+/// assert!(stats.allocations <= 1);
+/// ```
+pub fn measure<T>(f: impl FnOnce() -> T) -> (T, Stats) {
+    let before = COUNTERS.with(|counters| {
+        let mut value = counters.get();
+        // the peak while running `f` is all that's interesting
+        value.peak_bytes = value.current_bytes;
+        counters.set(value);
+        value
+    });
+    let result = f();
+    let after = COUNTERS.with(Cell::get);
+    (
+        result,
+        Stats {
+            allocations: after.allocations - before.allocations,
+            allocated_bytes: after.allocated_bytes - before.allocated_bytes,
+            peak_bytes: after.peak_bytes - before.current_bytes,
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_allocations() {
+        assert!(is_counting());
+
+        let (_, stats) = measure(|| {
+            let first = vec![0_u8; 1000];
+            drop(first);
+            let second = vec![0_u8; 600];
+            let third = vec![0_u8; 300];
+            second.len() + third.len()
+        });
+
+        assert_eq!(stats.allocations, 3);
+        assert_eq!(stats.allocated_bytes, 1900);
+        assert_eq!(stats.peak_bytes, 1000);
+    }
+
+    #[test]
+    fn test_nothing_allocated() {
+        let (sum, stats) = measure(|| (0..100_u32).sum::<u32>());
+
+        assert_eq!(sum, 4950);
+        assert_eq!(stats, Stats::default());
+    }
+
+    #[test]
+    fn test_bytes() {
+        assert_eq!(bytes(512), "512 B");
+        assert_eq!(bytes(1536), "1.5 KiB");
+        assert_eq!(bytes(3 * 1024 * 1024), "3.0 MiB");
+    }
+}
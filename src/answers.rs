@@ -0,0 +1,442 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+use toml::Spanned;
+
+use crate::error::{ParseError, SolveError};
+use crate::solver::{Answer, DynSolver, Part, Registry};
+use crate::util::read_input;
+
+/// The manifest for the inputs in `input/2020`, compiled in so that tests can check against it
+const BUNDLED: &str = include_str!("../answers.toml");
+
+#[derive(Deserialize)]
+struct File {
+    #[serde(default)]
+    answers: Vec<Entry>,
+}
+
+#[derive(Deserialize)]
+struct Entry {
+    day: u32,
+    part: Spanned<u32>,
+    value: Value,
+}
+
+/// Numeric answers can be written without quotes
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Value {
+    Number(i64),
+    Text(String),
+}
+
+///
+/// Known answers, keyed by day and part, read from a TOML file with one `[[answers]]` table per part
+/// example
+/// ```
+/// # use advent_of_code_2020::answers::Manifest;
+/// # use advent_of_code_2020::solver::Part;
+/// let manifest: Manifest = r#"
+/// [[answers]]
+/// day = 5
+/// part = 1
+/// value = 906
+///
+/// [[answers]]
+/// day = 5
+/// part = 2
+/// value = "519"
+/// "#
+/// .parse()
+/// .unwrap();
+///
+/// assert_eq!(manifest.get(5, Part::One), Some("906"));
+/// assert_eq!(manifest.get(5, Part::Two), Some("519"));
+/// assert_eq!(manifest.get(6, Part::One), None);
+/// ```
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Manifest {
+    answers: BTreeMap<(u32, Part), Answer>,
+}
+
+impl Manifest {
+    /// The answers for the puzzle inputs checked into this repository
+    pub fn bundled() -> Self {
+        BUNDLED
+            .parse()
+            .unwrap_or_else(|e| panic!("answers.toml is broken: {}", e))
+    }
+
+    pub fn get(&self, day: u32, part: Part) -> Option<&str> {
+        self.answers.get(&(day, part)).map(String::as_str)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (u32, Part, &str)> {
+        self.answers
+            .iter()
+            .map(|((day, part), answer)| (*day, *part, answer.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.answers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.answers.is_empty()
+    }
+}
+
+impl FromStr for Manifest {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<Self, ParseError> {
+        let file: File = toml::from_str(input).map_err(|e| {
+            let error = ParseError::new("an answers manifest", e.message().trim());
+            match e.span() {
+                Some(span) => at_offset(error, input, span.start),
+                None => error,
+            }
+        })?;
+
+        let mut answers = BTreeMap::new();
+        for entry in file.answers {
+            let part_start = entry.part.span().start;
+            let at_part = |error| at_offset(error, input, part_start);
+            let number = *entry.part.get_ref();
+            let part = Part::from_number(number)
+                .ok_or_else(|| at_part(ParseError::new("part 1 or 2", number.to_string())))?;
+            let value = match entry.value {
+                Value::Number(number) => number.to_string(),
+                Value::Text(text) => text,
+            };
+            if answers.insert((entry.day, part), value).is_some() {
+                return Err(at_part(ParseError::new(
+                    "one answer per day and part",
+                    format!("a second answer for day {} part {}", entry.day, part),
+                )));
+            }
+        }
+        Ok(Self { answers })
+    }
+}
+
+/// Points `error` at the line and column of the byte `offset` in `input`
+fn at_offset(error: ParseError, input: &str, offset: usize) -> ParseError {
+    let before = &input[..offset.min(input.len())];
+    let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+    error
+        .at_column(before[line_start..].chars().count() + 1)
+        .at_line(before.matches('\n').count() + 1)
+}
+
+///
+/// The answer for `day` and `part` in the bundled manifest, for the `verify_part*` tests
+///
+/// Panics if there is none
+pub fn expected(day: u32, part: Part) -> Answer {
+    Manifest::bundled()
+        .get(day, part)
+        .unwrap_or_else(|| panic!("answers.toml has no answer for day {} part {}", day, part))
+        .to_owned()
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Status {
+    Pass,
+    /// The solution gave a different answer than the manifest
+    Wrong {
+        expected: Answer,
+    },
+    /// The input didn't parse
+    Invalid(ParseError),
+    /// The input parsed, but the solution has no answer for it
+    Failed(SolveError),
+    /// The input file couldn't be read
+    NoInput(PathBuf),
+    /// The manifest has no answer to compare with
+    NoAnswer,
+    /// The manifest has an answer for a part without a registered solution
+    NoSolution,
+}
+
+impl Status {
+    pub const fn is_failure(&self) -> bool {
+        matches!(
+            self,
+            Self::Wrong { .. } | Self::Invalid(_) | Self::Failed(_)
+        )
+    }
+
+    /// `PASS`, `FAIL` or `MISSING`
+    pub const fn label(&self) -> &'static str {
+        match self {
+            Self::Pass => "PASS",
+            Self::Wrong { .. } | Self::Invalid(_) | Self::Failed(_) => "FAIL",
+            Self::NoInput(_) | Self::NoAnswer | Self::NoSolution => "MISSING",
+        }
+    }
+}
+
+/// How the main solution or one variant of a part did against the manifest
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome {
+    pub day: u32,
+    pub part: Part,
+    /// `None` for the main solution
+    pub variant: Option<&'static str>,
+    pub status: Status,
+    pub answer: Option<Answer>,
+    /// Parsing is shared by all variants of a day, so they all report the same parse time
+    pub parse_time: Option<Duration>,
+    pub solve_time: Option<Duration>,
+}
+
+impl Outcome {
+    fn new(day: u32, part: Part, variant: Option<&'static str>, status: Status) -> Self {
+        Self {
+            day,
+            part,
+            variant,
+            status,
+            answer: None,
+            parse_time: None,
+            solve_time: None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.variant.unwrap_or("main")
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:<7}  day {:>2} part {}  {:<24}",
+            self.status.label(),
+            self.day,
+            self.part,
+            self.name()
+        )?;
+        match &self.status {
+            Status::Pass => write!(f, "  {}", self.answer.as_deref().unwrap_or_default())?,
+            Status::Wrong { expected } => write!(
+                f,
+                "  expected {}, got {}",
+                expected,
+                self.answer.as_deref().unwrap_or_default()
+            )?,
+            Status::Invalid(error) => write!(f, "  {}", error)?,
+            Status::Failed(error) => write!(f, "  {}", error)?,
+            Status::NoInput(path) => write!(f, "  no input at {:?}", path)?,
+            Status::NoAnswer => write!(
+                f,
+                "  no answer in the manifest, got {}",
+                self.answer.as_deref().unwrap_or_default()
+            )?,
+            Status::NoSolution => write!(f, "  no registered solution")?,
+        }
+        if let (Some(parse), Some(solve)) = (self.parse_time, self.solve_time) {
+            write!(f, "  (parse {:.1?}, solve {:.1?})", parse, solve)?;
+        }
+        Ok(())
+    }
+}
+
+///
+/// Runs the main solution and every variant of every solver in `registry` on the input at
+/// `input_path(day)` and compares the answers to `manifest`. `day` limits it to a single day.
+/// Answers in the manifest for days or parts that aren't registered are reported as
+/// [`Status::NoSolution`]
+/// example
+/// ```
+/// # use advent_of_code_2020::answers::{self, Manifest, Status};
+/// # use advent_of_code_2020::solver::{registry, Part};
+/// # use std::path::PathBuf;
+/// let registry = registry();
+/// let manifest = Manifest::bundled();
+///
+/// let outcomes = answers::verify(&registry, &manifest, Some(6), |day| {
+///     PathBuf::from(format!("input/2020/day{}.txt", day))
+/// });
+///
+/// assert_eq!(outcomes.len(), 4);
+/// assert!(outcomes.iter().all(|outcome| outcome.status == Status::Pass));
+/// ```
+pub fn verify(
+    registry: &Registry,
+    manifest: &Manifest,
+    day: Option<u32>,
+    input_path: impl Fn(u32) -> PathBuf,
+) -> Vec<Outcome> {
+    let mut outcomes = registry
+        .values()
+        .filter(|solver| day.is_none_or(|day| day == solver.day()))
+        .flat_map(|solver| verify_day(solver.as_ref(), manifest, &input_path(solver.day())))
+        .collect::<Vec<_>>();
+
+    outcomes.extend(
+        manifest
+            .iter()
+            .filter(|(answer_day, _, _)| day.is_none_or(|day| day == *answer_day))
+            .filter(|(day, part, _)| {
+                !outcomes
+                    .iter()
+                    .any(|outcome| outcome.day == *day && outcome.part == *part)
+            })
+            .map(|(day, part, _)| Outcome::new(day, part, None, Status::NoSolution))
+            .collect::<Vec<_>>(),
+    );
+    outcomes.sort_by_key(|outcome| (outcome.day, outcome.part));
+    outcomes
+}
+
+fn verify_day(solver: &dyn DynSolver, manifest: &Manifest, path: &Path) -> Vec<Outcome> {
+    let day = solver.day();
+    let runs = Part::BOTH
+        .iter()
+        .flat_map(|part| {
+            std::iter::once(None)
+                .chain(solver.variant_names(*part).into_iter().map(Some))
+                .map(move |variant| (*part, variant))
+        })
+        .collect::<Vec<_>>();
+    let all_with = |status: Status| {
+        runs.iter()
+            .map(|(part, variant)| Outcome::new(day, *part, *variant, status.clone()))
+            .collect()
+    };
+
+    let input = match read_input(path) {
+        Ok(input) => input,
+        Err(_) => return all_with(Status::NoInput(path.to_owned())),
+    };
+    let start = Instant::now();
+    let parsed = match solver.parse_boxed(&input) {
+        Ok(parsed) => parsed,
+        Err(error) => return all_with(Status::Invalid(error)),
+    };
+    let parse_time = start.elapsed();
+
+    runs.iter()
+        .filter_map(|(part, variant)| {
+            let start = Instant::now();
+            let solution = solver.solve(parsed.as_ref(), *part, *variant)?;
+            let solve_time = start.elapsed();
+            let answer = match solution {
+                Ok(answer) => answer,
+                Err(error) => {
+                    return Some(Outcome {
+                        parse_time: Some(parse_time),
+                        solve_time: Some(solve_time),
+                        ..Outcome::new(day, *part, *variant, Status::Failed(error))
+                    })
+                }
+            };
+            let status = match manifest.get(day, *part) {
+                Some(expected) if expected == answer => Status::Pass,
+                Some(expected) => Status::Wrong {
+                    expected: expected.to_owned(),
+                },
+                None => Status::NoAnswer,
+            };
+            Some(Outcome {
+                answer: Some(answer),
+                parse_time: Some(parse_time),
+                solve_time: Some(solve_time),
+                ..Outcome::new(day, *part, *variant, status)
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate;
+    use crate::solver::registry;
+    use std::fs;
+
+    #[test]
+    fn test_bundled_manifest_parses() {
+        let manifest = Manifest::bundled();
+
+        assert_eq!(manifest.get(1, Part::One), Some("485739"));
+        assert_eq!(manifest.get(19, Part::Two), None);
+        assert!(registry()
+            .keys()
+            .all(|day| manifest.get(*day, Part::One).is_some()));
+        assert!((1..=18).all(|day| manifest.get(day, Part::Two).is_some()));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = "[[answers]]\nday = 1\npart = 3\nvalue = 1"
+            .parse::<Manifest>()
+            .unwrap_err();
+        assert_eq!((error.line, error.column), (Some(3), Some(8)));
+        assert_eq!(error.expected, "part 1 or 2");
+
+        let twice = "[[answers]]\nday = 1\npart = 1\nvalue = 1\n";
+        let error = format!("{}{}", twice, twice)
+            .parse::<Manifest>()
+            .unwrap_err();
+        assert_eq!(error.line, Some(7));
+
+        let error = "[[answers]]\nday = 1\npart = 1\nvalue = true"
+            .parse::<Manifest>()
+            .unwrap_err();
+        assert_eq!(error.line, Some(4));
+    }
+
+    #[test]
+    fn test_verify_generated_input() {
+        let dir = std::env::temp_dir().join(format!("aoc2020-verify-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let puzzle = generate::generate(5, 7).unwrap();
+        fs::write(dir.join("day5.txt"), &puzzle.input).unwrap();
+        // parses, but jumps out of bounds
+        fs::write(dir.join("day8.txt"), "jmp +3\nnop +0").unwrap();
+        let manifest: Manifest = format!(
+            "[[answers]]\nday = 5\npart = 1\nvalue = \"{}\"\n\n\
+             [[answers]]\nday = 5\npart = 2\nvalue = 0\n\n\
+             [[answers]]\nday = 6\npart = 1\nvalue = 1\n\n\
+             [[answers]]\nday = 30\npart = 1\nvalue = 1",
+            puzzle.part1
+        )
+        .parse()
+        .unwrap();
+
+        let outcomes = verify(&registry(), &manifest, None, |day| {
+            dir.join(format!("day{}.txt", day))
+        });
+        fs::remove_dir_all(&dir).unwrap();
+
+        let status = |day, part| {
+            outcomes
+                .iter()
+                .find(|outcome| (outcome.day, outcome.part, outcome.variant) == (day, part, None))
+                .map(|outcome| outcome.status.clone())
+        };
+        assert_eq!(status(5, Part::One), Some(Status::Pass));
+        assert_eq!(
+            status(5, Part::Two),
+            Some(Status::Wrong {
+                expected: "0".into()
+            })
+        );
+        assert!(matches!(status(6, Part::One), Some(Status::NoInput(_))));
+        assert_eq!(status(30, Part::One), Some(Status::NoSolution));
+        assert!(
+            matches!(status(8, Part::One), Some(Status::Failed(error)) if error.day == Some(8))
+        );
+        // day 5 part 2, and day 8 part 1 with its threaded variant
+        assert_eq!(outcomes.iter().filter(|o| o.status.is_failure()).count(), 3);
+    }
+}
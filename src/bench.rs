@@ -0,0 +1,308 @@
+use std::fmt;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use crate::error::{ParseError, SolveError};
+use crate::solver::{Answer, DynSolver, Part};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Options {
+    /// Untimed runs before measuring, to warm up caches and branch predictors
+    pub warmup: usize,
+    pub iterations: usize,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            warmup: 3,
+            iterations: 20,
+        }
+    }
+}
+
+/// Timings for the main solution or one variant of a part
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timing {
+    /// `None` for the main solution
+    pub variant: Option<&'static str>,
+    pub answer: Result<Answer, SolveError>,
+    pub median: Duration,
+    pub p95: Duration,
+    /// How many times faster than the main solution, by median
+    pub speed_up: f64,
+}
+
+impl Timing {
+    pub fn name(&self) -> &'static str {
+        self.variant.unwrap_or("main")
+    }
+
+    /// The answer, or why there is none
+    fn outcome(&self) -> String {
+        match &self.answer {
+            Ok(answer) => answer.clone(),
+            Err(error) => format!("failed: {}", error.message),
+        }
+    }
+}
+
+/// Every variant of one day and part, measured on the same input
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub day: u32,
+    pub part: Part,
+    pub options: Options,
+    pub timings: Vec<Timing>,
+}
+
+///
+/// Benchmarks the main solution and every variant of `part`. The input is parsed once up front,
+/// only solving is timed
+/// example
+/// ```
+/// # use advent_of_code_2020::bench::{self, Options};
+/// # use advent_of_code_2020::solver::{registry, Part};
+/// let registry = registry();
+/// let options = Options { warmup: 1, iterations: 5 };
+///
+/// let report = bench::bench(registry[&6].as_ref(), "abc\n\nab\nac", Part::One, options).unwrap();
+///
+/// let names = report.timings.iter().map(|t| t.name()).collect::<Vec<_>>();
+/// assert_eq!(names, vec!["main", "fp", "bit"]);
+/// assert!(report.timings.iter().all(|t| t.answer == Ok("6".into())));
+/// ```
+pub fn bench(
+    solver: &dyn DynSolver,
+    input: &str,
+    part: Part,
+    options: Options,
+) -> Result<Report, ParseError> {
+    let parsed = solver.parse_boxed(input)?;
+
+    let variants = std::iter::once(None).chain(solver.variant_names(part).into_iter().map(Some));
+    let mut timings = variants
+        .filter_map(|variant| {
+            let solve = || solver.solve(parsed.as_ref(), part, variant);
+            for _ in 0..options.warmup {
+                black_box(solve());
+            }
+            let mut answer = solve()?;
+            let mut samples = Vec::with_capacity(options.iterations);
+            for _ in 0..options.iterations {
+                let start = Instant::now();
+                answer = black_box(solve())?;
+                samples.push(start.elapsed());
+            }
+            samples.sort();
+            Some(Timing {
+                variant,
+                answer,
+                median: median(&samples),
+                p95: percentile(&samples, 95),
+                speed_up: 1.0,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    if let Some(baseline) = timings.first().map(|main| main.median) {
+        for timing in &mut timings {
+            timing.speed_up = speed_up(baseline, timing.median);
+        }
+    }
+
+    Ok(Report {
+        day: solver.day(),
+        part,
+        options,
+        timings,
+    })
+}
+
+/// Median of sorted `samples`, zero if there are none
+fn median(samples: &[Duration]) -> Duration {
+    match samples.len() {
+        0 => Duration::default(),
+        len if len % 2 == 0 => (samples[len / 2 - 1] + samples[len / 2]) / 2,
+        len => samples[len / 2],
+    }
+}
+
+/// Nearest-rank percentile of sorted `samples`, zero if there are none
+fn percentile(samples: &[Duration], percent: usize) -> Duration {
+    let rank = (samples.len() * percent).div_ceil(100);
+    samples
+        .get(rank.saturating_sub(1))
+        .copied()
+        .unwrap_or_default()
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn speed_up(baseline: Duration, duration: Duration) -> f64 {
+    // a single nanosecond is as fast as Instant can tell anyway, and avoids dividing by zero
+    baseline.as_nanos().max(1) as f64 / duration.as_nanos().max(1) as f64
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Day {} - Part {} ({} iterations, {} warm-up)",
+            self.day, self.part, self.options.iterations, self.options.warmup
+        )?;
+        let width = self
+            .timings
+            .iter()
+            .map(|timing| timing.name().len())
+            .max()
+            .unwrap_or(0)
+            .max("variant".len());
+        writeln!(
+            f,
+            "  {:<width$}  {:>12}  {:>12}  {:>9}  answer",
+            "variant",
+            "median",
+            "p95",
+            "speed-up",
+            width = width
+        )?;
+        for timing in &self.timings {
+            writeln!(
+                f,
+                "  {:<width$}  {:>12}  {:>12}  {:>8.2}x  {}",
+                timing.name(),
+                format!("{:.1?}", timing.median),
+                format!("{:.1?}", timing.p95),
+                timing.speed_up,
+                timing.outcome(),
+                width = width
+            )?;
+        }
+        Ok(())
+    }
+}
+
+///
+/// All timings of `reports` as a JSON array, one object per variant, durations in nanoseconds.
+/// Variants without an answer have a `null` answer and an `error`
+/// example
+/// ```
+/// # use advent_of_code_2020::bench::{to_json, Options, Report, Timing};
+/// # use advent_of_code_2020::solver::Part;
+/// # use std::time::Duration;
+/// let report = Report {
+///     day: 6,
+///     part: Part::One,
+///     options: Options { warmup: 1, iterations: 5 },
+///     timings: vec![Timing {
+///         variant: Some("bit"),
+///         answer: Ok("6".into()),
+///         median: Duration::from_nanos(1500),
+///         p95: Duration::from_nanos(2000),
+///         speed_up: 1.25,
+///     }],
+/// };
+///
+/// assert_eq!(
+///     to_json(&[report]),
+///     r#"[{"day":6,"part":1,"variant":"bit","answer":"6","median_ns":1500,"p95_ns":2000,"speed_up":1.25,"iterations":5}]"#
+/// );
+/// ```
+pub fn to_json(reports: &[Report]) -> String {
+    let objects = reports
+        .iter()
+        .flat_map(|report| {
+            report.timings.iter().map(move |timing| {
+                format!(
+                    "{{\"day\":{},\"part\":{},\"variant\":{},{},\"median_ns\":{},\"p95_ns\":{},\"speed_up\":{},\"iterations\":{}}}",
+                    report.day,
+                    report.part,
+                    json_string(timing.name()),
+                    match &timing.answer {
+                        Ok(answer) => format!("\"answer\":{}", json_string(answer)),
+                        Err(error) => format!(
+                            "\"answer\":null,\"error\":{}",
+                            json_string(&error.message)
+                        ),
+                    },
+                    timing.median.as_nanos(),
+                    timing.p95.as_nanos(),
+                    timing.speed_up,
+                    report.options.iterations
+                )
+            })
+        })
+        .collect::<Vec<_>>();
+    format!("[{}]", objects.join(","))
+}
+
+/// `value` as a JSON string, quoted and escaped
+pub(crate) fn json_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for char in value.chars() {
+        match char {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn micros(values: &[u64]) -> Vec<Duration> {
+        values.iter().map(|v| Duration::from_micros(*v)).collect()
+    }
+
+    #[test]
+    fn test_median() {
+        assert_eq!(median(&micros(&[1, 2, 10])), Duration::from_micros(2));
+        assert_eq!(median(&micros(&[1, 2, 4, 10])), Duration::from_micros(3));
+        assert_eq!(median(&[]), Duration::default());
+    }
+
+    #[test]
+    fn test_p95() {
+        let samples = micros(&(1..=20).collect::<Vec<_>>());
+        assert_eq!(percentile(&samples, 95), Duration::from_micros(19));
+        assert_eq!(percentile(&micros(&[7]), 95), Duration::from_micros(7));
+        assert_eq!(percentile(&[], 95), Duration::default());
+    }
+
+    #[test]
+    fn test_speed_up() {
+        let baseline = Duration::from_micros(30);
+        assert!((speed_up(baseline, Duration::from_micros(10)) - 3.0).abs() < f64::EPSILON);
+        assert!((speed_up(baseline, Duration::from_micros(60)) - 0.5).abs() < f64::EPSILON);
+        assert!(speed_up(baseline, Duration::default()).is_finite());
+    }
+
+    #[test]
+    fn test_failed_runs_are_reported() {
+        let registry = crate::solver::registry();
+        let options = Options {
+            warmup: 0,
+            iterations: 1,
+        };
+
+        let report = bench(registry[&8].as_ref(), "jmp +3\nnop +0", Part::One, options).unwrap();
+
+        assert!(report.timings.iter().all(|timing| timing.answer.is_err()));
+        assert!(report
+            .to_string()
+            .contains("failed: jmp +3 at 0 jumps to 3"));
+        assert!(to_json(&[report]).contains(r#""answer":null,"error":"jmp +3 at 0"#));
+    }
+
+    #[test]
+    fn test_json_escapes_answers() {
+        assert_eq!(json_string("a\"b\\c\n"), r#""a\"b\\c\n""#);
+    }
+}
@@ -0,0 +1,800 @@
+use std::any::Any;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use std::{env, fs, process};
+
+use advent_of_code_2020::alloc::CountingAllocator;
+use advent_of_code_2020::answers::{self, Manifest};
+use advent_of_code_2020::bench;
+use advent_of_code_2020::day18_math_homework::evaluate_file;
+use advent_of_code_2020::expr::diagnostic::Diagnostic;
+use advent_of_code_2020::expr::explain::Explanation;
+use advent_of_code_2020::expr::number::Number;
+use advent_of_code_2020::expr::parser::parse;
+use advent_of_code_2020::expr::precedence::OperatorTable;
+use advent_of_code_2020::generate;
+use advent_of_code_2020::input::{self, Client};
+use advent_of_code_2020::profile;
+use advent_of_code_2020::solver::{self, DynSolver, Part};
+use advent_of_code_2020::util;
+use advent_of_code_2020::vm::debugger::{Command, Debugger};
+use advent_of_code_2020::vm::{Exit, Machine, Program};
+use num_bigint::BigInt;
+
+const USAGE: &str = "Usage:
+    aoc2020 run --day <N> [--part <1|2>] [--variant <name>] [--input <path>]
+    aoc2020 run --all [--input-dir <dir>]
+    aoc2020 bench --day <N> [--part <1|2>] [--input <path>] [--warmup <N>] [--iterations <N>] [--json]
+    aoc2020 bench --all [--input-dir <dir>] [--warmup <N>] [--iterations <N>] [--json]
+    aoc2020 profile --day <N> [--part <1|2>] [--input <path>]
+    aoc2020 profile --all [--input-dir <dir>]
+    aoc2020 verify [--day <N>] [--answers <path>] [--input-dir <dir>]
+    aoc2020 input --day <N> [--input-dir <dir>] [--base-url <url>] [--session <token>]
+    aoc2020 input --all [--input-dir <dir>] [--base-url <url>] [--session <token>]
+    aoc2020 debug [--input <path>]
+    aoc2020 coverage [--input <path>] [--json]
+    aoc2020 homework [--input <path>] [--rules <left-to-right|addition-first|arithmetic>] [--number <u64|u128|i128|bigint>] [--explain [--line <N>]]
+    aoc2020 generate --day <N> [--seed <N>] [--output <path>]
+    aoc2020 list";
+
+/// Counts allocations for `profile`, for the other commands it's just the system allocator
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[derive(Debug, Default)]
+struct RunOptions {
+    all: bool,
+    day: Option<u32>,
+    part: Option<u32>,
+    variant: Option<String>,
+    input: Option<PathBuf>,
+    input_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Default)]
+struct InputOptions {
+    all: bool,
+    day: Option<u32>,
+    input_dir: Option<PathBuf>,
+    base_url: Option<String>,
+    session: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct GenerateOptions {
+    day: Option<u32>,
+    seed: Option<u64>,
+    output: Option<PathBuf>,
+}
+
+#[derive(Debug, Default)]
+struct VerifyOptions {
+    day: Option<u32>,
+    answers: Option<PathBuf>,
+    input_dir: Option<PathBuf>,
+}
+
+#[derive(Debug)]
+struct HomeworkOptions {
+    input: PathBuf,
+    table: OperatorTable,
+    number: String,
+    explain: bool,
+    /// Only explain this line
+    line: Option<usize>,
+}
+
+#[derive(Debug, Default)]
+struct BenchOptions {
+    run: RunOptions,
+    bench: bench::Options,
+    json: bool,
+}
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+
+    let result = match args.first().map(String::as_str) {
+        Some("run") => parse_run_options(&args[1..]).and_then(|options| run(&options)),
+        Some("bench") => parse_bench_options(&args[1..]).and_then(|options| bench(&options)),
+        Some("profile") => parse_run_options(&args[1..]).and_then(|options| profile(&options)),
+        Some("verify") => parse_verify_options(&args[1..]).and_then(|options| verify(&options)),
+        Some("input") => parse_input_options(&args[1..]).and_then(|options| fetch(&options)),
+        Some("debug") => parse_debug_options(&args[1..]).and_then(|path| debug(&path)),
+        Some("coverage") => {
+            parse_coverage_options(&args[1..]).and_then(|(path, json)| coverage(&path, json))
+        }
+        Some("homework") => {
+            parse_homework_options(&args[1..]).and_then(|options| homework(&options))
+        }
+        Some("generate") => {
+            parse_generate_options(&args[1..]).and_then(|options| generate(&options))
+        }
+        Some("list") => {
+            list();
+            Ok(())
+        }
+        Some("help") | Some("--help") | Some("-h") => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        Some(other) => Err(format!("Unknown command {:?}", other)),
+        None => Err(String::from("Missing command")),
+    };
+
+    if let Err(msg) = result {
+        eprintln!("{}\n\n{}", msg, USAGE);
+        process::exit(2);
+    }
+}
+
+fn parse_run_options(args: &[String]) -> Result<RunOptions, String> {
+    let mut options = RunOptions::default();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = || {
+            iter.next()
+                .ok_or_else(|| format!("Missing value for {}", arg))
+        };
+        match arg.as_str() {
+            "--all" => options.all = true,
+            "--day" => options.day = Some(parse_number(arg, value()?)?),
+            "--part" => options.part = Some(parse_number(arg, value()?)?),
+            "--variant" => options.variant = Some(value()?.clone()),
+            "--input" => options.input = Some(PathBuf::from(value()?)),
+            "--input-dir" => options.input_dir = Some(PathBuf::from(value()?)),
+            other => return Err(format!("Unknown argument {:?}", other)),
+        }
+    }
+
+    match (options.all, options.day) {
+        (true, Some(_)) => Err(String::from("--all and --day are mutually exclusive")),
+        (false, None) => Err(String::from("Either --day or --all is required")),
+        (true, None) if options.input.is_some() => Err(String::from(
+            "--input only works for a single day, use --input-dir with --all",
+        )),
+        _ => Ok(options),
+    }
+}
+
+/// Takes out the bench specific arguments, the rest are parsed like for `run`
+fn parse_bench_options(args: &[String]) -> Result<BenchOptions, String> {
+    let mut options = BenchOptions::default();
+    let mut run_args = vec![];
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = || {
+            iter.next()
+                .ok_or_else(|| format!("Missing value for {}", arg))
+        };
+        match arg.as_str() {
+            "--warmup" => options.bench.warmup = parse_number(arg, value()?)?,
+            "--iterations" => options.bench.iterations = parse_number(arg, value()?)?,
+            "--json" => options.json = true,
+            "--variant" => return Err(String::from("bench always runs every variant")),
+            _ => run_args.push(arg.clone()),
+        }
+    }
+    if options.bench.iterations == 0 {
+        return Err(String::from("--iterations must be at least 1"));
+    }
+    options.run = parse_run_options(&run_args)?;
+    Ok(options)
+}
+
+fn parse_verify_options(args: &[String]) -> Result<VerifyOptions, String> {
+    let mut options = VerifyOptions::default();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = || {
+            iter.next()
+                .ok_or_else(|| format!("Missing value for {}", arg))
+        };
+        match arg.as_str() {
+            "--day" => options.day = Some(parse_number(arg, value()?)?),
+            "--answers" => options.answers = Some(PathBuf::from(value()?)),
+            "--input-dir" => options.input_dir = Some(PathBuf::from(value()?)),
+            other => return Err(format!("Unknown argument {:?}", other)),
+        }
+    }
+    Ok(options)
+}
+
+fn parse_input_options(args: &[String]) -> Result<InputOptions, String> {
+    let mut options = InputOptions::default();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = || {
+            iter.next()
+                .ok_or_else(|| format!("Missing value for {}", arg))
+        };
+        match arg.as_str() {
+            "--all" => options.all = true,
+            "--day" => options.day = Some(parse_number(arg, value()?)?),
+            "--input-dir" => options.input_dir = Some(PathBuf::from(value()?)),
+            "--base-url" => options.base_url = Some(value()?.clone()),
+            "--session" => options.session = Some(value()?.clone()),
+            other => return Err(format!("Unknown argument {:?}", other)),
+        }
+    }
+    match (options.all, options.day) {
+        (true, Some(_)) => Err(String::from("--all and --day are mutually exclusive")),
+        (false, None) => Err(String::from("Either --day or --all is required")),
+        _ => Ok(options),
+    }
+}
+
+/// The program to debug, day 8's input by default
+fn parse_debug_options(args: &[String]) -> Result<PathBuf, String> {
+    match args {
+        [] => Ok(PathBuf::from("input/2020/day8.txt")),
+        [flag, path] if flag == "--input" => Ok(PathBuf::from(path)),
+        _ => Err(format!("Unknown arguments {:?}", args)),
+    }
+}
+
+/// The program to profile, day 8's input by default, and whether to print JSON
+fn parse_coverage_options(args: &[String]) -> Result<(PathBuf, bool), String> {
+    let json = args.iter().any(|arg| arg == "--json");
+    let rest = args
+        .iter()
+        .filter(|arg| *arg != "--json")
+        .cloned()
+        .collect::<Vec<_>>();
+    parse_debug_options(&rest).map(|path| (path, json))
+}
+
+fn parse_homework_options(args: &[String]) -> Result<HomeworkOptions, String> {
+    let mut options = HomeworkOptions {
+        input: PathBuf::from("input/2020/day18.txt"),
+        table: OperatorTable::left_to_right(),
+        number: String::from("u128"),
+        explain: false,
+        line: None,
+    };
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = || {
+            iter.next()
+                .ok_or_else(|| format!("Missing value for {}", arg))
+        };
+        match arg.as_str() {
+            "--input" => options.input = PathBuf::from(value()?),
+            "--rules" => {
+                options.table = match value()?.as_str() {
+                    "left-to-right" => OperatorTable::left_to_right(),
+                    "addition-first" => OperatorTable::addition_first(),
+                    "arithmetic" => OperatorTable::arithmetic(),
+                    other => return Err(format!("Unknown rules {:?}", other)),
+                }
+            }
+            "--number" => options.number = value()?.clone(),
+            "--explain" => options.explain = true,
+            "--line" => options.line = Some(parse_number(arg, value()?)?),
+            other => return Err(format!("Unknown argument {:?}", other)),
+        }
+    }
+    if options.line.is_some() && !options.explain {
+        return Err(String::from("--line only works with --explain"));
+    }
+    Ok(options)
+}
+
+fn parse_generate_options(args: &[String]) -> Result<GenerateOptions, String> {
+    let mut options = GenerateOptions::default();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = || {
+            iter.next()
+                .ok_or_else(|| format!("Missing value for {}", arg))
+        };
+        match arg.as_str() {
+            "--day" => options.day = Some(parse_number(arg, value()?)?),
+            "--seed" => options.seed = Some(parse_number(arg, value()?)?),
+            "--output" => options.output = Some(PathBuf::from(value()?)),
+            other => return Err(format!("Unknown argument {:?}", other)),
+        }
+    }
+    if options.day.is_none() {
+        return Err(String::from("--day is required"));
+    }
+    Ok(options)
+}
+
+fn parse_number<T: FromStr>(arg: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{} expects a number, got {:?}", arg, value))
+}
+
+fn parse_part(options: &RunOptions) -> Result<Option<Part>, String> {
+    match options.part {
+        None => Ok(None),
+        Some(number) => Part::from_number(number)
+            .map(Some)
+            .ok_or_else(|| format!("There's no part {}", number)),
+    }
+}
+
+fn input_path(options: &RunOptions, day: u32) -> PathBuf {
+    options.input.clone().unwrap_or_else(|| {
+        options
+            .input_dir
+            .clone()
+            .unwrap_or_else(|| PathBuf::from("input/2020"))
+            .join(format!("day{}.txt", day))
+    })
+}
+
+fn run(options: &RunOptions) -> Result<(), String> {
+    let part = parse_part(options)?;
+    let registry = solver::registry();
+    // every day with the parts and variants of it to run, so its input is only parsed once
+    let selected = registry
+        .values()
+        .filter(|solver| options.day.is_none_or(|day| day == solver.day()))
+        .map(|solver| {
+            let runs = Part::BOTH
+                .iter()
+                .filter(|p| part.is_none_or(|part| part == **p))
+                .flat_map(|part| {
+                    std::iter::once(None)
+                        .chain(solver.variant_names(*part).into_iter().map(Some))
+                        .map(move |variant| (*part, variant))
+                })
+                .filter(|(_, variant)| match (&options.variant, variant) {
+                    (None, _) => true,
+                    (Some(wanted), Some(variant)) => wanted.eq_ignore_ascii_case(variant),
+                    (Some(_), None) => false,
+                })
+                .collect::<Vec<_>>();
+            (solver, runs)
+        })
+        .filter(|(_, runs)| !runs.is_empty())
+        .collect::<Vec<_>>();
+
+    if selected.is_empty() {
+        return Err(String::from(
+            "No solution matches the given day, part and variant",
+        ));
+    }
+
+    let mut failed = false;
+    for (solver, runs) in selected {
+        let day = solver.day();
+        let input = read_input(&input_path(options, day))?;
+        let start_time = Instant::now();
+        let parsed = match solver.parse_boxed(&input) {
+            Ok(parsed) => parsed,
+            Err(error) => {
+                eprintln!("Day {}: FAILED while parsing:\n\t{}\n", day, error);
+                failed = true;
+                continue;
+            }
+        };
+        let parse_time = start_time.elapsed();
+
+        for (part, variant) in runs {
+            let title = match variant {
+                Some(name) => format!("Day {} - Part {} - {}", day, part, name),
+                None => format!("Day {} - Part {}", day, part),
+            };
+
+            let solved = run_solution(
+                solver.as_ref(),
+                parsed.as_ref(),
+                parse_time,
+                part,
+                variant,
+                &title,
+            );
+            if let Err(error) = solved {
+                eprintln!("{}: {}\n", title, error);
+                failed = true;
+            }
+        }
+    }
+
+    if failed {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+/// Solves one part of a day from its already parsed input, `parse_time` is shown as the
+/// generator time of every part
+fn run_solution(
+    solver: &dyn DynSolver,
+    parsed: &dyn Any,
+    parse_time: Duration,
+    part: Part,
+    variant: Option<&str>,
+    title: &str,
+) -> Result<(), String> {
+    let start_time = Instant::now();
+    match solver.solve(parsed, part, variant) {
+        Some(Ok(result)) => {
+            println!(
+                "{}: {}\n\tgenerator: {:?},\n\trunner: {:?}\n",
+                title,
+                result,
+                parse_time,
+                start_time.elapsed()
+            );
+        }
+        Some(Err(error)) => return Err(format!("FAILED:\n\t{}", error)),
+        None => println!("{}: not solved yet\n", title),
+    }
+    Ok(())
+}
+
+fn bench(options: &BenchOptions) -> Result<(), String> {
+    let part = parse_part(&options.run)?;
+    let registry = solver::registry();
+    let solvers = registry
+        .values()
+        .filter(|solver| options.run.day.is_none_or(|day| day == solver.day()))
+        .collect::<Vec<_>>();
+    if solvers.is_empty() {
+        return Err(String::from("No solution matches the given day"));
+    }
+
+    let mut reports = vec![];
+    let mut failed = false;
+    for solver in solvers {
+        let input = read_input(&input_path(&options.run, solver.day()))?;
+        for part in Part::BOTH
+            .iter()
+            .filter(|p| part.is_none_or(|part| part == **p))
+        {
+            match bench::bench(solver.as_ref(), &input, *part, options.bench) {
+                Ok(report) => {
+                    if !options.json {
+                        println!("{}", report);
+                    }
+                    failed |= report.timings.iter().any(|timing| timing.answer.is_err());
+                    reports.push(report);
+                }
+                Err(error) => {
+                    eprintln!(
+                        "Day {} - Part {}: FAILED while parsing:\n\t{}\n",
+                        solver.day(),
+                        part,
+                        error
+                    );
+                    failed = true;
+                }
+            }
+        }
+    }
+
+    if options.json {
+        println!("{}", bench::to_json(&reports));
+    }
+    if failed {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+/// Prints parse and solve times and allocations, like `bench` it runs every variant
+fn profile(options: &RunOptions) -> Result<(), String> {
+    if options.variant.is_some() {
+        return Err(String::from("profile always runs every variant"));
+    }
+    let part = parse_part(options)?;
+    let registry = solver::registry();
+    let solvers = registry
+        .values()
+        .filter(|solver| options.day.is_none_or(|day| day == solver.day()))
+        .collect::<Vec<_>>();
+    if solvers.is_empty() {
+        return Err(String::from("No solution matches the given day"));
+    }
+
+    let mut failed = false;
+    for solver in solvers {
+        let input = read_input(&input_path(options, solver.day()))?;
+        match profile::profile(solver.as_ref(), &input, part) {
+            Ok(profile) => {
+                println!("{}", profile);
+                failed |= profile.runs.iter().any(|run| run.answer.is_err());
+            }
+            Err(error) => {
+                eprintln!("Day {}: FAILED while parsing:\n\t{}\n", solver.day(), error);
+                failed = true;
+            }
+        }
+    }
+
+    if failed {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+/// Prints pass/fail/missing for every solution and variant, exits with 1 if any answer is wrong
+fn verify(options: &VerifyOptions) -> Result<(), String> {
+    let manifest = match &options.answers {
+        Some(path) => util::read_input(path)
+            .map_err(|e| format!("Couldn't read answers {:?}: {}", path, e))?
+            .parse()
+            .map_err(|e| format!("Invalid answers {:?}: {}", path, e))?,
+        None => Manifest::bundled(),
+    };
+    let registry = solver::registry();
+    let input_dir = options
+        .input_dir
+        .clone()
+        .unwrap_or_else(|| PathBuf::from("input/2020"));
+
+    let outcomes = answers::verify(&registry, &manifest, options.day, |day| {
+        input_dir.join(format!("day{}.txt", day))
+    });
+    if outcomes.is_empty() {
+        return Err(String::from("No solution or answer matches the given day"));
+    }
+
+    for outcome in &outcomes {
+        println!("{}", outcome);
+    }
+    let count = |label| {
+        outcomes
+            .iter()
+            .filter(|outcome| outcome.status.label() == label)
+            .count()
+    };
+    println!(
+        "\n{} passed, {} failed, {} missing",
+        count("PASS"),
+        count("FAIL"),
+        count("MISSING")
+    );
+
+    if outcomes.iter().any(|outcome| outcome.status.is_failure()) {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+/// Downloads missing inputs, `--all` fetches every day that has a solution
+fn fetch(options: &InputOptions) -> Result<(), String> {
+    let client = match &options.session {
+        Some(session) => Client::new(session.as_str()),
+        None => Client::from_env().ok_or_else(|| {
+            format!(
+                "A session token is required, either --session or {}",
+                input::SESSION_VARIABLE
+            )
+        })?,
+    };
+    let client = match &options.base_url {
+        Some(base_url) => client.base_url(base_url.as_str()),
+        None => client,
+    };
+    let mut client = match &options.input_dir {
+        Some(input_dir) => client.cache_dir(input_dir),
+        None => client,
+    };
+
+    let days = match options.day {
+        Some(day) => vec![day],
+        None => solver::registry().keys().copied().collect(),
+    };
+    let mut failed = false;
+    for day in days {
+        match client.fetch(day) {
+            Ok(path) => println!("day {:>2}: {}", day, path.display()),
+            Err(error) => {
+                eprintln!("day {:>2}: FAILED: {}", day, error);
+                failed = true;
+            }
+        }
+    }
+
+    if failed {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+/// Reads debugger commands from stdin until `quit` or the end of input.
+/// An empty line repeats the previous command
+fn debug(path: &Path) -> Result<(), String> {
+    let program = read_program(path);
+    println!(
+        "Loaded {} instructions from {:?}, type help for a list of commands",
+        program.len(),
+        path
+    );
+    let mut debugger = Debugger::new(program);
+
+    let mut previous: Option<Command> = None;
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("(dbg) ");
+        io::stdout().flush().map_err(|e| e.to_string())?;
+        let line = match lines.next() {
+            Some(line) => line.map_err(|e| e.to_string())?,
+            None => return Ok(()),
+        };
+
+        let command = match (line.trim().is_empty(), &previous) {
+            (true, Some(previous)) => previous.clone(),
+            (true, None) => continue,
+            (false, _) => match line.parse::<Command>() {
+                Ok(command) => command,
+                Err(error) => {
+                    println!("{}", error);
+                    continue;
+                }
+            },
+        };
+        if command == Command::Quit {
+            return Ok(());
+        }
+        println!("{}", debugger.execute(&command));
+        previous = Some(command);
+    }
+}
+
+/// Runs a day 8 program until it terminates, loops or faults, and shows what it executed
+fn coverage(path: &Path, json: bool) -> Result<(), String> {
+    let program = read_program(path);
+    let mut machine = Machine::new(program).profiled();
+    let stopped = match machine.run() {
+        Ok(Exit::Terminated) => String::from("terminated"),
+        Ok(Exit::Halted) => String::from("halted"),
+        Ok(Exit::Loop(cycle)) => format!("about to loop back to {}", cycle.entry()),
+        Ok(Exit::OutOfSteps) => String::from("out of steps"),
+        Err(fault) => format!("fault: {}", fault),
+    };
+    let coverage = machine.coverage().expect("the machine is profiled");
+    if json {
+        println!("{}", coverage.to_json(machine.code()));
+        return Ok(());
+    }
+
+    println!("{}", coverage.annotate(machine.code()));
+    println!(
+        "\nStopped after {} steps, {}, acc = {}",
+        machine.steps(),
+        stopped,
+        machine.acc()
+    );
+    println!(
+        "{} of {} instructions never executed",
+        coverage.dead().count(),
+        machine.code().len()
+    );
+    println!("Hottest jumps:");
+    for edge in coverage.hottest_jumps(5) {
+        println!("{:>8} -> {:<8} {} times", edge.from, edge.to, edge.count);
+    }
+    Ok(())
+}
+
+/// Evaluates every line of a day 18 homework file and prints the sum, or how every line is
+/// evaluated with `--explain`. What's wrong with every line that doesn't parse or has no value
+/// goes to stderr
+fn homework(options: &HomeworkOptions) -> Result<(), String> {
+    let input = read_input(&options.input)?;
+    let lines = input.lines().count();
+    if let Some(line) = options.line.filter(|line| !(1..=lines).contains(line)) {
+        return Err(format!("{:?} has no line {}", options.input, line));
+    }
+    let (output, diagnostics) = match options.number.as_str() {
+        "u64" => homework_in::<u64>(&input, options),
+        "u128" => homework_in::<u128>(&input, options),
+        "i128" => homework_in::<i128>(&input, options),
+        "bigint" => homework_in::<BigInt>(&input, options),
+        other => return Err(format!("Unknown number type {:?}", other)),
+    };
+    if !output.is_empty() {
+        println!("{}", output);
+    }
+    if !diagnostics.is_empty() {
+        for diagnostic in &diagnostics {
+            eprintln!("{}\n", diagnostic);
+        }
+        eprintln!("{} of {} lines have errors", diagnostics.len(), lines);
+        process::exit(1);
+    }
+    Ok(())
+}
+
+/// The sum or the explanations, in `N`, and the errors
+fn homework_in<N: Number>(input: &str, options: &HomeworkOptions) -> (String, Vec<Diagnostic>) {
+    let table = &options.table;
+    if !options.explain {
+        return match evaluate_file::<N>(input, table) {
+            Ok(sum) => (sum.to_string(), vec![]),
+            Err(diagnostics) => (String::new(), diagnostics),
+        };
+    }
+
+    let mut explanations = vec![];
+    let mut diagnostics = vec![];
+    for (index, line) in input.lines().enumerate() {
+        let number = index + 1;
+        if options.line.is_some_and(|wanted| wanted != number) {
+            continue;
+        }
+        let explanation = parse(line, table)
+            .map_err(|error| Diagnostic::parse(&error.at_line(number), line))
+            .and_then(|expr| {
+                Explanation::new::<N>(&expr, table)
+                    .map_err(|error| Diagnostic::eval(&error.at_line(number), line))
+            });
+        match explanation {
+            Ok(explanation) => explanations.push(format!("line {}\n{}", number, explanation)),
+            Err(diagnostic) => diagnostics.push(diagnostic),
+        }
+    }
+    (explanations.join("\n\n"), diagnostics)
+}
+
+/// Prints the generated input, or writes it to `--output`. The seed and answers go to stderr
+/// so that the input can be piped somewhere
+fn generate(options: &GenerateOptions) -> Result<(), String> {
+    let day = options.day.unwrap_or_default();
+    let seed = options.seed.unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs())
+    });
+    let puzzle = generate::generate(day, seed).ok_or_else(|| {
+        format!(
+            "There's no generator for day {}, only for days {:?}",
+            day,
+            generate::DAYS
+        )
+    })?;
+
+    match &options.output {
+        Some(path) => fs::write(path, &puzzle.input)
+            .map_err(|e| format!("Couldn't write {:?}: {}", path, e))?,
+        None => println!("{}", puzzle.input),
+    }
+    eprintln!(
+        "day {} seed {}
+part 1: {}
+part 2: {}",
+        puzzle.day, puzzle.seed, puzzle.part1, puzzle.part2
+    );
+    Ok(())
+}
+
+/// Reads a day 8 program, exiting if it doesn't parse
+fn read_program(path: &Path) -> Program {
+    let parsed = read_input(path).map(|input| input.parse::<Program>());
+    match parsed {
+        Ok(Ok(program)) => program,
+        Ok(Err(error)) => {
+            eprintln!("{:?} isn't a valid program:\n\t{}", path, error);
+            process::exit(1);
+        }
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(1);
+        }
+    }
+}
+
+fn read_input(path: &Path) -> Result<String, String> {
+    util::read_input(path).map_err(|e| format!("Couldn't read input {:?}: {}", path, e))
+}
+
+fn list() {
+    for solver in solver::registry().values() {
+        for part in &Part::BOTH {
+            println!("day {:>2} part {}", solver.day(), part);
+            for variant in solver.variant_names(*part) {
+                println!("day {:>2} part {} {}", solver.day(), part, variant);
+            }
+        }
+    }
+}
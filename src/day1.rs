@@ -1,10 +1,13 @@
+use crate::error::ParseError;
+use crate::solver::{Part, Solution, Solver, Variant};
+use crate::util::{parse_lines, try_parse};
 use itertools::Itertools;
 use std::collections::HashSet;
 use std::iter::FromIterator;
 
 #[aoc_generator(day1)]
-fn lines_of_ints_to_int_array(input: &str) -> Vec<i32> {
-    input.lines().map(|line| line.parse().unwrap()).collect()
+fn lines_of_ints_to_int_array(input: &str) -> Result<Vec<i32>, ParseError> {
+    parse_lines(input, try_parse)
 }
 
 #[aoc(day1, part1)]
@@ -13,7 +16,7 @@ fn part1(input: &[i32]) -> i32 {
         .iter()
         .permutations(2)
         // map from vector of 2 elements to a tuple2
-        .map(|vec: Vec<&i32>| (**vec.get(0).unwrap(), **vec.get(1).unwrap()))
+        .map(|vec: Vec<&i32>| (**vec.first().unwrap(), **vec.get(1).unwrap()))
         .find_map(|(left, right)| {
             if left + right == 2020 {
                 Some(left * right)
@@ -45,7 +48,7 @@ fn part2(input: &[i32]) -> i32 {
         // map from vector of 3 elements to a tuple3
         .map(|vec| {
             (
-                **vec.get(0).unwrap(),
+                **vec.first().unwrap(),
                 **vec.get(1).unwrap(),
                 **vec.get(2).unwrap(),
             )
@@ -62,37 +65,63 @@ fn part2(input: &[i32]) -> i32 {
         .expect("should be a number here")
 }
 
+pub struct Day1;
+
+impl Solver for Day1 {
+    type Input = Vec<i32>;
+    const DAY: u32 = 1;
+
+    fn parse(&self, input: &str) -> Result<Vec<i32>, ParseError> {
+        lines_of_ints_to_int_array(input)
+    }
+
+    fn part1(&self, input: &Vec<i32>) -> Solution {
+        Ok(part1(input).to_string())
+    }
+
+    fn part2(&self, input: &Vec<i32>) -> Option<Solution> {
+        Some(Ok(part2(input).to_string()))
+    }
+
+    fn variants(&self) -> Vec<Variant<Vec<i32>>> {
+        vec![Variant::new(Part::One, "Set", |input| {
+            Ok(part1_set(input).to_string())
+        })]
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::answers::expected;
 
     #[test]
     fn test_part1() {
         let input = include_str!("../input/2020/day1.txt");
-        let input = lines_of_ints_to_int_array(input);
+        let input = lines_of_ints_to_int_array(input).unwrap();
 
         let result = part1(input.as_slice());
 
-        assert_eq!(result, 485739);
+        assert_eq!(result.to_string(), expected(1, Part::One));
     }
 
     #[test]
     fn test_part1_set() {
         let input = include_str!("../input/2020/day1.txt");
-        let input = lines_of_ints_to_int_array(input);
+        let input = lines_of_ints_to_int_array(input).unwrap();
 
         let result = part1_set(input.as_slice());
 
-        assert_eq!(result, 485739);
+        assert_eq!(result.to_string(), expected(1, Part::One));
     }
 
     #[test]
     fn test_part2() {
         let input = include_str!("../input/2020/day1.txt");
-        let input = lines_of_ints_to_int_array(input);
+        let input = lines_of_ints_to_int_array(input).unwrap();
 
         let result = part2(input.as_slice());
 
-        assert_eq!(result, 161109702);
+        assert_eq!(result.to_string(), expected(1, Part::Two));
     }
 }
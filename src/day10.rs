@@ -1,9 +1,15 @@
+use crate::error::ParseError;
+use crate::solver::{Solution, Solver};
+use crate::util::{parse_lines, try_parse};
+
+#[aoc_generator(day10)]
+fn parse(input: &str) -> Result<Vec<u32>, ParseError> {
+    parse_lines(input, try_parse)
+}
+
 #[aoc(day10, part1)]
-fn part1(input: &str) -> u32 {
-    let mut input = input
-        .lines()
-        .map(|line| line.parse::<u32>().unwrap())
-        .collect::<Vec<_>>();
+fn part1(input: &[u32]) -> u32 {
+    let mut input = input.to_vec();
     let my_device_voltage = input.iter().max().unwrap() + 3;
     let (one_differences, three_differences) =
         calculate_differences(&mut input, my_device_voltage).unwrap();
@@ -11,12 +17,8 @@ fn part1(input: &str) -> u32 {
 }
 
 #[aoc(day10, part2)]
-fn part2(input: &str) -> u64 {
-    let input = input
-        .lines()
-        .map(|line| line.parse::<u32>().unwrap())
-        .collect::<Vec<_>>();
-    solve_part2(input)
+fn part2(input: &[u32]) -> u64 {
+    solve_part2(input.to_vec())
 }
 
 fn solve_part2(mut numbers: Vec<u32>) -> u64 {
@@ -53,7 +55,7 @@ fn solve_part2(mut numbers: Vec<u32>) -> u64 {
                 }
             }
         }
-        paths[i] = current_paths as u64;
+        paths[i] = current_paths;
     }
 
     *paths.last().unwrap()
@@ -109,19 +111,46 @@ fn calculate_differences(input: &mut [u32], my_device_voltage: u32) -> Option<(u
     Some((one_differences, three_differences))
 }
 
+pub struct Day10;
+
+impl Solver for Day10 {
+    type Input = Vec<u32>;
+    const DAY: u32 = 10;
+
+    fn parse(&self, input: &str) -> Result<Vec<u32>, ParseError> {
+        parse(input)
+    }
+
+    fn part1(&self, input: &Vec<u32>) -> Solution {
+        Ok(part1(input).to_string())
+    }
+
+    fn part2(&self, input: &Vec<u32>) -> Option<Solution> {
+        Some(Ok(part2(input).to_string()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::answers::expected;
+    use crate::solver::Part;
 
     #[test]
     fn verify_part1() {
         let input = include_str!("../input/2020/day10.txt");
-        assert_eq!(part1(input), 1953);
+        assert_eq!(
+            part1(&parse(input).unwrap()).to_string(),
+            expected(10, Part::One)
+        );
     }
 
     #[test]
     fn verify_part2() {
         let input = include_str!("../input/2020/day10.txt");
-        assert_eq!(part2(input), 3543369523456);
+        assert_eq!(
+            part2(&parse(input).unwrap()).to_string(),
+            expected(10, Part::Two)
+        );
     }
 }
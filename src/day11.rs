@@ -1,19 +1,23 @@
-use std::collections::HashMap;
-use std::convert::TryFrom;
+use crate::error::ParseError;
+use crate::grid::{Cell, Grid, Position, DIRECTIONS8};
+use crate::solver::{Solution, Solver};
+
+#[aoc_generator(day11)]
+fn parse(input: &str) -> Result<Seats, ParseError> {
+    input.parse()
+}
 
 #[aoc(day11, part1)]
-fn part1(input: &str) -> usize {
-    let input = parse(input);
-    part1_calc_final_occupied_seats(input)
+fn part1(seats: &Seats) -> usize {
+    part1_calc_final_occupied_seats(seats.clone())
 }
 
 #[aoc(day11, part2)]
-fn part2(input: &str) -> usize {
-    let input = parse(input);
-    part2_calc_final_occupied_seats(input)
+fn part2(seats: &Seats) -> usize {
+    part2_calc_final_occupied_seats(seats.clone())
 }
 
-fn part1_calc_final_occupied_seats(seats: HashMap<(i32, i32), Seat>) -> usize {
+fn part1_calc_final_occupied_seats(seats: Seats) -> usize {
     let mut last_gen_seats = seats;
 
     loop {
@@ -22,9 +26,8 @@ fn part1_calc_final_occupied_seats(seats: HashMap<(i32, i32), Seat>) -> usize {
         if new_generation == last_gen_seats {
             //done
             return new_generation
-                .values()
-                .into_iter()
-                .filter(|seat| matches!(seat, Seat::Occupied))
+                .iter()
+                .filter(|(_, seat)| **seat == Seat::Occupied)
                 .count();
         }
         last_gen_seats = new_generation;
@@ -32,165 +35,75 @@ fn part1_calc_final_occupied_seats(seats: HashMap<(i32, i32), Seat>) -> usize {
 }
 
 fn calculate_next_generation(
-    seats: &HashMap<(i32, i32), Seat>,
+    seats: &Seats,
     seats_required_to_make_empty: usize,
-    fn_adjacent_seats: &dyn Fn(&HashMap<(i32, i32), Seat>, i32, i32) -> usize,
-) -> HashMap<(i32, i32), Seat> {
-    let mut next_generation = HashMap::new();
-    for ((row, column), seat) in seats {
-        let num_adjacent_seats = fn_adjacent_seats(seats, *row, *column);
+    fn_adjacent_seats: &dyn Fn(&Seats, Position) -> usize,
+) -> Seats {
+    seats.map(|position, seat| {
+        let num_adjacent_seats = fn_adjacent_seats(seats, position);
 
-        let new_seat_status = if *seat == Seat::Empty && num_adjacent_seats == 0 {
+        if *seat == Seat::Empty && num_adjacent_seats == 0 {
             Seat::Occupied
         } else if *seat == Seat::Occupied && num_adjacent_seats >= seats_required_to_make_empty {
             Seat::Empty
         } else {
             *seat
-        };
-        next_generation.insert((*row, *column), new_seat_status);
-    }
-    next_generation
+        }
+    })
 }
 
+type Seats = Grid<Seat>;
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
-enum Seat {
+pub enum Seat {
     Empty,
     Occupied,
     Floor,
 }
 
-fn parse(input: &str) -> HashMap<(i32, i32), Seat> {
-    let mut seats = HashMap::new();
-    for (row, line) in input.lines().enumerate() {
-        for (column, char) in line.chars().enumerate() {
-            let seat = match char {
-                'L' => Seat::Empty,
-                '#' => Seat::Occupied,
-                '.' => Seat::Floor,
-                other => panic!("Unexpcted char: {:?}", other),
-            };
-            seats.insert(
-                (i32::try_from(row).unwrap(), i32::try_from(column).unwrap()),
-                seat,
-            );
+impl Cell for Seat {
+    const EXPECTED: &'static str = "one of 'L', '#' or '.'";
+
+    fn from_char(char: char) -> Option<Self> {
+        match char {
+            'L' => Some(Self::Empty),
+            '#' => Some(Self::Occupied),
+            '.' => Some(Self::Floor),
+            _ => None,
         }
     }
-    seats
-}
-
-fn calculate_num_occupied_adjacent_seats(
-    seats: &HashMap<(i32, i32), Seat>,
-    row: i32,
-    column: i32,
-) -> usize {
-    let adjacents_offsets = vec![
-        (row - 1, column - 1),
-        (row - 1, column),
-        (row - 1, column + 1),
-        (row, column - 1),
-        (row, column + 1),
-        (row + 1, column - 1),
-        (row + 1, column),
-        (row + 1, column + 1),
-    ];
-
-    adjacents_offsets
-        .into_iter()
-        .filter(|(row, column)| {
-            if let Some(seat) = seats.get(&(*row, *column)) {
-                if let Seat::Occupied = seat {
-                    return true;
-                }
-            }
-            false
-        })
-        .count()
-}
 
-fn calculate_num_occupied_adjacent_seats_part2(
-    seats: &HashMap<(i32, i32), Seat>,
-    row: i32,
-    column: i32,
-) -> usize {
-    let adjacents_offsets = vec![
-        (-1, -1),
-        (-1, 0),
-        (-1, 1),
-        (0, -1),
-        (0, 1),
-        (1, -1),
-        (1, 0),
-        (1, 1),
-    ];
-
-    let mut occupied_seats_seen = 0;
-
-    'direction: for (row_offset, column_offset) in adjacents_offsets {
-        let mut r = row;
-        let mut c = column;
-        loop {
-            r += row_offset;
-            c += column_offset;
-
-            let maybe_seat = seats.get(&(r, c));
-            match maybe_seat {
-                Some(Seat::Occupied) => {
-                    // We saw a seat
-                    occupied_seats_seen += 1;
-                    continue 'direction;
-                }
-                Some(Seat::Empty) | None => {
-                    // We saw something that wasn't an occupied seat or we reached Out of bounds
-                    continue 'direction;
-                }
-                _ => {
-                    // we saw something that wasn't an occupied seat
-                }
-            }
+    fn to_char(&self) -> char {
+        match self {
+            Self::Empty => 'L',
+            Self::Occupied => '#',
+            Self::Floor => '.',
         }
     }
-    occupied_seats_seen
 }
 
-#[allow(dead_code)]
-fn print(seats: &HashMap<(i32, i32), Seat>) {
-    let mut row = 0;
-    let mut column;
-    // loop rows
-    loop {
-        column = 0;
-        // loop columns
-        'column: loop {
-            let cell = seats.get(&(row, column));
-            match cell {
-                None => {
-                    if column == 0 {
-                        // we just switched row so we're done
-                        return;
-                    } else {
-                        // we're probably just at the end of the column
-                        row += 1;
-                        println!();
-                        break 'column;
-                    }
-                }
-                Some(seat) => {
-                    print!(
-                        "{} ",
-                        match seat {
-                            Seat::Empty => 'L',
-                            Seat::Occupied => '#',
-                            Seat::Floor => '.',
-                        }
-                    )
-                }
-            }
-            column += 1;
-        }
-    }
+fn calculate_num_occupied_adjacent_seats(seats: &Seats, position: Position) -> usize {
+    seats
+        .neighbours8(position)
+        .filter(|neighbour| seats[*neighbour] == Seat::Occupied)
+        .count()
+}
+
+fn calculate_num_occupied_adjacent_seats_part2(seats: &Seats, position: Position) -> usize {
+    DIRECTIONS8
+        .iter()
+        .filter(|direction| {
+            // the first thing that isn't floor is the seat we'll see
+            let first_seat = seats
+                .ray(position, **direction)
+                .map(|seen| seats[seen])
+                .find(|seen| *seen != Seat::Floor);
+            first_seat == Some(Seat::Occupied)
+        })
+        .count()
 }
 
-fn part2_calc_final_occupied_seats(seats: HashMap<(i32, i32), Seat>) -> usize {
+fn part2_calc_final_occupied_seats(seats: Seats) -> usize {
     let mut last_gen_seats = seats;
 
     loop {
@@ -202,29 +115,55 @@ fn part2_calc_final_occupied_seats(seats: HashMap<(i32, i32), Seat>) -> usize {
         if new_generation == last_gen_seats {
             //done
             return new_generation
-                .values()
-                .into_iter()
-                .filter(|seat| matches!(seat, Seat::Occupied))
+                .iter()
+                .filter(|(_, seat)| **seat == Seat::Occupied)
                 .count();
         }
         last_gen_seats = new_generation;
     }
 }
 
+pub struct Day11;
+
+impl Solver for Day11 {
+    type Input = Seats;
+    const DAY: u32 = 11;
+
+    fn parse(&self, input: &str) -> Result<Seats, ParseError> {
+        parse(input)
+    }
+
+    fn part1(&self, input: &Seats) -> Solution {
+        Ok(part1(input).to_string())
+    }
+
+    fn part2(&self, input: &Seats) -> Option<Solution> {
+        Some(Ok(part2(input).to_string()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::answers::expected;
+    use crate::solver::Part;
 
     #[test]
     fn verify_part1() {
         let input = include_str!("../input/2020/day11.txt");
-        assert_eq!(part1(input), 2441);
+        assert_eq!(
+            part1(&parse(input).unwrap()).to_string(),
+            expected(11, Part::One)
+        );
     }
 
     #[test]
     fn verify_part2() {
         let input = include_str!("../input/2020/day11.txt");
-        assert_eq!(part2(input), 2190);
+        assert_eq!(
+            part2(&parse(input).unwrap()).to_string(),
+            expected(11, Part::Two)
+        );
     }
 
     #[test]
@@ -240,7 +179,7 @@ LLLLLLLLLL
 L.LLLLLL.L
 L.LLLLL.LL";
 
-        let parsed = parse(input);
+        let parsed = parse(input).unwrap();
 
         let occupied_seats = part1_calc_final_occupied_seats(parsed);
 
@@ -260,10 +199,25 @@ LLLLLLLLLL
 L.LLLLLL.L
 L.LLLLL.LL";
 
-        let parsed = parse(input);
+        let parsed = parse(input).unwrap();
 
         let occupied_seats = part2_calc_final_occupied_seats(parsed);
 
         assert_eq!(occupied_seats, 26)
     }
+
+    #[test]
+    fn test_parse_error() {
+        let error = parse("L.L\nL.X").unwrap_err();
+        assert_eq!((error.line, error.column), (Some(2), Some(3)));
+    }
+
+    #[test]
+    fn test_first_generation_fills_every_seat() {
+        let seats = parse("L.L\nLLL").unwrap();
+
+        let next = calculate_next_generation(&seats, 4, &calculate_num_occupied_adjacent_seats);
+
+        assert_eq!(next.to_string(), "#.#\n###");
+    }
 }
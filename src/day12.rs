@@ -1,20 +1,47 @@
+use crate::error::ParseError;
+use crate::solver::{Solution, Solver};
+use crate::util::{parse_lines, try_parse};
+
+type Instructions = Vec<(Direction, i32)>;
+
+#[aoc_generator(day12)]
+fn parse(input: &str) -> Result<Instructions, ParseError> {
+    parse_lines(input, |line| {
+        let split = line.chars().next().map_or(0, char::len_utf8);
+        let (direction, amount) = line.split_at(split);
+        let direction = match direction {
+            "N" => Direction::North,
+            "S" => Direction::South,
+            "E" => Direction::East,
+            "W" => Direction::West,
+            "L" => Direction::Left,
+            "R" => Direction::Right,
+            "F" => Direction::Forward,
+            other => {
+                return Err(ParseError::new("one of N, S, E, W, L, R or F", other).at_column(1))
+            }
+        };
+        let amount = try_parse(amount).map_err(|e| e.shifted(split))?;
+        Ok((direction, amount))
+    })
+}
+
 #[aoc(day12, part1)]
-fn part1(input: &str) -> i32 {
-    part1_manhattan_distance(input).0
+fn part1(instructions: &[(Direction, i32)]) -> i32 {
+    part1_manhattan_distance(instructions).0
 }
 
 #[aoc(day12, part2)]
-fn part2(input: &str) -> i32 {
-    part2_manhattan_distance(input).distance
+fn part2(instructions: &[(Direction, i32)]) -> i32 {
+    part2_manhattan_distance(instructions).distance
 }
 
-fn part1_manhattan_distance(input: &str) -> (i32, Direction) {
-    let parsed = parse(input);
+fn part1_manhattan_distance(instructions: &[(Direction, i32)]) -> (i32, Direction) {
     let mut facing = Direction::East;
     let mut x = 0; // west <-> east
     let mut y = 0; // north <-> south
 
-    for (direction, amount) in parsed {
+    for &(direction, amount) in instructions {
         #[allow(clippy::match_same_arms)]
         match (direction, facing, amount) {
             (Direction::North, _, amount) => y -= amount,
@@ -62,7 +89,7 @@ fn turn(direction: Direction, degrees: i32) -> Direction {
 }
 
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
-enum Direction {
+pub enum Direction {
     North,
     East,
     South,
@@ -71,28 +98,6 @@ enum Direction {
     Right,
     Forward,
 }
-fn parse(input: &str) -> Vec<(Direction, i32)> {
-    input
-        .lines()
-        .map(|line| {
-            let (direction, amount) = line.split_at(1);
-            let direction = match direction {
-                "N" => Direction::North,
-                "S" => Direction::South,
-                "E" => Direction::East,
-                "W" => Direction::West,
-                "L" => Direction::Left,
-                "R" => Direction::Right,
-                "F" => Direction::Forward,
-                other => panic!("Unexpected Direction {:?}", other),
-            };
-            let amount = amount
-                .parse()
-                .unwrap_or_else(|_| panic!("Couldn't parse {:?}", amount));
-            (direction, amount)
-        })
-        .collect()
-}
 
 #[allow(dead_code)]
 struct PartTwoResponse {
@@ -103,15 +108,14 @@ struct PartTwoResponse {
     waypoint_y: i32,
 }
 
-fn part2_manhattan_distance(input: &str) -> PartTwoResponse {
-    let parsed = parse(input);
+fn part2_manhattan_distance(instructions: &[(Direction, i32)]) -> PartTwoResponse {
     // let mut facing = Direction::East;
     let mut waypoint_x = 10; // west <-> east
     let mut waypoint_y = -1; // north <-> south
     let mut ship_x = 0;
     let mut ship_y = 0;
 
-    for (direction, amount) in parsed {
+    for &(direction, amount) in instructions {
         match direction {
             Direction::North => waypoint_y -= amount,
             Direction::South => waypoint_y += amount,
@@ -165,20 +169,47 @@ const fn rotate_left(mut x: i32, mut y: i32, mut degrees: i32) -> (i32, i32) {
     (x, y)
 }
 
+pub struct Day12;
+
+impl Solver for Day12 {
+    type Input = Instructions;
+    const DAY: u32 = 12;
+
+    fn parse(&self, input: &str) -> Result<Instructions, ParseError> {
+        parse(input)
+    }
+
+    fn part1(&self, input: &Instructions) -> Solution {
+        Ok(part1(input).to_string())
+    }
+
+    fn part2(&self, input: &Instructions) -> Option<Solution> {
+        Some(Ok(part2(input).to_string()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::answers::expected;
+    use crate::solver::Part;
 
     #[test]
     fn verify_part1() {
         let input = include_str!("../input/2020/day12.txt");
-        assert_eq!(part1(input), 362);
+        assert_eq!(
+            part1(&parse(input).unwrap()).to_string(),
+            expected(12, Part::One)
+        );
     }
 
     #[test]
     fn verify_part2() {
         let input = include_str!("../input/2020/day12.txt");
-        assert_eq!(part2(input), 29895);
+        assert_eq!(
+            part2(&parse(input).unwrap()).to_string(),
+            expected(12, Part::Two)
+        );
     }
 
     #[test]
@@ -190,7 +221,7 @@ W90
 F11
 L1
 R2";
-        let result = parse(input);
+        let result = parse(input).unwrap();
 
         assert_eq!(
             result,
@@ -214,35 +245,35 @@ F7
 R90
 F11";
 
-        let result = part1_manhattan_distance(input).0;
+        let result = part1_manhattan_distance(&parse(input).unwrap()).0;
 
         assert_eq!(result, 25);
     }
 
     #[test]
     fn should_face_east() {
-        let (_, direction) = part1_manhattan_distance("F1");
+        let (_, direction) = part1_manhattan_distance(&parse("F1").unwrap());
         assert_eq!(direction, Direction::East);
     }
 
     #[test]
     fn test_with_turn_left() {
-        let (_, direction) = part1_manhattan_distance("L90");
+        let (_, direction) = part1_manhattan_distance(&parse("L90").unwrap());
         assert_eq!(direction, Direction::North);
     }
 
     #[test]
     fn test_with_turn_right() {
-        let (_, direction) = part1_manhattan_distance("R90");
+        let (_, direction) = part1_manhattan_distance(&parse("R90").unwrap());
         assert_eq!(direction, Direction::South);
     }
 
     #[test]
     fn test_with_turn_180() {
-        let (_, direction) = part1_manhattan_distance("R180");
+        let (_, direction) = part1_manhattan_distance(&parse("R180").unwrap());
         assert_eq!(direction, Direction::West, "turn Right 180");
 
-        let (_, direction) = part1_manhattan_distance("L180");
+        let (_, direction) = part1_manhattan_distance(&parse("L180").unwrap());
         assert_eq!(direction, Direction::West, "turn Left 180");
     }
 
@@ -254,7 +285,7 @@ F7
 R90
 F11";
 
-        let result = part2_manhattan_distance(input).distance;
+        let result = part2_manhattan_distance(&parse(input).unwrap()).distance;
 
         assert_eq!(result, 286);
     }
@@ -266,7 +297,7 @@ N3
 F7
 R90";
 
-        let result = part2_manhattan_distance(input);
+        let result = part2_manhattan_distance(&parse(input).unwrap());
 
         assert_eq!(result.ship_x, 170);
         assert_eq!(result.ship_y, -38);
@@ -277,14 +308,32 @@ R90";
 
     #[test]
     fn test_rotate_waypoint_left() {
-        let result = part2_manhattan_distance("L90");
+        let result = part2_manhattan_distance(&parse("L90").unwrap());
 
         assert_eq!(result.waypoint_x, -1, "check waypoint x");
         assert_eq!(result.waypoint_y, -10, "check waypoint y");
 
-        let result = part2_manhattan_distance("L90\nL90");
+        let result = part2_manhattan_distance(&parse("L90\nL90").unwrap());
 
         assert_eq!(result.waypoint_x, -10, "check waypoint x");
         assert_eq!(result.waypoint_y, 1, "check waypoint y");
     }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = parse(
+            "N10
+X3",
+        )
+        .unwrap_err();
+        assert_eq!((error.line, error.column), (Some(2), Some(1)));
+
+        let error = parse(
+            "N10
+F-",
+        )
+        .unwrap_err();
+        assert_eq!((error.line, error.column), (Some(2), Some(2)));
+        assert_eq!(error.expected, "i32");
+    }
 }
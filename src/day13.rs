@@ -1,24 +1,53 @@
+use crate::error::ParseError;
+use crate::solver::{Solution, Solver};
+use crate::util::try_parse;
 use itertools::Itertools;
 use std::fmt;
 use std::fmt::Formatter;
 use std::str::FromStr;
-use std::string::ParseError;
+
+/// My earliest departure time, and the busses in service
+type Notes = (u32, Vec<Buss>);
+
+#[aoc_generator(day13)]
+fn parse(input: &str) -> Result<Notes, ParseError> {
+    let mut iter = input.lines();
+    let my_time: u32 = try_parse(iter.next().unwrap_or_default()).map_err(|e| e.at_line(1))?;
+
+    let line = iter
+        .next()
+        .ok_or_else(|| ParseError::new("a line of buss ids", "end of input").at_line(2))?;
+    let mut column = 1;
+    let busses = line
+        .split(',')
+        .map(|buss| {
+            let parsed = buss
+                .parse::<Buss>()
+                .map_err(|e| e.shifted(column - 1).at_line(2));
+            column += buss.len() + 1;
+            parsed
+        })
+        .collect::<Result<Vec<_>, ParseError>>()?;
+
+    Ok((my_time, busses))
+}
 
 #[aoc(day13, part1)]
-fn part1(input: &str) -> u32 {
-    let (buss, minutes) = get_id_and_minutes(input);
+fn part1(notes: &Notes) -> u32 {
+    let (buss, minutes) = get_id_and_minutes(notes);
     buss * minutes
 }
 
 #[aoc(day13, part2)]
-fn part2(input: &str) -> i128 {
-    part2_calc_buss_offsets_using_chinese_remainder_theorem(input)
+fn part2(notes: &Notes) -> i128 {
+    part2_calc_buss_offsets_using_chinese_remainder_theorem(&notes.1)
 }
 
-fn get_id_and_minutes(input: &str) -> (u32, u32) {
-    let (my_time, busses): (u32, Vec<Buss>) = parse(input);
+fn get_id_and_minutes(notes: &Notes) -> (u32, u32) {
+    let (my_time, busses) = notes;
+    let my_time = *my_time;
 
-    let multiplied_until_after_my_time = busses.into_iter().filter_map(|buss| match buss {
+    let multiplied_until_after_my_time = busses.iter().filter_map(|buss| match buss {
         Buss::Numeric(b) => Some({
             let mut new_buss = 0;
             while new_buss < my_time {
@@ -46,7 +75,7 @@ fn get_id_and_minutes(input: &str) -> (u32, u32) {
 }
 
 #[derive(Eq, PartialEq, Copy, Clone, Hash)]
-enum Buss {
+pub enum Buss {
     Numeric(NumericBuss),
     X,
 }
@@ -73,7 +102,7 @@ impl Buss {
 }
 
 #[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
-struct NumericBuss {
+pub struct NumericBuss {
     id: u32,
     departure_time: u32,
 }
@@ -84,41 +113,21 @@ impl FromStr for Buss {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "x" => Ok(Self::X),
-            other => {
-                let as_int: u32 = other.parse().expect("asd");
-                Ok(Self::from_id(as_int))
-            }
+            other => match try_parse(other) {
+                Ok(id) => Ok(Self::from_id(id)),
+                Err(_) => Err(ParseError::new("a buss id or \"x\"", other).at_column(1)),
+            },
         }
     }
 }
 
-fn parse(input: &str) -> (u32, Vec<Buss>) {
-    let mut iter = input.lines();
-    let my_time: u32 = iter.next().unwrap().parse().unwrap();
-
-    let busses = iter.next().unwrap();
-    let busses = busses
-        .split(',')
-        .map(|buss| buss.parse::<Buss>().unwrap())
-        .collect::<Vec<_>>();
-
-    (my_time, busses)
-}
-
-fn part2_calc_buss_offsets_using_chinese_remainder_theorem(input: &str) -> i128 {
-    let _ = input.lines().next();
-    let ids: Vec<(usize, i128)> = input
-        .lines()
-        .nth(1)
-        .unwrap()
-        .split(',')
+fn part2_calc_buss_offsets_using_chinese_remainder_theorem(busses: &[Buss]) -> i128 {
+    let ids: Vec<(usize, i128)> = busses
+        .iter()
         .enumerate()
-        .filter_map(|(i, v)| {
-            if v == "x" {
-                None
-            } else {
-                Some((i, v.parse::<i128>().unwrap()))
-            }
+        .filter_map(|(i, buss)| match buss {
+            Buss::Numeric(b) => Some((i, i128::from(b.id))),
+            Buss::X => None,
         })
         .collect();
 
@@ -144,7 +153,7 @@ fn chinese_remainder(residues: &[i128], modulii: &[i128]) -> Option<i128> {
     Some(sum % prod)
 }
 
-#[allow(clippy::clippy::many_single_char_names)]
+#[allow(clippy::many_single_char_names)]
 fn egcd(a: i128, b: i128) -> (i128, i128, i128) {
     if a == 0 {
         (b, 0, 1)
@@ -163,20 +172,47 @@ fn mod_inv(x: i128, n: i128) -> Option<i128> {
     }
 }
 
+pub struct Day13;
+
+impl Solver for Day13 {
+    type Input = Notes;
+    const DAY: u32 = 13;
+
+    fn parse(&self, input: &str) -> Result<Notes, ParseError> {
+        parse(input)
+    }
+
+    fn part1(&self, input: &Notes) -> Solution {
+        Ok(part1(input).to_string())
+    }
+
+    fn part2(&self, input: &Notes) -> Option<Solution> {
+        Some(Ok(part2(input).to_string()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::answers::expected;
+    use crate::solver::Part;
 
     #[test]
     fn verify_part1() {
         let input = include_str!("../input/2020/day13.txt");
-        assert_eq!(part1(input), 2845);
+        assert_eq!(
+            part1(&parse(input).unwrap()).to_string(),
+            expected(13, Part::One)
+        );
     }
 
     #[test]
     fn verify_part2() {
         let input = include_str!("../input/2020/day13.txt");
-        assert_eq!(part2(input), 487905974205117);
+        assert_eq!(
+            part2(&parse(input).unwrap()).to_string(),
+            expected(13, Part::Two)
+        );
     }
 
     #[test]
@@ -184,7 +220,7 @@ mod tests {
         let input = "939
 7,13,x,x,59,x,31,19";
 
-        let (my_time, busses): (u32, Vec<Buss>) = parse(input);
+        let (my_time, busses) = parse(input).unwrap();
 
         assert_eq!(my_time, 939);
         assert_eq!(
@@ -208,12 +244,21 @@ mod tests {
         assert_eq!("13".parse::<Buss>().unwrap(), Buss::from_id(13));
     }
 
+    #[test]
+    fn test_parse_errors() {
+        let error = parse("939\n7,13,y,59").unwrap_err();
+        assert_eq!((error.line, error.column), (Some(2), Some(6)));
+
+        let error = parse("soon\n7,13").unwrap_err();
+        assert_eq!((error.line, error.column), (Some(1), Some(1)));
+    }
+
     #[test]
     fn test_with_provided_data() {
         let input = "939
 7,13,x,x,59,x,31,19";
 
-        let (bus_id, minutes) = get_id_and_minutes(input);
+        let (bus_id, minutes) = get_id_and_minutes(&parse(input).unwrap());
 
         assert_eq!(bus_id, 59);
         assert_eq!(minutes, 5);
@@ -221,30 +266,15 @@ mod tests {
 
     #[test]
     fn day13_part2_provided_data() {
-        assert_eq!(
-            part2_calc_buss_offsets_using_chinese_remainder_theorem("0\n17,x,13,19"),
-            3417
-        );
-        assert_eq!(
-            part2_calc_buss_offsets_using_chinese_remainder_theorem("0\n7,13,x,x,59,x,31,19"),
-            1068781
-        );
-        assert_eq!(
-            part2_calc_buss_offsets_using_chinese_remainder_theorem("0\n67,7,59,61"),
-            754018
-        );
-        assert_eq!(
-            part2_calc_buss_offsets_using_chinese_remainder_theorem("0\n67,x,7,59,61"),
-            779210
-        );
-        assert_eq!(
-            part2_calc_buss_offsets_using_chinese_remainder_theorem("0\n67,7,x,59,61"),
-            1261476
-        );
-        assert_eq!(
-            part2_calc_buss_offsets_using_chinese_remainder_theorem("0\n1789,37,47,1889"),
-            1202161486
-        );
+        let part2_calc = |input| {
+            part2_calc_buss_offsets_using_chinese_remainder_theorem(&parse(input).unwrap().1)
+        };
+        assert_eq!(part2_calc("0\n17,x,13,19"), 3417);
+        assert_eq!(part2_calc("0\n7,13,x,x,59,x,31,19"), 1068781);
+        assert_eq!(part2_calc("0\n67,7,59,61"), 754018);
+        assert_eq!(part2_calc("0\n67,x,7,59,61"), 779210);
+        assert_eq!(part2_calc("0\n67,7,x,59,61"), 1261476);
+        assert_eq!(part2_calc("0\n1789,37,47,1889"), 1202161486);
     }
 
     #[test]
@@ -1,31 +1,34 @@
-use crate::util;
+use crate::error::ParseError;
+use crate::solver::{Solution, Solver};
+use crate::util::{parse_lines, try_parse, try_str_split};
 use std::collections::{HashMap, HashSet};
 use std::iter::FromIterator;
 
-#[aoc(day14, part1)]
-fn part1(input: &str) -> u64 {
-    let input = parse(input);
+#[aoc_generator(day14)]
+fn parse(input: &str) -> Result<Vec<Operation>, ParseError> {
+    parse_lines(input, parse_operation)
+}
 
+#[aoc(day14, part1)]
+fn part1(operations: &[Operation]) -> u64 {
     let mut computer = DockingComputer::new();
 
-    for op in input {
+    for op in operations {
         computer.process(op);
     }
     computer.memory.iter().sum()
 }
 
 #[aoc(day14, part2)]
-fn part2(input: &str) -> u64 {
-    let input = parse(input);
-
+fn part2(operations: &[Operation]) -> u64 {
     let mut memory: HashMap<usize, u64> = HashMap::new();
-    let mut mask = String::new();
-    for op in input {
+    let mut mask = "";
+    for op in operations {
         match op {
             Operation::Bitmask(m) => {
                 mask = m;
             }
-            Operation::MemSet(index, value) => {
+            &Operation::MemSet(index, value) => {
                 let index_str = format!("{:036b}", index);
                 let mut new_address = String::new();
                 for (mask_bit, address_bit) in mask.chars().zip(index_str.chars()) {
@@ -90,20 +93,35 @@ fn handle_floatings_rec(mut addr: Vec<char>, index: usize) -> HashSet<usize> {
     }
 }
 
-fn parse(input: &str) -> Vec<Operation> {
-    input.lines().map(parse_operation).collect()
-}
+fn parse_operation(line: &str) -> Result<Operation, ParseError> {
+    let (target, value): (String, String) = try_str_split(line, "=")?;
+    let after_equals = &line[line.find('=').map_or(0, |i| i + 1)..];
+    let value_column = line.len() - after_equals.trim_start().len() + 1;
 
-fn parse_operation(str: &str) -> Operation {
-    if str.contains("mask") {
-        let (_, mask): (String, String) = util::str_split(str, "=").unwrap();
-        Operation::Bitmask(mask)
+    if target == "mask" {
+        if let Some((i, other)) = value
+            .chars()
+            .enumerate()
+            .find(|(_, c)| !matches!(c, '0' | '1' | 'X'))
+        {
+            return Err(
+                ParseError::new("one of '0', '1' or 'X'", other).at_column(value_column + i)
+            );
+        }
+        if value.len() != 36 {
+            return Err(ParseError::new("a mask of 36 bits", value).at_column(value_column));
+        }
+        Ok(Operation::Bitmask(value))
+    } else if let Some(address) = target
+        .strip_prefix("mem[")
+        .and_then(|rest| rest.strip_suffix(']'))
+    {
+        let address_column = line.len() - line.trim_start().len() + "mem[".len() + 1;
+        let address = try_parse(address).map_err(|e| e.shifted(address_column - 1))?;
+        let value = try_parse(&value).map_err(|e| e.shifted(value_column - 1))?;
+        Ok(Operation::MemSet(address, value))
     } else {
-        let (mem, value): (String, u64) = util::str_split(str, "=").unwrap();
-        let (_, mem): (String, String) = util::str_split(mem.as_str(), "[").unwrap();
-        let (mem, _): (usize, String) = util::str_split(mem.as_str(), "]").unwrap();
-
-        Operation::MemSet(mem, value)
+        Err(ParseError::new("\"mask\" or \"mem[<address>]\"", target).at_column(1))
     }
 }
 
@@ -115,7 +133,7 @@ struct DockingComputer {
 }
 
 #[derive(Debug, Eq, PartialEq)]
-enum Operation {
+pub enum Operation {
     Bitmask(String),
     MemSet(usize, u64),
 }
@@ -130,9 +148,9 @@ impl DockingComputer {
         }
     }
 
-    fn process(&mut self, op: Operation) {
-        match op {
-            Operation::Bitmask(mask) => self.bitmask = mask,
+    fn process(&mut self, op: &Operation) {
+        match *op {
+            Operation::Bitmask(ref mask) => self.bitmask.clone_from(mask),
             Operation::MemSet(pos, value) => {
                 assert!(
                     pos <= self.mem_size,
@@ -166,20 +184,47 @@ fn apply_bitmask(mut value: u64, bitmask: &str) -> u64 {
     value
 }
 
+pub struct Day14;
+
+impl Solver for Day14 {
+    type Input = Vec<Operation>;
+    const DAY: u32 = 14;
+
+    fn parse(&self, input: &str) -> Result<Vec<Operation>, ParseError> {
+        parse(input)
+    }
+
+    fn part1(&self, input: &Vec<Operation>) -> Solution {
+        Ok(part1(input).to_string())
+    }
+
+    fn part2(&self, input: &Vec<Operation>) -> Option<Solution> {
+        Some(Ok(part2(input).to_string()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::answers::expected;
+    use crate::solver::Part;
 
     #[test]
     fn verify_part1() {
         let input = include_str!("../input/2020/day14.txt");
-        assert_eq!(part1(input), 6386593869035);
+        assert_eq!(
+            part1(&parse(input).unwrap()).to_string(),
+            expected(14, Part::One)
+        );
     }
 
     #[test]
     fn verify_part2() {
         let input = include_str!("../input/2020/day14.txt");
-        assert_eq!(part2(input), 4288986482164);
+        assert_eq!(
+            part2(&parse(input).unwrap()).to_string(),
+            expected(14, Part::Two)
+        );
     }
 
     #[test]
@@ -199,7 +244,7 @@ mod tests {
     fn test_parse() {
         let input = "mask = 00X10101X110010011XX0X011X100000X010\nmem[13197] = 47579321";
 
-        let result = parse(input);
+        let result = parse(input).unwrap();
 
         assert_eq!(
             result,
@@ -217,7 +262,7 @@ mem[8] = 11
 mem[7] = 101
 mem[8] = 0";
 
-        let result = part1(input);
+        let result = part1(&parse(input).unwrap());
 
         assert_eq!(result, 165);
     }
@@ -229,11 +274,27 @@ mem[42] = 100
 mask = 00000000000000000000000000000000X0XX
 mem[26] = 1";
 
-        let result = part2(input);
+        let result = part2(&parse(input).unwrap());
 
         assert_eq!(result, 208);
     }
 
+    #[test]
+    fn test_parse_errors() {
+        let error = parse_operation("mask = 00X1010").unwrap_err();
+        assert_eq!(error.column, Some(8));
+        assert_eq!(error.expected, "a mask of 36 bits");
+
+        let error = parse_operation("mask = 00X10101X110010011XX0X011X10000YX010").unwrap_err();
+        assert_eq!((error.column, error.found.as_str()), (Some(39), "Y"));
+
+        let error = parse_operation("mem[13a97] = 47579321").unwrap_err();
+        assert_eq!((error.column, error.expected.as_str()), (Some(5), "usize"));
+
+        let error = parse("mem[1] = 2\nmem[1] = -2").unwrap_err();
+        assert_eq!((error.line, error.column), (Some(2), Some(10)));
+    }
+
     #[test]
     fn test_handle_floatings() {
         let result = handle_floatings("000000000000000000000000000000X1101X");
@@ -1,24 +1,24 @@
+use crate::error::ParseError;
+use crate::solver::{Solution, Solver};
+use crate::util::try_parse_separated;
 use std::collections::hash_map::RandomState;
 use std::collections::HashMap;
 
-#[aoc(day15, part1)]
-fn part1_2020th_number(input: &str) -> u32 {
-    let starting_numbers = input
-        .lines()
-        .next()
-        .unwrap()
-        .split(',')
-        .map(|line| line.parse().unwrap())
-        .collect::<Vec<u32>>();
+#[aoc_generator(day15)]
+fn parse(input: &str) -> Result<Vec<u32>, ParseError> {
+    try_parse_separated(input.lines().next().unwrap_or_default(), ",").map_err(|e| e.at_line(1))
+}
 
+#[aoc(day15, part1)]
+fn part1_2020th_number(starting_numbers: &[u32]) -> u32 {
     let mut last_spokens: HashMap<u32, u32, RandomState> = HashMap::new();
     let mut next_number = 0;
 
-    for turn in 1..2020 as u32 {
+    for turn in 1..2020_u32 {
         let maybe_precomputed_number = starting_numbers.get((turn - 1) as usize);
         if let Some(precomp) = maybe_precomputed_number {
             let when_was_it_last_said = last_spokens.get(precomp).copied();
-            last_spokens.insert(*precomp, turn as u32);
+            last_spokens.insert(*precomp, turn);
             // last_number = *precomp;
 
             // check if we have a precomputed number next
@@ -41,7 +41,7 @@ fn part1_2020th_number(input: &str) -> u32 {
         } else {
             let this_turns_number = next_number;
             let when_was_it_last_said = last_spokens.get(&next_number).copied();
-            last_spokens.insert(this_turns_number, turn as u32);
+            last_spokens.insert(this_turns_number, turn);
 
             next_number = match when_was_it_last_said {
                 None => {
@@ -60,23 +60,15 @@ fn part1_2020th_number(input: &str) -> u32 {
 }
 
 #[aoc(day15, part2)]
-fn part2_30000000th_number(input: &str) -> u32 {
-    let starting_numbers = input
-        .lines()
-        .next()
-        .unwrap()
-        .split(',')
-        .map(|line| line.parse().unwrap())
-        .collect::<Vec<u32>>();
-
+fn part2_30000000th_number(starting_numbers: &[u32]) -> u32 {
     let mut last_spokens: HashMap<u32, u32, RandomState> = HashMap::new();
     let mut next_number = 0;
 
-    for turn in 1..30_000_000 as u32 {
+    for turn in 1..30_000_000_u32 {
         let maybe_precomputed_number = starting_numbers.get((turn - 1) as usize);
         if let Some(precomp) = maybe_precomputed_number {
             let when_was_it_last_said = last_spokens.get(precomp).copied();
-            last_spokens.insert(*precomp, turn as u32);
+            last_spokens.insert(*precomp, turn);
             // last_number = *precomp;
 
             // check if we have a precomputed number next
@@ -99,7 +91,7 @@ fn part2_30000000th_number(input: &str) -> u32 {
         } else {
             let this_turns_number = next_number;
             let when_was_it_last_said = last_spokens.get(&next_number).copied();
-            last_spokens.insert(this_turns_number, turn as u32);
+            last_spokens.insert(this_turns_number, turn);
 
             next_number = match when_was_it_last_said {
                 None => {
@@ -117,20 +109,47 @@ fn part2_30000000th_number(input: &str) -> u32 {
     next_number
 }
 
+pub struct Day15;
+
+impl Solver for Day15 {
+    type Input = Vec<u32>;
+    const DAY: u32 = 15;
+
+    fn parse(&self, input: &str) -> Result<Vec<u32>, ParseError> {
+        parse(input)
+    }
+
+    fn part1(&self, input: &Vec<u32>) -> Solution {
+        Ok(part1_2020th_number(input).to_string())
+    }
+
+    fn part2(&self, input: &Vec<u32>) -> Option<Solution> {
+        Some(Ok(part2_30000000th_number(input).to_string()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::answers::expected;
+    use crate::solver::Part;
 
     #[test]
     fn verify_part1() {
         let input = include_str!("../input/2020/day15.txt");
-        assert_eq!(part1_2020th_number(input), 610);
+        assert_eq!(
+            part1_2020th_number(&parse(input).unwrap()).to_string(),
+            expected(15, Part::One)
+        );
     }
 
     #[test]
     fn verify_part2() {
         let input = include_str!("../input/2020/day15.txt");
-        assert_eq!(part2_30000000th_number(input), 1407);
+        assert_eq!(
+            part2_30000000th_number(&parse(input).unwrap()).to_string(),
+            expected(15, Part::Two)
+        );
     }
 
     // #[test]
@@ -141,17 +160,23 @@ mod tests {
 
     #[test]
     fn test_provided_examples() {
-        assert_eq!(part1_2020th_number("0,3,6"), 436);
-        assert_eq!(part1_2020th_number("1,3,2"), 1);
-        assert_eq!(part1_2020th_number("2,1,3"), 10);
-        assert_eq!(part1_2020th_number("1,2,3"), 27);
-        assert_eq!(part1_2020th_number("2,3,1"), 78);
-        assert_eq!(part1_2020th_number("3,2,1"), 438);
-        assert_eq!(part1_2020th_number("3,1,2"), 1836);
+        assert_eq!(part1_2020th_number(&parse("0,3,6").unwrap()), 436);
+        assert_eq!(part1_2020th_number(&parse("1,3,2").unwrap()), 1);
+        assert_eq!(part1_2020th_number(&parse("2,1,3").unwrap()), 10);
+        assert_eq!(part1_2020th_number(&parse("1,2,3").unwrap()), 27);
+        assert_eq!(part1_2020th_number(&parse("2,3,1").unwrap()), 78);
+        assert_eq!(part1_2020th_number(&parse("3,2,1").unwrap()), 438);
+        assert_eq!(part1_2020th_number(&parse("3,1,2").unwrap()), 1836);
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let error = parse("0,3,six").unwrap_err();
+        assert_eq!((error.line, error.column), (Some(1), Some(5)));
     }
 
     #[test]
     fn test_30000000th() {
-        assert_eq!(part2_30000000th_number("0,3,6"), 175594);
+        assert_eq!(part2_30000000th_number(&parse("0,3,6").unwrap()), 175594);
     }
 }
@@ -1,11 +1,43 @@
-use crate::util;
+use crate::error::ParseError;
+use crate::solver::{Solution, Solver};
+use crate::util::{parse_lines, try_parse_separated, try_str_split};
 use itertools::Itertools;
 use std::collections::{HashMap, HashSet};
 
-#[aoc(day16, part1)]
-fn part1_error_scanning_rate(input: &str) -> u64 {
-    let parsed = parse(input);
+#[aoc_generator(day16)]
+fn parse(input: &str) -> Result<TicketInfo, ParseError> {
+    let sections = input.split("\n\n").collect::<Vec<_>>();
+    let (rules, my_ticket, nearby_tickets) = match sections.as_slice() {
+        &[rules, my_ticket, nearby_tickets] => (rules, my_ticket, nearby_tickets),
+        _ => {
+            return Err(ParseError::new(
+                "rules, your ticket and nearby tickets separated by blank lines",
+                format!("{} sections", sections.len()),
+            ))
+        }
+    };
+    let my_ticket_line = rules.lines().count() + 1;
+    let nearby_tickets_line = my_ticket_line + my_ticket.lines().count() + 1;
+
+    let rules = parse_lines(rules, parse_rule)?;
+    let parse_ticket = |ticket: &str| parse_ticket(ticket, rules.len());
+    let my_ticket = parse_tickets(my_ticket, parse_ticket)
+        .map_err(|e| e.lines_shifted(my_ticket_line))?
+        .into_iter()
+        .next()
+        .ok_or_else(|| ParseError::new("your ticket", "no ticket").lines_shifted(my_ticket_line))?;
+    let nearby_tickets = parse_tickets(nearby_tickets, parse_ticket)
+        .map_err(|e| e.lines_shifted(nearby_tickets_line))?;
+
+    Ok(TicketInfo {
+        rules,
+        my_ticket,
+        nearby_tickets,
+    })
+}
 
+#[aoc(day16, part1)]
+fn part1_error_scanning_rate(parsed: &TicketInfo) -> u64 {
     let mut error_scanning_rate = 0;
     for ticket in &parsed.nearby_tickets {
         let invalid_fields = get_invalid_fields(ticket, &parsed.rules);
@@ -16,9 +48,7 @@ fn part1_error_scanning_rate(input: &str) -> u64 {
 }
 
 #[aoc(day16, part2)]
-fn part2_multiply_departures(input: &str) -> u64 {
-    let parsed = parse(input);
-
+fn part2_multiply_departures(parsed: &TicketInfo) -> u64 {
     let fields = figure_out_which_field_is_which(&parsed.nearby_tickets, &parsed.rules);
 
     let departures = fields
@@ -41,14 +71,14 @@ fn part2_multiply_departures(input: &str) -> u64 {
 }
 
 #[derive(Debug, Eq, PartialEq)]
-struct TicketInfo {
+pub struct TicketInfo {
     rules: Vec<Rule>,
     my_ticket: Ticket,
     nearby_tickets: Vec<Ticket>,
 }
 
 #[derive(Debug, Eq, PartialEq)]
-struct Rule {
+pub struct Rule {
     class: String,
     ranges: Vec<(u64, u64)>,
 }
@@ -75,7 +105,7 @@ impl From<(String, u64, u64, u64, u64)> for Rule {
 }
 
 #[derive(Debug, Eq, PartialEq)]
-struct Ticket {
+pub struct Ticket {
     fields: Vec<u64>,
 }
 
@@ -85,65 +115,52 @@ impl From<Vec<u64>> for Ticket {
     }
 }
 
-fn parse(input: &str) -> TicketInfo {
-    let (rules, my_ticket, nearby_tickets): (&str, &str, &str) =
-        input.split("\n\n").collect_tuple().unwrap();
-
-    TicketInfo {
-        rules: parse_rules(rules),
-        my_ticket: parse_tickets(my_ticket)
-            .into_iter()
-            .next()
-            .expect("should be a ticket here"),
-        nearby_tickets: parse_tickets(nearby_tickets),
-    }
-}
-
-fn parse_rules(rules: &str) -> Vec<Rule> {
-    rules.lines().map(parse_rule).collect()
-}
+fn parse_rule(rule: &str) -> Result<Rule, ParseError> {
+    let colon = rule
+        .find(':')
+        .ok_or_else(|| ParseError::new("\":\"", rule).at_column(rule.len() + 1))?;
+    let (class, ranges) = (&rule[..colon], &rule[colon + 1..]);
+
+    let mut offset = colon + 1;
+    let ranges = ranges
+        .split(" or ")
+        .map(|range| {
+            let parsed = try_str_split(range, "-").map_err(|e| e.shifted(offset));
+            offset += range.len() + " or ".len();
+            parsed
+        })
+        .collect::<Result<Vec<_>, ParseError>>()?;
 
-fn parse_rule(rule: &str) -> Rule {
-    let (class, rules): (String, String) = util::str_split(rule, ":").unwrap();
-    let (first_rule, second_rule): (String, String) =
-        util::str_split(rules.as_str(), "or").unwrap();
-
-    let (first_first_rule, first_second_rule): (u64, u64) =
-        util::str_split(first_rule.as_str(), "-").unwrap();
-    let (second_first_rule, second_second_rule): (u64, u64) =
-        util::str_split(second_rule.as_str(), "-").unwrap();
-
-    Rule::from((
-        class,
-        first_first_rule,
-        first_second_rule,
-        second_first_rule,
-        second_second_rule,
-    ))
+    Ok(Rule {
+        class: class.trim().to_string(),
+        ranges,
+    })
 }
 
-fn parse_ticket(ticket: &str) -> Ticket {
-    let numbers = ticket
-        .split(',')
-        .map(|n| {
-            n.parse()
-                .unwrap_or_else(|_| panic!("Couldn't parse {:?}", n))
-        })
-        .collect::<Vec<_>>();
-    Ticket::from(numbers)
+fn parse_ticket(ticket: &str, fields: usize) -> Result<Ticket, ParseError> {
+    let numbers: Vec<u64> = try_parse_separated(ticket, ",")?;
+    if numbers.len() != fields {
+        return Err(
+            ParseError::new(format!("a ticket with {} fields", fields), ticket).at_column(1),
+        );
+    }
+    Ok(Ticket::from(numbers))
 }
 
-fn parse_tickets(rules: &str) -> Vec<Ticket> {
-    rules
-        .lines()
-        .filter_map(|line| {
-            if line.contains("your ticket") || line.contains("nearby tickets") {
-                None
-            } else {
-                Some(parse_ticket(line))
-            }
-        })
-        .collect()
+fn parse_tickets(
+    tickets: &str,
+    parse_ticket: impl Fn(&str) -> Result<Ticket, ParseError>,
+) -> Result<Vec<Ticket>, ParseError> {
+    Ok(parse_lines(tickets, |line| {
+        if line.contains("your ticket") || line.contains("nearby tickets") {
+            Ok(None)
+        } else {
+            parse_ticket(line).map(Some)
+        }
+    })?
+    .into_iter()
+    .flatten()
+    .collect())
 }
 
 fn get_invalid_fields(ticket: &Ticket, rules: &[Rule]) -> Vec<u64> {
@@ -184,7 +201,7 @@ fn figure_out_which_field_is_which(tickets: &[Ticket], rules: &[Rule]) -> HashMa
             // this means that this rule things all of these fields valid
             field_to_valid_rules_mapping
                 .entry(field_index)
-                .or_insert_with(HashSet::new)
+                .or_default()
                 .insert(rule.class.clone());
         }
     }
@@ -226,20 +243,47 @@ fn figure_out_which_field_is_which(tickets: &[Ticket], rules: &[Rule]) -> HashMa
     result
 }
 
+pub struct Day16;
+
+impl Solver for Day16 {
+    type Input = TicketInfo;
+    const DAY: u32 = 16;
+
+    fn parse(&self, input: &str) -> Result<TicketInfo, ParseError> {
+        parse(input)
+    }
+
+    fn part1(&self, input: &TicketInfo) -> Solution {
+        Ok(part1_error_scanning_rate(input).to_string())
+    }
+
+    fn part2(&self, input: &TicketInfo) -> Option<Solution> {
+        Some(Ok(part2_multiply_departures(input).to_string()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::answers::expected;
+    use crate::solver::Part;
 
     #[test]
     fn verify_part1() {
         let input = include_str!("../input/2020/day16.txt");
-        assert_eq!(part1_error_scanning_rate(input), 22000);
+        assert_eq!(
+            part1_error_scanning_rate(&parse(input).unwrap()).to_string(),
+            expected(16, Part::One)
+        );
     }
 
     #[test]
     fn verify_part2() {
         let input = include_str!("../input/2020/day16.txt");
-        assert_eq!(part2_multiply_departures(input), 410460648673);
+        assert_eq!(
+            part2_multiply_departures(&parse(input).unwrap()).to_string(),
+            expected(16, Part::Two)
+        );
     }
 
     #[test]
@@ -270,7 +314,7 @@ nearby tickets:
 15,1,5
 5,14,9";
 
-        let parsed = parse(input);
+        let parsed = parse(input).unwrap();
 
         let fields = figure_out_which_field_is_which(&parsed.nearby_tickets, &parsed.rules);
 
@@ -278,4 +322,22 @@ nearby tickets:
         assert_eq!(fields[&1], "class");
         assert_eq!(fields[&2], "seat");
     }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = parse_rule("class: 0-1 or 4--19").unwrap_err();
+        assert_eq!((error.column, error.expected.as_str()), (Some(17), "u64"));
+
+        let input = "class: 0-1 or 4-19
+row: 0-5 or 8-19
+
+your ticket:
+11,12
+
+nearby tickets:
+3,9
+15,x";
+        let error = parse(input).unwrap_err();
+        assert_eq!((error.line, error.column), (Some(9), Some(4)));
+    }
 }
@@ -1,9 +1,20 @@
+use crate::error::ParseError;
+use crate::grid::Grid;
+use crate::solver::{Solution, Solver};
 use std::cmp;
 use std::collections::HashSet;
 
+/// The initial slice, `true` where a cube is active
+type Slice = Grid<bool>;
+
+#[aoc_generator(day17)]
+fn parse(input: &str) -> Result<Slice, ParseError> {
+    input.parse()
+}
+
 #[aoc(day17, part1)]
-fn part1_3d_game_of_life(input: &str) -> usize {
-    let mut space = parse(input);
+fn part1_3d_game_of_life(slice: &Slice) -> usize {
+    let mut space = to_space(slice);
 
     // print(&space);
 
@@ -17,8 +28,8 @@ fn part1_3d_game_of_life(input: &str) -> usize {
 }
 
 #[aoc(day17, part2)]
-fn part2_4d_game_of_life(input: &str) -> usize {
-    let mut space = hyper_parse(input);
+fn part2_4d_game_of_life(slice: &Slice) -> usize {
+    let mut space = to_hyper_space(slice);
 
     // hyper_print(&space);
 
@@ -224,67 +235,67 @@ fn hyper_print(coordinates: &HashSet<HyperCoordinate>) {
     }
 }
 
-fn parse(input: &str) -> HashSet<Coordinate> {
-    input
-        .lines()
-        .enumerate()
-        .flat_map(|(row, line)| {
-            line.chars()
-                .enumerate()
-                .map(move |(column, char)| (row, column, char))
-        })
-        .filter_map(|(row, column, char)| {
-            if char == '#' {
-                Some(Coordinate {
-                    x: column as i64,
-                    y: row as i64,
-                    z: 0,
-                })
-            } else {
-                None
-            }
-        })
-        .collect::<HashSet<Coordinate>>()
+/// (x, y) of every active cube in the slice
+fn active_cubes(slice: &Slice) -> impl Iterator<Item = (i64, i64)> + '_ {
+    slice
+        .iter()
+        .filter(|(_, active)| **active)
+        .map(|((row, column), _)| (column as i64, row as i64))
 }
 
-fn hyper_parse(input: &str) -> HashSet<HyperCoordinate> {
-    input
-        .lines()
-        .enumerate()
-        .flat_map(|(row, line)| {
-            line.chars()
-                .enumerate()
-                .map(move |(column, char)| (row, column, char))
-        })
-        .filter_map(|(row, column, char)| {
-            if char == '#' {
-                Some(HyperCoordinate {
-                    x: column as i64,
-                    y: row as i64,
-                    z: 0,
-                    w: 0,
-                })
-            } else {
-                None
-            }
-        })
-        .collect::<HashSet<HyperCoordinate>>()
+fn to_space(slice: &Slice) -> HashSet<Coordinate> {
+    active_cubes(slice)
+        .map(|(x, y)| Coordinate { x, y, z: 0 })
+        .collect()
+}
+
+fn to_hyper_space(slice: &Slice) -> HashSet<HyperCoordinate> {
+    active_cubes(slice)
+        .map(|(x, y)| HyperCoordinate { x, y, z: 0, w: 0 })
+        .collect()
+}
+
+pub struct Day17;
+
+impl Solver for Day17 {
+    type Input = Slice;
+    const DAY: u32 = 17;
+
+    fn parse(&self, input: &str) -> Result<Slice, ParseError> {
+        parse(input)
+    }
+
+    fn part1(&self, input: &Slice) -> Solution {
+        Ok(part1_3d_game_of_life(input).to_string())
+    }
+
+    fn part2(&self, input: &Slice) -> Option<Solution> {
+        Some(Ok(part2_4d_game_of_life(input).to_string()))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::answers::expected;
+    use crate::solver::Part;
 
     #[test]
     fn verify_part1() {
         let input = include_str!("../input/2020/day17.txt");
-        assert_eq!(part1_3d_game_of_life(input), 286);
+        assert_eq!(
+            part1_3d_game_of_life(&parse(input).unwrap()).to_string(),
+            expected(17, Part::One)
+        );
     }
 
     #[test]
     fn verify_part2() {
         let input = include_str!("../input/2020/day17.txt");
-        assert_eq!(part2_4d_game_of_life(input), 960);
+        assert_eq!(
+            part2_4d_game_of_life(&parse(input).unwrap()).to_string(),
+            expected(17, Part::Two)
+        );
     }
 
     #[test]
@@ -293,7 +304,7 @@ mod tests {
 ..#
 ###";
 
-        let result = part1_3d_game_of_life(input);
+        let result = part1_3d_game_of_life(&parse(input).unwrap());
         assert_eq!(result, 112);
     }
 
@@ -303,7 +314,13 @@ mod tests {
 ..#
 ###";
 
-        let result = part2_4d_game_of_life(input);
+        let result = part2_4d_game_of_life(&parse(input).unwrap());
         assert_eq!(result, 848);
     }
+
+    #[test]
+    fn test_parse_error() {
+        let error = parse(".#.\n.o#").unwrap_err();
+        assert_eq!((error.line, error.column), (Some(2), Some(2)));
+    }
 }
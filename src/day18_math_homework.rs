@@ -1,180 +1,415 @@
-use itertools::Itertools;
-use regex::Regex;
+use std::error::Error;
+use std::fmt;
+
+use num_bigint::BigInt;
+
+use crate::error::ParseError;
+use crate::expr::diagnostic::Diagnostic;
+use crate::expr::lexer::{tokenize, Token};
+use crate::expr::number::{EvalError, Number};
+use crate::expr::parser::{parse as parse_expression, parse_tokens};
+use crate::expr::precedence::OperatorTable;
+use crate::expr::{Expr, Operator};
+use crate::solver::{Part, Solution, Solver, Variant};
+use crate::util::parse_lines;
+
+/// Only splits the lines into tokens, whether they parse depends on the operators of each part
+#[aoc_generator(day18)]
+fn parse(input: &str) -> Result<Vec<Vec<Token>>, ParseError> {
+    parse_lines(input, tokenize)
+}
 
 #[aoc(day18, part1)]
-fn part1_homework(input: &str) -> u64 {
-    input
-        .lines()
-        .map(part1_calculate_line)
-        .map(|(count, _)| count)
-        .sum()
+fn part1_homework(expressions: &[Vec<Token>]) -> Result<u128, HomeworkError> {
+    homework(expressions, &OperatorTable::left_to_right())
 }
 
 #[aoc(day18, part2)]
-fn part2_homework(input: &str) -> u128 {
-    input.lines().map(part2_calculate_line).sum()
+fn part2_homework(expressions: &[Vec<Token>]) -> Result<u128, HomeworkError> {
+    homework(expressions, &OperatorTable::addition_first())
 }
 
-#[derive(Debug)]
-enum Operation {
-    Addition,
-    Multiplication,
-    None,
+/// Exact however big the homework gets
+#[aoc(day18, part1, bigint)]
+fn part1_bigint(expressions: &[Vec<Token>]) -> Result<BigInt, HomeworkError> {
+    homework(expressions, &OperatorTable::left_to_right())
 }
 
-fn part1_calculate_line(line: &str) -> (u64, usize) {
-    let mut sum = 0;
-    let mut operation = Operation::Addition;
-    let mut parenthesis_skip = 0;
-    let mut skip_to_index = 0;
+#[aoc(day18, part2, bigint)]
+fn part2_bigint(expressions: &[Vec<Token>]) -> Result<BigInt, HomeworkError> {
+    homework(expressions, &OperatorTable::addition_first())
+}
 
-    for (index, char) in line.chars().enumerate() {
-        if index < skip_to_index {
-            continue;
-        }
-        match char {
-            '+' => operation = Operation::Addition,
-            '*' => operation = Operation::Multiplication,
-            '(' => {
-                // lets recursively call this for the thing right of the (
-                let right_side = &line[index + 1..];
-                let (digit, new_index) = part1_calculate_line(right_side);
-                skip_to_index = index + new_index + 1;
-                match operation {
-                    Operation::Addition => sum += digit as u64,
-                    Operation::Multiplication => sum *= digit as u64,
-                    Operation::None => panic!("Don't know which is the current operation"),
-                }
-                parenthesis_skip += 1;
-            }
-            ')' => {
-                // exit this
-                parenthesis_skip -= 1;
-                if parenthesis_skip < 0 {
-                    return (sum, index);
-                }
-            }
-            ' ' => {}
-            digit => {
-                //we have a digit, lets operate it together with the last
-                let digit = digit.to_digit(10).expect("This should be a number");
-                match operation {
-                    Operation::Addition => sum += u64::from(digit),
-                    Operation::Multiplication => sum *= u64::from(digit),
-                    Operation::None => panic!("Don't know which is the current operation"),
-                }
-                operation = Operation::None;
-            }
+///
+/// A line of the homework that either doesn't parse with the operators of a part, or has no value
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum HomeworkError {
+    Parse(ParseError),
+    Eval(EvalError),
+}
+
+impl fmt::Display for HomeworkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HomeworkError::Parse(error) => error.fmt(f),
+            HomeworkError::Eval(error) => error.fmt(f),
         }
     }
+}
+
+impl Error for HomeworkError {}
+
+impl From<ParseError> for HomeworkError {
+    fn from(error: ParseError) -> Self {
+        HomeworkError::Parse(error)
+    }
+}
 
-    (sum, line.len())
+impl From<EvalError> for HomeworkError {
+    fn from(error: EvalError) -> Self {
+        HomeworkError::Eval(error)
+    }
 }
 
-pub fn part2_calculate_line(line: &str) -> u128 {
-    let original = line;
-    let mut line = original.to_string();
+///
+/// The sum of every line of tokens, evaluated in `N` with the precedence and associativity of
+/// `table`. The error has the line that uses an operator `table` doesn't have, or of the
+/// operation that has no value, adding up the lines overflowing is an error of the line where it
+/// happened
+/// example
+/// ```
+/// # use advent_of_code_2020::day18_math_homework::{homework, HomeworkError};
+/// # use advent_of_code_2020::expr::lexer::tokenize;
+/// # use advent_of_code_2020::expr::precedence::OperatorTable;
+/// let lines = vec![tokenize("2 * 3").unwrap(), tokenize("8 - 5").unwrap()];
+///
+/// assert_eq!(homework::<u64>(&lines, &OperatorTable::arithmetic()), Ok(9));
+/// match homework::<u64>(&lines, &OperatorTable::left_to_right()) {
+///     Err(HomeworkError::Parse(error)) => {
+///         assert_eq!((error.line, error.column), (Some(2), Some(3)))
+///     }
+///     other => panic!("expected a parse error, got {:?}", other),
+/// }
+/// ```
+pub fn homework<N: Number>(
+    expressions: &[Vec<Token>],
+    table: &OperatorTable,
+) -> Result<N, HomeworkError> {
+    let mut sum = N::from(0);
+    for (index, tokens) in expressions.iter().enumerate() {
+        let expr = parse_tokens(tokens, table).map_err(|error| error.at_line(index + 1))?;
+        let value = expr.evaluate().map_err(|error| error.at_line(index + 1))?;
+        sum = add_line(&sum, &value, index + 1, &expr)?;
+    }
+    Ok(sum)
+}
 
-    // find the "smallest" parenthesis, resolve and replace it, repeat until no more parens
-    while line.contains('(') {
-        let mut left_parenthesis = 0;
-        let mut right_parenthesis = 0;
-        for (index, char) in line.chars().enumerate() {
-            match char {
-                '(' => left_parenthesis = index,
-                ')' => {
-                    right_parenthesis = index;
-                    break;
+///
+/// The sum of every line of `input`, like [`homework`], but every line that doesn't parse or has
+/// no value is a [`Diagnostic`] instead of stopping at the first one
+/// example
+/// ```
+/// # use advent_of_code_2020::day18_math_homework::evaluate_file;
+/// # use advent_of_code_2020::expr::precedence::OperatorTable;
+/// let table = OperatorTable::left_to_right();
+/// assert_eq!(evaluate_file::<u64>("1 + 2\n3 * 4", &table), Ok(15));
+///
+/// let diagnostics = evaluate_file::<u64>("1 +\n2\n(3", &table).unwrap_err();
+/// let lines = diagnostics.iter().map(|diagnostic| diagnostic.line).collect::<Vec<_>>();
+/// assert_eq!(lines, vec![1, 3]);
+/// ```
+pub fn evaluate_file<N: Number>(input: &str, table: &OperatorTable) -> Result<N, Vec<Diagnostic>> {
+    // `None` once adding up the lines overflowed, the lines after it are still checked
+    let mut sum = Some(N::from(0));
+    let mut diagnostics = vec![];
+    for (index, line) in input.lines().enumerate() {
+        let number = index + 1;
+        let expr = match parse_expression(line, table) {
+            Ok(expr) => expr,
+            Err(error) => {
+                diagnostics.push(Diagnostic::parse(&error.at_line(number), line));
+                continue;
+            }
+        };
+        let value = match expr.evaluate() {
+            Ok(value) => value,
+            Err(error) => {
+                diagnostics.push(Diagnostic::eval(&error.at_line(number), line));
+                continue;
+            }
+        };
+        if let Some(before) = &sum {
+            match add_line(before, &value, number, &expr) {
+                Ok(after) => sum = Some(after),
+                Err(error) => {
+                    diagnostics.push(Diagnostic::eval(&error, line));
+                    sum = None;
                 }
-                _ => (),
             }
         }
-        let expression = &line[left_parenthesis..=right_parenthesis];
-        let inside_parenthesis = &expression[1..];
-        let inside_parenthesis = &inside_parenthesis[0..inside_parenthesis.len() - 1];
-
-        let value = evaluate_paranthesisless_expression(inside_parenthesis);
-        let value_str = value.to_string();
-        let value_str = value_str.as_str();
-        let new_line = line.replace(expression, value_str);
-        line = new_line;
     }
+    match sum {
+        Some(sum) if diagnostics.is_empty() => Ok(sum),
+        _ => Err(diagnostics),
+    }
+}
 
-    let result = evaluate_paranthesisless_expression(line.as_str());
-    result
+/// Adds the `value` of line `number` to the sum of the ones before it
+fn add_line<N: Number>(sum: &N, value: &N, number: usize, expr: &Expr) -> Result<N, EvalError> {
+    N::apply(Operator::Add, sum, value).map_err(|error| EvalError {
+        line: Some(number),
+        span: expr.span(),
+        error,
+        expression: format!("the sum of lines 1 to {}", number),
+        number: N::NAME,
+    })
 }
 
-lazy_static! {
-    static ref REGEX: Regex = Regex::new(r"(?P<left>\d+) \+ (?P<right>\d+)").unwrap();
+#[cfg(test)]
+fn part1_calculate_line(line: &str) -> u128 {
+    calculate_line(line, &OperatorTable::left_to_right())
 }
 
-fn evaluate_paranthesisless_expression(line: &str) -> u128 {
-    assert!(!line.contains('('));
-    assert!(!line.contains(')'));
+#[cfg(test)]
+fn part2_calculate_line(line: &str) -> u128 {
+    calculate_line(line, &OperatorTable::addition_first())
+}
 
-    let original_line = line;
-    let mut line = original_line.to_string();
-    while line.contains('+') {
-        let mut iter = REGEX.captures_iter(line.as_str());
-        let captures = iter.next().unwrap();
-        let left_str = &captures["left"];
-        let left = left_str.parse::<u64>().unwrap();
-        let right_str = &captures["right"];
-        let right = right_str.parse::<u64>().unwrap();
+#[cfg(test)]
+fn calculate_line(line: &str, table: &OperatorTable) -> u128 {
+    parse_expression(line, table)
+        .unwrap_or_else(|error| panic!("{}", error))
+        .evaluate()
+        .unwrap_or_else(|error| panic!("{}", error))
+}
 
-        let result = left + right;
-        let expression = left_str.to_owned() + " + " + right_str;
+pub struct Day18;
 
-        line = line.replacen(expression.as_str(), result.to_string().as_str(), 1);
+impl Solver for Day18 {
+    type Input = Vec<Vec<Token>>;
+    const DAY: u32 = 18;
+
+    fn parse(&self, input: &str) -> Result<Vec<Vec<Token>>, ParseError> {
+        parse(input)
+    }
+
+    fn part1(&self, input: &Vec<Vec<Token>>) -> Solution {
+        Ok(part1_homework(input)?.to_string())
     }
-    // now there should be just numbers and multiplication signs
 
-    let result = line
-        .split('*')
-        .map(|digit| digit.trim().parse::<u128>().unwrap())
-        .fold1(|left, right| left * right)
-        .unwrap();
-    result
+    fn part2(&self, input: &Vec<Vec<Token>>) -> Option<Solution> {
+        Some(
+            part2_homework(input)
+                .map(|sum| sum.to_string())
+                .map_err(Into::into),
+        )
+    }
+
+    fn variants(&self) -> Vec<Variant<Vec<Vec<Token>>>> {
+        vec![
+            Variant::new(Part::One, "bigint", |input| {
+                Ok(part1_bigint(input)?.to_string())
+            }),
+            Variant::new(Part::Two, "bigint", |input| {
+                Ok(part2_bigint(input)?.to_string())
+            }),
+        ]
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::answers::expected;
+    use crate::solver::Part;
 
     #[test]
     fn verify_part1() {
         let input = include_str!("../input/2020/day18.txt");
-        assert_eq!(part1_homework(input), 21347713555555);
+        assert_eq!(
+            part1_homework(&parse(input).unwrap()).unwrap().to_string(),
+            expected(18, Part::One)
+        );
     }
 
     #[test]
     fn verify_part2() {
         let input = include_str!("../input/2020/day18.txt");
-        assert_eq!(part2_homework(input), 275011754427339);
+        assert_eq!(
+            part2_homework(&parse(input).unwrap()).unwrap().to_string(),
+            expected(18, Part::Two)
+        );
+    }
+
+    #[test]
+    fn verify_bigint() {
+        let input = parse(include_str!("../input/2020/day18.txt")).unwrap();
+
+        assert_eq!(
+            part1_bigint(&input).unwrap().to_string(),
+            expected(18, Part::One)
+        );
+        assert_eq!(
+            part2_bigint(&input).unwrap().to_string(),
+            expected(18, Part::Two)
+        );
+    }
+
+    #[test]
+    fn test_deep_lines_are_reported() {
+        let long = vec!["1"; 50_000].join(" + ");
+        let nested = format!("{}1{}", "(".repeat(50_000), ")".repeat(50_000));
+        let input = format!("{}\n{}", long, nested);
+
+        let diagnostics =
+            evaluate_file::<u64>(&input, &OperatorTable::left_to_right()).unwrap_err();
+        let messages = diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.line, diagnostic.message.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            vec![(2, "expected at most 256 levels of nesting, found \"(\"")]
+        );
+
+        let input = parse(&input).unwrap();
+        match part1_homework(&input) {
+            Err(HomeworkError::Parse(error)) => assert_eq!(error.line, Some(2)),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+        assert_eq!(part1_homework(&input[..1]).unwrap(), 50_000);
+    }
+
+    #[test]
+    fn test_every_bad_line_is_reported() {
+        let input = "1 + 2\n2 * (3 + 4))\n4294967296 * 4294967296\n5 ^ 2\n(6";
+
+        let diagnostics = evaluate_file::<u64>(input, &OperatorTable::left_to_right()).unwrap_err();
+
+        let report = diagnostics
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_eq!(
+            report,
+            r#"line 2, column 12: expected a matching '(', found ")"
+  2 | 2 * (3 + 4))
+    |            ^
+line 3, column 1: 4294967296 * 4294967296 overflows u64
+  3 | 4294967296 * 4294967296
+    | ^^^^^^^^^^^^^^^^^^^^^^^
+line 4, column 3: expected one of '+', '*', found "^"
+  4 | 5 ^ 2
+    |   ^
+line 5, column 1: expected a matching ')', found "("
+  5 | (6
+    | ^"#
+        );
+        assert_eq!(
+            evaluate_file::<BigInt>(input, &OperatorTable::arithmetic())
+                .unwrap_err()
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_the_sum_overflowing_is_reported_once() {
+        let big = "18446744073709551615";
+        let input = format!("{0}\n{0}\n{0}\n1 +", big);
+
+        let diagnostics =
+            evaluate_file::<u64>(&input, &OperatorTable::left_to_right()).unwrap_err();
+
+        let messages = diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.line, diagnostic.message.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            vec![
+                (2, "the sum of lines 1 to 2 overflows u64"),
+                (4, "expected a number or '(', found \"nothing\"")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_overflowing_homework() {
+        let big = "99999999999 * 99999999999";
+        let input = parse(&format!("1 + 2\n{0} * {0} * {0} * {0}", big)).unwrap();
+
+        match part1_homework(&input) {
+            Err(HomeworkError::Eval(error)) => {
+                assert_eq!((error.line, error.span.start), (Some(2), 0))
+            }
+            other => panic!("expected an overflow, got {:?}", other),
+        }
+        assert_eq!(
+            part1_bigint(&input).unwrap().to_string(),
+            (BigInt::from(99_999_999_999_u64).pow(8) + 3_u32).to_string()
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = parse("1 + 2\n2 & 3").unwrap_err();
+        assert_eq!((error.line, error.column), (Some(2), Some(3)));
+
+        let input = parse("1 + 2\n2 * (3 + 4))").unwrap();
+        match part1_homework(&input) {
+            Err(HomeworkError::Parse(error)) => {
+                assert_eq!((error.line, error.column), (Some(2), Some(12)))
+            }
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_operators_depend_on_the_part() {
+        let input = parse("2 - 3").unwrap();
+
+        match part1_homework(&input) {
+            Err(HomeworkError::Parse(error)) => {
+                assert_eq!((error.column, error.found.as_str()), (Some(3), "-"))
+            }
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+        assert_eq!(
+            homework::<i128>(&input, &OperatorTable::arithmetic()),
+            Ok(-1)
+        );
     }
 
     #[test]
     fn test_no_parenthesis() {
-        assert_eq!(part1_calculate_line("2 * 3").0, 6);
-        assert_eq!(part1_calculate_line("2 + 3").0, 5);
-        assert_eq!(part1_calculate_line("2 + 3 * 2").0, 10);
+        assert_eq!(part1_calculate_line("2 * 3"), 6);
+        assert_eq!(part1_calculate_line("2 + 3"), 5);
+        assert_eq!(part1_calculate_line("2 + 3 * 2"), 10);
+    }
+
+    #[test]
+    fn test_multi_digit_numbers() {
+        assert_eq!(part1_calculate_line("12 + 100 * 3"), 336);
+        assert_eq!(part2_calculate_line("12 * 100 + 3"), 1236);
     }
 
     #[test]
     fn test_provided_examples() {
-        assert_eq!(part1_calculate_line("2 * 3 + (4 * 5)").0, 26, "First");
+        assert_eq!(part1_calculate_line("2 * 3 + (4 * 5)"), 26, "First");
         assert_eq!(
-            part1_calculate_line("5 + (8 * 3 + 9 + 3 * 4 * 3)").0,
+            part1_calculate_line("5 + (8 * 3 + 9 + 3 * 4 * 3)"),
             437,
             "Second"
         );
         assert_eq!(
-            part1_calculate_line("5 * 9 * (7 * 3 * 3 + 9 * 3 + (8 + 6 * 4))").0,
+            part1_calculate_line("5 * 9 * (7 * 3 * 3 + 9 * 3 + (8 + 6 * 4))"),
             12240,
             "Third"
         );
         assert_eq!(
-            part1_calculate_line("((2 + 4 * 9) * (6 + 9 * 8 + 6) + 6) + 2 + 4 * 2").0,
+            part1_calculate_line("((2 + 4 * 9) * (6 + 9 * 8 + 6) + 6) + 2 + 4 * 2"),
             13632,
             "Fourth"
         );
@@ -1,182 +1,253 @@
-use regex::Regex;
-use crate::util;
-use itertools::Itertools;
-use std::collections::VecDeque;
-use std::iter::FromIterator;
-
-#[derive(Debug,Eq, PartialEq, Clone)]
-enum Rule {
+use std::collections::HashMap;
+
+use crate::error::ParseError;
+use crate::solver::{Solution, Solver};
+use crate::util::{parse_lines, try_parse, try_parse_separated};
+
+#[aoc_generator(day19)]
+fn parse_input(input: &str) -> Result<(Rules, Vec<String>), ParseError> {
+    let (rules, messages) = parse(input)?;
+    Ok((rules, messages.into_iter().map(String::from).collect()))
+}
+
+#[aoc(day19, part1)]
+fn part1((rules, messages): &(Rules, Vec<String>)) -> usize {
+    get_valid_messages(messages, rules).len()
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum Rule {
     Char(char),
     Sequence(Vec<usize>),
-    Or(Vec<usize>,Vec<usize>),
+    Or(Vec<usize>, Vec<usize>),
 }
 
-lazy_static! {
-    // static ref RULE_REGEX: Regex = Regex::new(r"(?P<left>\d+) \+ (?P<right>\d+)").unwrap();
-    static ref RULE_REGEX: Regex = Regex::new("(?P<index>\\d+): \"?(P?<value>[\\d\\w]+)\"?").unwrap();
-}
+/// Rules by their number, which can be anything the input says
+pub type Rules = HashMap<usize, Rule>;
 
-fn parse(input: &str) -> (Vec<Rule>, Vec<&str>) {
-    let mut iter = input.split("\n\n");
-    let rules = iter.next().unwrap();
-
-    let rules = rules.lines().map(|line|{
-        let (index, data):(usize, String) = util::str_split(line, ":").unwrap();
-
-        if data.contains("\"") {
-            //it's  i: "c"
-            let value = data.replace('"', "");
-            let value:char = value.parse().unwrap_or_else(|_|panic!("Couldn't parse {:?}", value));
-            (index,Rule::Char(value))
-        } else if data.contains('|') {
-            // it's i: x y | z h
-            let (left, right): (Vec<usize>, Vec<usize>) = data.split('|')
-                .map(|digits|digits.split_ascii_whitespace()
-                    .map(|digit|digit.parse().unwrap())
-                    .collect::<Vec<_>>())
-                .collect_tuple().unwrap_or_else(||panic!("Couldn't parse pair of vec for {:?}", data));
-
-            (index,Rule::Or(left, right))
-        } else {
-            // it's i: x y z
-            let values = data.split_ascii_whitespace()
-                .map(|digit|digit.parse().unwrap())
-                .collect::<Vec<_>>();
-            (index,Rule::Sequence(values))
+impl Rule {
+    fn references(&self) -> Vec<usize> {
+        match self {
+            Self::Char(_) => vec![],
+            Self::Sequence(sequence) => sequence.clone(),
+            Self::Or(left, right) => left.iter().chain(right).copied().collect(),
         }
-    }).collect::<Vec<_>>();
-
-    let mut result_rules = vec![];
-    let max = rules.iter().map(|(index,_)|index).max().unwrap();
-    result_rules.resize_with(*max + 1, ||None);
-    for (i,rule) in rules {
-        result_rules[i] = Some(rule);
     }
 
-    assert_eq!(result_rules.iter().filter(|rule|rule.is_none()).count(), 0);
-    let result_rules = result_rules.into_iter().map(|rule|rule.unwrap()).collect();
+    /// The rules a match of this one starts with, without having matched any character yet
+    fn leftmost(&self) -> Vec<usize> {
+        match self {
+            Self::Char(_) => vec![],
+            Self::Sequence(sequence) => sequence.first().copied().into_iter().collect(),
+            Self::Or(left, right) => left
+                .first()
+                .into_iter()
+                .chain(right.first())
+                .copied()
+                .collect(),
+        }
+    }
+}
 
+fn parse(input: &str) -> Result<(Rules, Vec<&str>), ParseError> {
+    let mut iter = input.split("\n\n");
+    let rules = iter.next().unwrap_or_default();
+    let messages_line = rules.lines().count() + 1;
+
+    let rules = parse_lines(rules, parse_rule)?;
+    let lines = rules
+        .iter()
+        .enumerate()
+        .map(|(line, (index, _))| (*index, line + 1))
+        .collect::<HashMap<_, _>>();
+    let rules = rules.into_iter().collect::<Rules>();
+
+    if let Some(missing) = Some(0)
+        .into_iter()
+        .chain(rules.values().flat_map(Rule::references))
+        .find(|reference| !rules.contains_key(reference))
+    {
+        return Err(ParseError::new(
+            format!("a rule numbered {}", missing),
+            "none",
+        ));
+    }
+    if let Some(cycle) = left_recursion(&rules) {
+        let path = cycle.iter().map(usize::to_string).collect::<Vec<_>>();
+        return Err(ParseError::new(
+            "rules that match a character before referring back to themselves",
+            path.join(" -> "),
+        )
+        .at_line(lines[&cycle[0]]));
+    }
 
-    let messages = iter.next().unwrap();
+    let messages = iter
+        .next()
+        .ok_or_else(|| ParseError::new("messages", "end of input").lines_shifted(messages_line))?;
     let messages = messages.lines().collect();
 
-     (result_rules, messages)
+    Ok((rules, messages))
 }
 
-fn get_valid_messages<'a>(messages: &'a [&str], rules: &[Rule]) -> Vec<&'a str> {
-    messages.into_iter().filter(|msg|is_valid(msg, rules)).cloned().collect()
-}
+/// Rules that get back to themselves without matching a character would never stop matching,
+/// returns the first such loop of rule numbers, ending where it starts
+fn left_recursion(rules: &Rules) -> Option<Vec<usize>> {
+    #[derive(Clone, Copy, Eq, PartialEq)]
+    enum Visit {
+        InProgress,
+        Done,
+    }
 
-fn is_valid(message: &str, rules: &[Rule]) -> bool {
-    println!("Does {:?} match {:?}", message, rules);
-    let mut rules_stack: VecDeque<Rule> = VecDeque::new();
-    rules_stack.push_front(rules.first().cloned().unwrap());
-    let mut index_stack = VecDeque::new();
-
-    let mut current_rule = rules.first().unwrap();
-    let mut next_rule_index = 1;
-
-    let mut i = 0;
-    loop {
-        let char = match &message.chars().nth(i) {
-            None => {
-                // I think this means we're done
-                return true;
-            }
-            Some(c) => *c,
-        };
-
-        println!("\tchecking {:?}({}), rules: {:?}", char, i, rules_stack);
-        let current_rule = rules_stack.pop_front().expect("should be a rule here");
-        match &current_rule {
-            Rule::Char(c) => {
-                if char != *c {
-                    // not a match
-                    if index_stack.is_empty() {
-                        println!("\t\tchar rule invalid: {:?} != {:?}. but index stack: {:?}", char, c, index_stack);
-                        return false;
-                    } else {
-                        // this is wrong but we have alternate paths to follow
-                        println!("\t\tchar rule invalid: {:?} != {:?}. but index stack: {:?}", char, c, index_stack);
+    let mut visits = HashMap::new();
+    let mut starts = rules.keys().copied().collect::<Vec<_>>();
+    starts.sort_unstable();
+    for start in starts {
+        if visits.contains_key(&start) {
+            continue;
+        }
+        // the path from `start`, with the leftmost rules of each step that are left to visit
+        let mut path = vec![(start, rules[&start].leftmost())];
+        visits.insert(start, Visit::InProgress);
+        while let Some((rule, next)) = path.last_mut() {
+            match next.pop() {
+                Some(next) => match visits.get(&next) {
+                    Some(Visit::InProgress) => {
+                        let entry = path.iter().position(|(rule, _)| *rule == next)?;
+                        let mut cycle = path[entry..]
+                            .iter()
+                            .map(|(rule, _)| *rule)
+                            .collect::<Vec<_>>();
+                        cycle.push(next);
+                        return Some(cycle);
                     }
-
-                } else {
-                    println!("\t\tChar rule valid!");
-                    i += 1;
-                }
-            }
-            Rule::Sequence(seq) => {
-                let rules = seq.iter().map(|index|&rules[*index]).rev().collect::<Vec<_>>();
-                println!("\t\t{:?} -> {:?}", current_rule, rules);
-                for rule in rules {
-                    rules_stack.push_front(rule.clone());
+                    Some(Visit::Done) => {}
+                    None => {
+                        visits.insert(next, Visit::InProgress);
+                        path.push((next, rules[&next].leftmost()));
+                    }
+                },
+                None => {
+                    visits.insert(*rule, Visit::Done);
+                    path.pop();
                 }
             }
-            Rule::Or(left_rules, right_rules) => {
-                // new approach, we kinda clone the stacks or something
-                //
-
-
-                // if left_rules.is_empty() {
-                //     // we've reached an or for the second time, peek to see if next is ok or we if change leg
-                //     let next_rule = rules_stack.get(1).expect("Should be a rule here I think");
-                //      // fuck, do I just
-                //
-                //
-                //     i = index_stack.pop_front().expect("There should be an index here...");
-                //     let rules = right_rules.iter().map(|index|&rules[*index]).rev().collect::<Vec<_>>();
-                //     for rule in rules {
-                //         rules_stack.push_front(rule.clone());
-                //     }
-                //
-                // } else {
-                //     // first time seeing this or, process left leg
-                //     let rules = left_rules.iter().map(|index|&rules[*index]).rev().collect::<Vec<_>>();
-                //     println!("\t\t{:?} -> {:?}", current_rule, rules);
-                //     let replacement_of_current_rule = Rule::Or(vec![], right_rules.clone());
-                //     rules_stack.push_front(replacement_of_current_rule);
-                //     index_stack.push_front(i);
-                //     for rule in rules {
-                //         rules_stack.push_front(rule.clone());
-                //     }
-                //
-                // }
-
-            }
         }
+    }
+    None
+}
+
+fn parse_rule(line: &str) -> Result<(usize, Rule), ParseError> {
+    let colon = line
+        .find(':')
+        .ok_or_else(|| ParseError::new("\":\"", line).at_column(line.len() + 1))?;
+    let index = try_parse(&line[..colon])?;
+    let data = &line[colon + 1..];
+    let offset = colon + 1;
+
+    let rule = if data.contains('"') {
+        //it's  i: "c"
+        let value = data.replace('"', "");
+        let value: char = try_parse(&value).map_err(|e| e.shifted(offset))?;
+        Rule::Char(value)
+    } else if let Some(bar) = data.find('|') {
+        // it's i: x y | z h
+        let (left, right) = (&data[..bar], &data[bar + 1..]);
+        Rule::Or(
+            parse_sequence(left).map_err(|e| e.shifted(offset))?,
+            parse_sequence(right).map_err(|e| e.shifted(offset + bar + 1))?,
+        )
+    } else {
+        // it's i: x y z
+        Rule::Sequence(parse_sequence(data).map_err(|e| e.shifted(offset))?)
+    };
+    Ok((index, rule))
+}
+
+fn parse_sequence(rules: &str) -> Result<Vec<usize>, ParseError> {
+    let trimmed = rules.trim_end();
+    let leading_whitespace = trimmed.len() - trimmed.trim_start().len();
+    let separated = trimmed.trim_start();
+    try_parse_separated(separated, " ").map_err(|e| e.shifted(leading_whitespace))
+}
+
+fn get_valid_messages<'a>(messages: &'a [impl AsRef<str>], rules: &Rules) -> Vec<&'a str> {
+    messages
+        .iter()
+        .map(AsRef::as_ref)
+        .filter(|msg| is_valid(msg, rules))
+        .collect()
+}
 
+/// Only terminates for rules without left recursion, which [`parse`] rejects
+fn is_valid(message: &str, rules: &Rules) -> bool {
+    let message = message.chars().collect::<Vec<_>>();
+    match_rule(&message, rules, 0, 0).contains(&message.len())
+}
+
+/// returns every index in `message` where a match of `rule` starting at `start` could end
+fn match_rule(message: &[char], rules: &Rules, rule: usize, start: usize) -> Vec<usize> {
+    match &rules[&rule] {
+        Rule::Char(c) => match message.get(start) {
+            Some(char) if char == c => vec![start + 1],
+            _ => vec![],
+        },
+        Rule::Sequence(sequence) => match_sequence(message, rules, sequence, start),
+        Rule::Or(left, right) => {
+            let mut ends = match_sequence(message, rules, left, start);
+            ends.extend(match_sequence(message, rules, right, start));
+            ends
+        }
     }
-    true
 }
 
+fn match_sequence(message: &[char], rules: &Rules, sequence: &[usize], start: usize) -> Vec<usize> {
+    sequence.iter().fold(vec![start], |ends, rule| {
+        ends.into_iter()
+            .flat_map(|end| match_rule(message, rules, *rule, end))
+            .collect()
+    })
+}
+
+pub struct Day19;
+
+impl Solver for Day19 {
+    type Input = (Rules, Vec<String>);
+    const DAY: u32 = 19;
+
+    fn parse(&self, input: &str) -> Result<Self::Input, ParseError> {
+        parse_input(input)
+    }
+
+    fn part1(&self, input: &Self::Input) -> Solution {
+        Ok(part1(input).to_string())
+    }
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::answers::expected;
+    use crate::solver::Part;
 
-    // #[test]
-    // fn verify_part1() {
-    //     let input = include_str!("../input/2020/day18.txt");
-    //     assert_eq!(part1_homework(input), 21347713555555);
-    // }
-    //
-    // #[test]
-    // fn verify_part2() {
-    //     let input = include_str!("../input/2020/day18.txt");
-    //     assert_eq!(part2_homework(input), 275011754427339);
-    // }
+    #[test]
+    fn verify_part1() {
+        let input = include_str!("../input/2020/day19.txt");
+        assert_eq!(
+            part1(&parse_input(input).unwrap()).to_string(),
+            expected(19, Part::One)
+        );
+    }
 
     #[test]
     fn test_parse() {
         let input = include_str!("../input/2020/day19.txt");
 
-        let parsed = parse(input);
+        let _parsed = parse(input).unwrap();
 
         // we just don't wanna panic
 
         // also make sure we're not order dependant
 
-
         let ordered_input = r#"0: 4 1 5
 1: 2 3 | 3 2
 2: 4 4 | 5 5
@@ -203,14 +274,49 @@ abbbab
 aaabbb
 aaaabbb"#;
 
-        let (ordered_rules, _) = parse(ordered_input);
-        let (scrambled_rules, _) = parse(scrambled_input);
+        let (ordered_rules, _) = parse(ordered_input).unwrap();
+        let (scrambled_rules, _) = parse(scrambled_input).unwrap();
 
         assert_eq!(ordered_rules, scrambled_rules);
     }
 
     #[test]
-    fn simple_example(){
+    fn test_parse_errors() {
+        let error = parse("0: 1 2\n1: \"a\"\n2: 1 x\n\nab").unwrap_err();
+        assert_eq!((error.line, error.column), (Some(3), Some(6)));
+
+        let error = parse("0: 1 3\n1: \"a\"\n\nab").unwrap_err();
+        assert_eq!(error.expected, "a rule numbered 3");
+
+        let error = parse("1: \"a\"\n\na").unwrap_err();
+        assert_eq!(error.expected, "a rule numbered 0");
+    }
+
+    #[test]
+    fn test_huge_rule_numbers() {
+        let (rules, messages) = parse("0: 4000000000\n4000000000: \"a\"\n\na\nb").unwrap();
+
+        assert_eq!(get_valid_messages(&messages, &rules), vec!["a"]);
+    }
+
+    #[test]
+    fn test_left_recursion_is_rejected() {
+        let error = parse("0: 0\n\na").unwrap_err();
+        assert_eq!((error.line, error.found.as_str()), (Some(1), "0 -> 0"));
+
+        let error = parse("0: 1 | 2\n1: \"a\"\n2: 3 1\n3: 1 | 0 1\n\na").unwrap_err();
+        assert_eq!(
+            (error.line, error.found.as_str()),
+            (Some(1), "0 -> 2 -> 3 -> 0")
+        );
+
+        // looping back after a character is fine, like the rules of part 2
+        let (rules, messages) = parse("0: 1 | 1 0\n1: \"a\"\n\naaa\nab").unwrap();
+        assert_eq!(get_valid_messages(&messages, &rules), vec!["aaa"]);
+    }
+
+    #[test]
+    fn simple_example() {
         let input = r#"0: 4 1 5
 1: 2 3 | 3 2
 2: 4 4 | 5 5
@@ -220,13 +326,10 @@ aaaabbb"#;
 
 abbbab"#;
 
-        let (rules, messages) = parse(input);
+        let (rules, messages) = parse(input).unwrap();
 
         let result = get_valid_messages(&messages, &rules);
-        assert_eq!(result, vec![
-            "abbbab"
-        ]);
-
+        assert_eq!(result, vec!["abbbab"]);
     }
 
     #[test]
@@ -244,32 +347,29 @@ abbbab
 aaabbb
 aaaabbb"#;
 
-        let (rules, messages) = parse(input);
-
-
-        println!("{:?}", messages);
-
-        assert_eq!(rules, vec![
-            Rule::Sequence(vec![4,1,5]),
-            Rule::Or(vec![2,3], vec![3,2]),
-            Rule::Or(vec![4,4], vec![5,5]),
-            Rule::Or(vec![4,5], vec![5,4]),
-            Rule::Char('a'),
-            Rule::Char('b'),
-        ]);
-
-        assert_eq!(messages, vec![
-"ababbb",
-"bababa",
-"abbbab",
-"aaabbb",
-"aaaabbb",
-        ]);
+        let (rules, messages) = parse(input).unwrap();
+
+        assert_eq!(
+            rules,
+            vec![
+                Rule::Sequence(vec![4, 1, 5]),
+                Rule::Or(vec![2, 3], vec![3, 2]),
+                Rule::Or(vec![4, 4], vec![5, 5]),
+                Rule::Or(vec![4, 5], vec![5, 4]),
+                Rule::Char('a'),
+                Rule::Char('b'),
+            ]
+            .into_iter()
+            .enumerate()
+            .collect::<Rules>()
+        );
+
+        assert_eq!(
+            messages,
+            vec!["ababbb", "bababa", "abbbab", "aaabbb", "aaaabbb",]
+        );
 
         let result = get_valid_messages(&messages, &rules);
-        assert_eq!(result, vec![
-            "ababbb", "abbbab"
-        ]);
-
+        assert_eq!(result, vec!["ababbb", "abbbab"]);
     }
-}
\ No newline at end of file
+}
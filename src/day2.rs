@@ -1,27 +1,21 @@
-use regex::Regex;
+use crate::error::ParseError;
+use crate::solver::{Part, Solution, Solver, Variant, WithText};
+use crate::util::{parse_lines, try_parse, try_str_split};
+use regex::{Captures, Regex};
+use std::str::FromStr;
 
 #[aoc(day2, part1)]
-fn part1(input: &str) -> usize {
-    input.lines().into_iter().filter(password_valid).count()
+fn part1(input: &str) -> Result<usize, ParseError> {
+    Ok(count_valid(&parse_policies(input)?, Policy::valid))
 }
 
 #[aoc(day2, part1, perf)]
-fn part1_perf(input: &str) -> usize {
+fn part1_perf(input: &str) -> Result<usize, ParseError> {
     let mut valid_passwords = 0;
 
-    for line in input.lines() {
-        let mut split = line.split(':');
-        let policy = split.next().unwrap();
-        let password = split.next().unwrap();
-
-        let mut ranges_and_char = policy.split(' ');
-        let ranges = ranges_and_char.next().unwrap();
-        let char = ranges_and_char.next().unwrap();
-
-        let mut ranges = ranges.split('-');
-
-        let lower_bounds: usize = ranges.next().unwrap().parse().unwrap();
-        let upper_bounds: usize = ranges.next().unwrap().parse().unwrap();
+    for (i, line) in input.lines().enumerate() {
+        let (lower_bounds, upper_bounds, char, password) =
+            split_policy(line).map_err(|e| e.at_line(i + 1))?;
 
         let characters = password.matches(char).count();
         if characters <= upper_bounds && characters >= lower_bounds {
@@ -29,119 +23,189 @@ fn part1_perf(input: &str) -> usize {
         }
     }
 
-    valid_passwords
-}
-
-#[aoc(day2, part2)]
-fn part2(input: &str) -> usize {
-    input
-        .lines()
-        .into_iter()
-        .filter(password_valid_part2)
-        .count()
+    Ok(valid_passwords)
 }
 
-fn password_valid(input: &&str) -> bool {
-    let split = input.split(':').collect::<Vec<_>>();
-    let policy = split.get(0).unwrap();
-    let password = split.get(1).unwrap();
-    let password = password.trim();
+/// "1-3 a: abcde" split up without allocating, for the perf variant
+fn split_policy(line: &str) -> Result<(usize, usize, char, &str), ParseError> {
+    let missing = |delimiter: char, within: &str| {
+        ParseError::new(format!("{:?}", delimiter), within).at_column(within.len() + 1)
+    };
+    let colon = line.find(':').ok_or_else(|| missing(':', line))?;
+    let (policy, password) = (&line[..colon], &line[colon + 1..]);
 
-    let ranges_and_char = policy.split(' ').collect::<Vec<_>>();
-    let char = ranges_and_char.get(1).unwrap();
-    let ranges = ranges_and_char.get(0).unwrap();
+    let space = policy.find(' ').ok_or_else(|| missing(' ', policy))?;
+    let (ranges, char) = (&policy[..space], &policy[space + 1..]);
+    let char = try_parse(char).map_err(|e| e.shifted(space + 1))?;
 
-    let ranges = ranges.split('-').collect::<Vec<_>>();
+    let dash = ranges.find('-').ok_or_else(|| missing('-', ranges))?;
+    let lower_bounds = try_parse(&ranges[..dash])?;
+    let upper_bounds = try_parse(&ranges[dash + 1..]).map_err(|e| e.shifted(dash + 1))?;
 
-    let lower_bounds = ranges.get(0).unwrap();
-    let lower_bounds: usize = lower_bounds.parse().unwrap();
-    let upper_bounds = ranges.get(1).unwrap();
-    let upper_bounds: usize = upper_bounds.parse().unwrap();
-
-    let characters = password.matches(char).count();
+    Ok((lower_bounds, upper_bounds, char, password))
+}
 
-    if characters > upper_bounds {
-        return false;
-    }
-    if characters < lower_bounds {
-        return false;
-    }
-    true
+#[aoc(day2, part2)]
+fn part2(input: &str) -> Result<usize, ParseError> {
+    Ok(count_valid(&parse_policies(input)?, Policy::valid_part2))
 }
 
-fn password_valid_part2(input: &&str) -> bool {
-    let split = input.split(':').collect::<Vec<_>>();
-    let policy = split.get(0).unwrap();
-    let password = split.get(1).unwrap();
-    let password = password.trim();
+fn count_valid(policies: &[Policy], valid: fn(&Policy) -> bool) -> usize {
+    policies.iter().filter(|policy| valid(policy)).count()
+}
 
-    let ranges_and_char = policy.split(' ').collect::<Vec<_>>();
-    let char = ranges_and_char.get(1).unwrap().chars().next().unwrap();
-    let ranges = ranges_and_char.get(0).unwrap();
+#[derive(Debug, Eq, PartialEq)]
+pub struct Policy {
+    lower: usize,
+    upper: usize,
+    char: char,
+    password: String,
+}
 
-    let ranges = ranges.split('-').collect::<Vec<_>>();
+impl Policy {
+    fn valid(&self) -> bool {
+        let characters = self.password.matches(self.char).count();
+        (self.lower..=self.upper).contains(&characters)
+    }
 
-    let lower_bounds = ranges.get(0).unwrap();
-    let first_index: usize = lower_bounds.parse().unwrap();
-    let upper_bounds = ranges.get(1).unwrap();
-    let second_index: usize = upper_bounds.parse().unwrap();
+    fn valid_part2(&self) -> bool {
+        // positions are 1-indexed
+        let matches_at = |position: usize| {
+            position
+                .checked_sub(1)
+                .and_then(|index| self.password.chars().nth(index))
+                == Some(self.char)
+        };
+        matches_at(self.lower) != matches_at(self.upper)
+    }
+}
 
-    let first_character = password.chars().nth(first_index - 1).unwrap();
-    let second_character = password.chars().nth(second_index - 1).unwrap();
+fn parse_policies(input: &str) -> Result<Vec<Policy>, ParseError> {
+    parse_lines(input, parse_policy)
+}
 
-    if first_character == second_character {
-        false
-    } else {
-        first_character == char || second_character == char
-    }
+fn parse_policy(line: &str) -> Result<Policy, ParseError> {
+    // 1-3 a: abcde
+    let (policy, password): (String, String) = try_str_split(line, ":")?;
+    let (ranges, char): (String, char) = try_str_split(&policy, " ")?;
+    let (lower, upper) = try_str_split(&ranges, "-")?;
+    Ok(Policy {
+        lower,
+        upper,
+        char,
+        password,
+    })
 }
 
 #[aoc(day2, part1, regex)]
-fn part1_regex(input: &str) -> usize {
-    parse(input)
+fn part1_regex(input: &str) -> Result<usize, ParseError> {
+    Ok(parse(input)?
         .into_iter()
         .filter(|(lower, higher, char, password)| {
             let characters = password.matches(*char).count();
             characters <= *higher && characters >= *lower
         })
-        .count()
+        .count())
 }
 
 #[aoc(day2, part1, regex_no_vec)]
-fn part1_regex_no_vec(input: &str) -> usize {
-    let re = Regex::new(r"(?m)^(\d+)-(\d+)\s(\w):\s(.*)$").unwrap();
+fn part1_regex_no_vec(input: &str) -> Result<usize, ParseError> {
+    let re = Regex::new(r"^(\d+)-(\d+)\s(\w):\s(.*)$").unwrap();
     let mut valid_passwords = 0;
-    for capture in re.captures_iter(input) {
-        let first_number: usize = capture.get(1).unwrap().as_str().parse().unwrap();
-        let second_number: usize = capture.get(2).unwrap().as_str().parse().unwrap();
-        let character: char = capture.get(3).unwrap().as_str().parse().unwrap();
-        let password: &str = capture.get(4).unwrap().as_str();
+    for (i, line) in input.lines().enumerate() {
+        let (first_number, second_number, character, password) =
+            match_policy(&re, line).map_err(|e| e.at_line(i + 1))?;
 
         let characters = password.matches(character).count();
         if characters <= second_number && characters >= first_number {
             valid_passwords += 1;
         }
     }
-    valid_passwords
+    Ok(valid_passwords)
 }
 
-fn parse(input: &str) -> Vec<(usize, usize, char, &str)> {
-    let re = Regex::new(r"(?m)^(\w+)-(\w+)\s(\w):\s(.*)$").unwrap();
+fn parse(input: &str) -> Result<Vec<(usize, usize, char, &str)>, ParseError> {
+    let re = Regex::new(r"^(\w+)-(\w+)\s(\w):\s(.*)$").unwrap();
 
-    re.captures_iter(input)
-        .map(|capture| {
-            let first_number: usize = capture.get(1).unwrap().as_str().parse().unwrap();
-            let second_number: usize = capture.get(2).unwrap().as_str().parse().unwrap();
-            let character: char = capture.get(3).unwrap().as_str().parse().unwrap();
-            let password: &str = capture.get(4).unwrap().as_str();
-            (first_number, second_number, character, password)
-        })
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| match_policy(&re, line).map_err(|e| e.at_line(i + 1)))
         .collect()
 }
 
+/// "1-3 a: abcde" matched by `re`, lines that don't match are an error instead of being skipped
+fn match_policy<'a>(
+    re: &Regex,
+    line: &'a str,
+) -> Result<(usize, usize, char, &'a str), ParseError> {
+    let capture = re
+        .captures(line)
+        .ok_or_else(|| ParseError::new("a policy like \"1-3 a: abcde\"", line).at_column(1))?;
+    let password = capture.get(4).map_or("", |password| password.as_str());
+    Ok((
+        parse_group(&capture, 1)?,
+        parse_group(&capture, 2)?,
+        parse_group(&capture, 3)?,
+        password,
+    ))
+}
+
+/// Parses capture group `group`, the error points at where the group starts in the line
+fn parse_group<T: FromStr>(capture: &Captures, group: usize) -> Result<T, ParseError> {
+    match capture.get(group) {
+        Some(matched) => try_parse(matched.as_str()).map_err(|e| e.shifted(matched.start())),
+        None => Err(ParseError::new(
+            format!("capture group {}", group),
+            "nothing",
+        )),
+    }
+}
+
+pub struct Day2;
+
+impl Solver for Day2 {
+    type Input = WithText<Vec<Policy>>;
+    const DAY: u32 = 2;
+
+    fn parse(&self, input: &str) -> Result<Self::Input, ParseError> {
+        Ok(WithText {
+            text: input.to_owned(),
+            parsed: parse_policies(input)?,
+        })
+    }
+
+    fn part1(&self, input: &Self::Input) -> Solution {
+        Ok(count_valid(&input.parsed, Policy::valid).to_string())
+    }
+
+    fn part2(&self, input: &Self::Input) -> Option<Solution> {
+        Some(Ok(
+            count_valid(&input.parsed, Policy::valid_part2).to_string()
+        ))
+    }
+
+    fn variants(&self) -> Vec<Variant<Self::Input>> {
+        vec![
+            Variant::new(Part::One, "perf", |input| {
+                Ok(part1_perf(&input.text)?.to_string())
+            }),
+            Variant::new(Part::One, "regex", |input| {
+                Ok(part1_regex(&input.text)?.to_string())
+            }),
+            Variant::new(Part::One, "regex_no_vec", |input| {
+                Ok(part1_regex_no_vec(&input.text)?.to_string())
+            }),
+        ]
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::answers::expected;
+    use crate::differential::{assert_variants_agree, lines};
+    use proptest::prelude::*;
 
     #[test]
     fn test_part1() {
@@ -149,7 +213,7 @@ mod tests {
 
         let result = part1(input);
 
-        assert_eq!(result, 396);
+        assert_eq!(result.map(|n| n.to_string()), Ok(expected(2, Part::One)));
     }
 
     #[test]
@@ -158,7 +222,7 @@ mod tests {
 
         let result = part1_regex(input);
 
-        assert_eq!(result, 396);
+        assert_eq!(result.map(|n| n.to_string()), Ok(expected(2, Part::One)));
     }
 
     #[test]
@@ -167,7 +231,7 @@ mod tests {
 
         let result = part1_regex_no_vec(input);
 
-        assert_eq!(result, 396);
+        assert_eq!(result.map(|n| n.to_string()), Ok(expected(2, Part::One)));
     }
 
     #[test]
@@ -176,7 +240,7 @@ mod tests {
 
         let result = part1_perf(input);
 
-        assert_eq!(result, 396);
+        assert_eq!(result.map(|n| n.to_string()), Ok(expected(2, Part::One)));
     }
 
     #[test]
@@ -185,6 +249,58 @@ mod tests {
 
         let result = part2(input);
 
-        assert_eq!(result, 428);
+        assert_eq!(result.map(|n| n.to_string()), Ok(expected(2, Part::Two)));
+    }
+
+    #[test]
+    fn test_parse_policy() {
+        assert_eq!(
+            parse_policy("1-3 b: cdefg"),
+            Ok(Policy {
+                lower: 1,
+                upper: 3,
+                char: 'b',
+                password: String::from("cdefg"),
+            })
+        );
+
+        let error = parse_policies("1-3 a: abcde\n2-x b: cdefg").unwrap_err();
+        assert_eq!((error.line, error.column), (Some(2), Some(3)));
+        assert_eq!(error.expected, "usize");
+    }
+
+    #[test]
+    fn test_variants_report_bad_lines() {
+        let input = "1-3 a: abcde\n2-x b: cdefg";
+
+        for part1 in &[part1, part1_perf, part1_regex, part1_regex_no_vec] {
+            let error = part1(input).unwrap_err();
+            assert_eq!(error.line, Some(2));
+        }
+        assert_eq!(part1_perf(input).unwrap_err().column, Some(3));
+        assert_eq!(part1_regex(input).unwrap_err().column, Some(3));
+        // the number pattern doesn't match "x"
+        assert_eq!(part1_regex_no_vec(input).unwrap_err().column, Some(1));
+        assert!(part1_perf("1-3 a abcde").is_err());
+    }
+
+    /// "1-3 a: abcde", with only a few letters so the policy letter shows up often
+    fn policy() -> impl Strategy<Value = String> {
+        (
+            1..=20_usize,
+            0..=10_usize,
+            proptest::char::range('a', 'e'),
+            "[a-e]{1,25}",
+        )
+            .prop_map(|(lower, extra, char, password)| {
+                format!("{}-{} {}: {}", lower, lower + extra, char, password)
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn test_variants_agree(input in lines(policy(), 1..50)) {
+            assert_variants_agree(&Day2, &input)?;
+        }
     }
 }
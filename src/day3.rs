@@ -1,34 +1,23 @@
+use crate::error::ParseError;
+use crate::grid::Grid;
+use crate::solver::{Part, Solution, Solver, Variant, WithText};
+
+/// `true` where there's a tree
+type Map = Grid<bool>;
+
 #[aoc_generator(day3, part1, map)]
-fn matrix_of_symbols_to_boolean_matrix(input: &str) -> Map {
-    let asd = input
-        .lines()
-        .map(|line| line.chars().map(|char| char == '#').collect())
-        .collect::<Vec<Vec<bool>>>();
-    let width = asd.get(0).unwrap().len();
-    Map {
-        coordinates: asd,
-        width,
-    }
+fn matrix_of_symbols_to_boolean_matrix(input: &str) -> Result<Map, ParseError> {
+    input.parse()
 }
 
 #[aoc_generator(day3, part2, map)]
-fn matrix_of_symbols_to_boolean_matrix2(input: &str) -> Map {
+fn matrix_of_symbols_to_boolean_matrix2(input: &str) -> Result<Map, ParseError> {
     matrix_of_symbols_to_boolean_matrix(input)
 }
 
-#[derive(Debug)]
-struct Map {
-    coordinates: Vec<Vec<bool>>,
-    width: usize,
-}
-
-impl Map {
-    fn tree_at(&self, row: usize, mut column: usize) -> bool {
-        while column >= self.width {
-            column -= self.width;
-        }
-        *self.coordinates.get(row).unwrap().get(column).unwrap()
-    }
+#[allow(clippy::cast_possible_wrap)]
+fn tree_at(map: &Map, row: usize, column: usize) -> bool {
+    *map.get_wrapping((row as isize, column as isize))
 }
 
 #[aoc(day3, part1, map)]
@@ -40,8 +29,8 @@ fn part1(map: &Map) -> i32 {
     let mut row = 0;
     let mut trees = 0;
 
-    while row < map.coordinates.len() {
-        if map.tree_at(row, column) {
+    while row < map.height() {
+        if tree_at(map, row, column) {
             trees += 1;
         }
         row += down_step;
@@ -176,8 +165,8 @@ fn part2(map: &Map) -> usize {
         let mut row = 0;
         let mut trees = 0;
 
-        while row < map.coordinates.len() {
-            if map.tree_at(row, column) {
+        while row < map.height() {
+            if tree_at(map, row, column) {
                 trees += 1;
             }
             row += down_step;
@@ -194,35 +183,77 @@ fn part2(map: &Map) -> usize {
         * get_trees_for_slope(map, 1, 2)
 }
 
+pub struct Day3;
+
+impl Solver for Day3 {
+    type Input = WithText<Map>;
+    const DAY: u32 = 3;
+
+    fn parse(&self, input: &str) -> Result<Self::Input, ParseError> {
+        Ok(WithText {
+            text: input.to_owned(),
+            parsed: matrix_of_symbols_to_boolean_matrix(input)?,
+        })
+    }
+
+    fn part1(&self, input: &Self::Input) -> Solution {
+        Ok(part1(&input.parsed).to_string())
+    }
+
+    fn part2(&self, input: &Self::Input) -> Option<Solution> {
+        Some(Ok(part2(&input.parsed).to_string()))
+    }
+
+    fn variants(&self) -> Vec<Variant<Self::Input>> {
+        vec![
+            Variant::new(Part::One, "perf", |input| {
+                Ok(part1_perf(&input.text).to_string())
+            }),
+            Variant::new(Part::One, "perf_enumerate", |input| {
+                Ok(part1_perf_enumerate(&input.text).to_string())
+            }),
+            Variant::new(Part::One, "perf_bytes", |input| {
+                Ok(part1_perf_bytes(input.text.as_bytes()).to_string())
+            }),
+            Variant::new(Part::One, "perf_bytes_split", |input| {
+                Ok(part1_perf_bytes_split(input.text.as_bytes()).to_string())
+            }),
+            Variant::new(Part::One, "perf_bytes_modulus", |input| {
+                Ok(part1_perf_bytes_modulus(input.text.as_bytes()).to_string())
+            }),
+        ]
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::answers::expected;
+    use crate::differential::{assert_variants_agree, lines};
+    use proptest::prelude::*;
 
     #[test]
     fn test_tree_at() {
-        let map = Map {
-            coordinates: vec![vec![true, false, false], vec![false, false, true]],
-            width: 3,
-        };
-
-        assert_eq!(map.tree_at(0, 0), true, "0,0");
-        assert_eq!(map.tree_at(0, 1), false, "0,1");
-        assert_eq!(map.tree_at(0, 2), false, "0,2");
-        assert_eq!(map.tree_at(0, 3), true, "0,3");
-        assert_eq!(map.tree_at(1, 0), false, "1,0");
-        assert_eq!(map.tree_at(1, 1), false, "1,1");
-        assert_eq!(map.tree_at(1, 2), true, "1,2");
-        assert_eq!(map.tree_at(1, 3), false, "1,3");
+        let map = Map::from_rows(vec![vec![true, false, false], vec![false, false, true]]).unwrap();
+
+        assert!(tree_at(&map, 0, 0), "0,0");
+        assert!(!tree_at(&map, 0, 1), "0,1");
+        assert!(!tree_at(&map, 0, 2), "0,2");
+        assert!(tree_at(&map, 0, 3), "0,3");
+        assert!(!tree_at(&map, 1, 0), "1,0");
+        assert!(!tree_at(&map, 1, 1), "1,1");
+        assert!(tree_at(&map, 1, 2), "1,2");
+        assert!(!tree_at(&map, 1, 3), "1,3");
     }
 
     #[test]
     fn verify_part1() {
         let input = include_str!("../input/2020/day3.txt");
-        let input = matrix_of_symbols_to_boolean_matrix(input);
+        let input = matrix_of_symbols_to_boolean_matrix(input).unwrap();
 
         let result = part1(&input);
 
-        assert_eq!(result, 171)
+        assert_eq!(result.to_string(), expected(3, Part::One))
     }
 
     #[test]
@@ -231,7 +262,7 @@ mod tests {
 
         let result = part1_perf(input);
 
-        assert_eq!(result, 171)
+        assert_eq!(result.to_string(), expected(3, Part::One))
     }
 
     #[test]
@@ -240,7 +271,7 @@ mod tests {
 
         let result = part1_perf_enumerate(input);
 
-        assert_eq!(result, 171)
+        assert_eq!(result.to_string(), expected(3, Part::One))
     }
 
     #[test]
@@ -249,7 +280,7 @@ mod tests {
 
         let result = part1_perf_bytes(input);
 
-        assert_eq!(result, 171)
+        assert_eq!(result.to_string(), expected(3, Part::One))
     }
 
     #[test]
@@ -258,7 +289,7 @@ mod tests {
 
         let result = part1_perf_bytes_split(input);
 
-        assert_eq!(result, 171)
+        assert_eq!(result.to_string(), expected(3, Part::One))
     }
 
     #[test]
@@ -267,16 +298,44 @@ mod tests {
 
         let result = part1_perf_bytes_modulus(input);
 
-        assert_eq!(result, 171)
+        assert_eq!(result.to_string(), expected(3, Part::One))
     }
 
     #[test]
     fn verify_part2() {
         let input = include_str!("../input/2020/day3.txt");
-        let input = matrix_of_symbols_to_boolean_matrix(input);
+        let input = matrix_of_symbols_to_boolean_matrix(input).unwrap();
 
         let result = part2(&input);
 
-        assert_eq!(result, 1206576000)
+        assert_eq!(result.to_string(), expected(3, Part::Two))
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = matrix_of_symbols_to_boolean_matrix("..#\n.x.").unwrap_err();
+        assert_eq!((error.line, error.column), (Some(2), Some(2)));
+
+        let error = matrix_of_symbols_to_boolean_matrix("..#\n.#\n...").unwrap_err();
+        assert_eq!(error.line, Some(2));
+    }
+
+    /// Maps at least 3 wide, the perf variants wrap around at most once per step right
+    fn map() -> impl Strategy<Value = String> {
+        (3..=40_usize).prop_flat_map(|width| {
+            let row = proptest::collection::vec(any::<bool>(), width).prop_map(|row| {
+                row.into_iter()
+                    .map(|tree| if tree { '#' } else { '.' })
+                    .collect()
+            });
+            lines(row, 1..60)
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn test_variants_agree(input in map()) {
+            assert_variants_agree(&Day3, &input)?;
+        }
     }
 }
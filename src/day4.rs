@@ -1,9 +1,51 @@
-use crate::util::str_split;
+use crate::error::ParseError;
+use crate::solver::{Solution, Solver};
+use crate::util::try_str_split;
+
+const REQUIRED_FIELDS: [&str; 7] = ["byr", "iyr", "eyr", "hgt", "hcl", "ecl", "pid"];
+
+#[aoc_generator(day4)]
+fn parse(input: &str) -> Result<Vec<Passport>, ParseError> {
+    let mut passports = vec![];
+    let mut fields = vec![];
+    for (line_index, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            if !fields.is_empty() {
+                passports.push(Passport { fields });
+                fields = vec![];
+            }
+            continue;
+        }
+        let mut column = 1;
+        for pair in line.split(' ') {
+            if !pair.is_empty() {
+                let (key, value): (String, String) = try_str_split(pair, ":")
+                    .map_err(|e| e.shifted(column - 1).at_line(line_index + 1))?;
+                if !REQUIRED_FIELDS.contains(&key.as_str()) && key != "cid" {
+                    return Err(ParseError::new("a passport field like \"byr\"", key)
+                        .at_column(column)
+                        .at_line(line_index + 1));
+                }
+                fields.push((key, value));
+            }
+            column += pair.len() + 1;
+        }
+    }
+    if !fields.is_empty() {
+        passports.push(Passport { fields });
+    }
+    Ok(passports)
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct Passport {
+    fields: Vec<(String, String)>,
+}
 
 #[aoc(day4, part1)]
-fn part1(input: &str) -> i32 {
+fn part1(passports: &[Passport]) -> i32 {
     let mut valid_passports = 0;
-    for passport in input.split("\n\n") {
+    for passport in passports {
         if !has_all_fields(passport) {
             continue;
         }
@@ -12,67 +54,57 @@ fn part1(input: &str) -> i32 {
     valid_passports
 }
 
-fn has_all_fields(passport: &str) -> bool {
-    passport.contains("byr")
-        && passport.contains("iyr")
-        && passport.contains("eyr")
-        && passport.contains("hgt")
-        && passport.contains("hcl")
-        && passport.contains("ecl")
-        && passport.contains("pid")
+fn has_all_fields(passport: &Passport) -> bool {
+    REQUIRED_FIELDS
+        .iter()
+        .all(|required| passport.fields.iter().any(|(key, _)| key == required))
 }
 
 #[aoc(day4, part2)]
-fn part2(input: &str) -> i32 {
+fn part2(passports: &[Passport]) -> i32 {
     let mut valids = 0;
 
-    let valid_eye_colors = vec!["amb", "blu", "brn", "gry", "grn", "hzl", "oth"];
+    let valid_eye_colors = ["amb", "blu", "brn", "gry", "grn", "hzl", "oth"];
     let valid_hair_color_digits = vec![
         "1", "2", "3", "4", "5", "6", "7", "8", "9", "0", "a", "b", "c", "d", "e", "f",
     ];
 
-    'passport: for passport in input.split("\n\n") {
-        if passport.is_empty() {
-            continue 'passport;
-        }
+    'passport: for passport in passports {
         if !has_all_fields(passport) {
             continue 'passport;
         }
-        let pairs = passport.split_ascii_whitespace();
 
-        for pair in pairs {
-            let (key, value): (String, String) = str_split(pair, ":").unwrap();
-            let key = key.as_str();
-            match key {
+        for (key, value) in &passport.fields {
+            match key.as_str() {
                 "byr" => {
                     let year: i32 = value.parse().unwrap_or(-1);
-                    if year < 1920 || year > 2002 || value.len() != 4 {
+                    if !(1920..=2002).contains(&year) || value.len() != 4 {
                         continue 'passport;
                     }
                 }
                 "iyr" => {
                     let year: i32 = value.parse().unwrap_or(-1);
-                    if year < 2010 || year > 2020 || value.len() != 4 {
+                    if !(2010..=2020).contains(&year) || value.len() != 4 {
                         continue 'passport;
                     }
                 }
                 "eyr" => {
                     let year: i32 = value.parse().unwrap_or(-1);
-                    if year < 2020 || year > 2030 || value.len() != 4 {
+                    if !(2020..=2030).contains(&year) || value.len() != 4 {
                         continue 'passport;
                     }
                 }
                 "hgt" => {
                     if value.ends_with("cm") {
                         let height_str = value.split("cm").next().unwrap();
-                        let height: i32 = height_str.parse().unwrap();
-                        if height < 150 || height > 193 || height_str.len() != 3 {
+                        let height: i32 = height_str.parse().unwrap_or(-1);
+                        if !(150..=193).contains(&height) || height_str.len() != 3 {
                             continue 'passport;
                         }
                     } else if value.ends_with("in") {
                         let height_str = value.split("in").next().unwrap();
-                        let height: i32 = height_str.parse().unwrap();
-                        if height < 59 || height > 193 || height_str.len() != 2 {
+                        let height: i32 = height_str.parse().unwrap_or(-1);
+                        if !(59..=193).contains(&height) || height_str.len() != 2 {
                             continue 'passport;
                         }
                     } else {
@@ -81,8 +113,7 @@ fn part2(input: &str) -> i32 {
                 }
                 "hcl" => {
                     let mut iter = value.chars();
-                    let first = iter.next().unwrap();
-                    if first != '#' {
+                    if iter.next() != Some('#') {
                         continue 'passport;
                     }
                     for digit in iter {
@@ -103,7 +134,7 @@ fn part2(input: &str) -> i32 {
                     }
                 }
                 "cid" => {}
-                key => panic!(format!("Unhandled key {:?}", key)),
+                key => unreachable!("parse should have rejected {:?}", key),
             }
         }
         valids += 1;
@@ -112,23 +143,54 @@ fn part2(input: &str) -> i32 {
     valids
 }
 
+pub struct Day4;
+
+impl Solver for Day4 {
+    type Input = Vec<Passport>;
+    const DAY: u32 = 4;
+
+    fn parse(&self, input: &str) -> Result<Vec<Passport>, ParseError> {
+        parse(input)
+    }
+
+    fn part1(&self, input: &Vec<Passport>) -> Solution {
+        Ok(part1(input).to_string())
+    }
+
+    fn part2(&self, input: &Vec<Passport>) -> Option<Solution> {
+        Some(Ok(part2(input).to_string()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::answers::expected;
+    use crate::solver::Part;
 
     #[test]
     fn validate_part1() {
         let input = include_str!("../input/2020/day4.txt");
 
-        let result = part1(input);
-        assert_eq!(result, 239)
+        let result = part1(&parse(input).unwrap());
+        assert_eq!(result.to_string(), expected(4, Part::One))
     }
 
     #[test]
     fn validate_part2() {
         let input = include_str!("../input/2020/day4.txt");
 
-        let result = part2(input);
-        assert_eq!(result, 188)
+        let result = part2(&parse(input).unwrap());
+        assert_eq!(result.to_string(), expected(4, Part::Two))
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = parse("ecl:gry pid:860033327\n\nhcl:#cfa07d byr 1929").unwrap_err();
+        assert_eq!((error.line, error.column), (Some(3), Some(16)));
+
+        let error = parse("ecl:gry pid:860033327\n\nhcl:#cfa07d hair:red").unwrap_err();
+        assert_eq!((error.line, error.column), (Some(3), Some(13)));
+        assert_eq!(error.found, "hair");
     }
 }
@@ -1,20 +1,40 @@
+use crate::error::ParseError;
+use crate::solver::{Solution, Solver};
+use crate::util::parse_lines;
 use itertools::Itertools;
 
+#[aoc_generator(day5)]
+fn parse(input: &str) -> Result<Vec<u16>, ParseError> {
+    parse_lines(input, parse_boarding_pass)
+}
+
+fn parse_boarding_pass(boarding_pass: &str) -> Result<u16, ParseError> {
+    let mut length = 0;
+    for (i, char) in boarding_pass.chars().enumerate() {
+        let (valid, expected) = match i {
+            0..=6 => (char == 'F' || char == 'B', "'F' or 'B'"),
+            7..=9 => (char == 'L' || char == 'R', "'L' or 'R'"),
+            _ => (false, "end of line"),
+        };
+        if !valid {
+            return Err(ParseError::new(expected, char).at_column(i + 1));
+        }
+        length += 1;
+    }
+    if length < 10 {
+        return Err(ParseError::new("10 characters", boarding_pass).at_column(length + 1));
+    }
+    Ok(get_seat_id(boarding_pass))
+}
+
 #[aoc(day5, part1)]
-fn part1(input: &str) -> u16 {
-    input
-        .lines()
-        .map(|boarding_pass| get_seat_id(boarding_pass))
-        .max()
-        .unwrap()
+fn part1(ids: &[u16]) -> u16 {
+    ids.iter().copied().max().unwrap()
 }
 
 #[aoc(day5, part2)]
-fn part2(input: &str) -> u16 {
-    let mut ids = input
-        .lines()
-        .map(|boarding_pass| get_seat_id(boarding_pass))
-        .sorted();
+fn part2(ids: &[u16]) -> u16 {
+    let mut ids = ids.iter().copied().sorted();
 
     let mut prev = ids.next().unwrap();
 
@@ -36,10 +56,10 @@ fn calc_middle(strip: &str, seats_or_rows: u16) -> u16 {
         let length_left = high - low;
         match char {
             'F' | 'L' => {
-                high -= (length_left + 1) / 2;
+                high -= length_left.div_ceil(2);
             }
             'B' | 'R' => {
-                low += (length_left + 1) / 2;
+                low += length_left.div_ceil(2);
             }
             other => panic!("Unrecognized strip char: {:?}", other),
         }
@@ -58,9 +78,30 @@ fn get_seat_id(boarding_pass: &str) -> u16 {
     row * 8 + column
 }
 
+pub struct Day5;
+
+impl Solver for Day5 {
+    type Input = Vec<u16>;
+    const DAY: u32 = 5;
+
+    fn parse(&self, input: &str) -> Result<Vec<u16>, ParseError> {
+        parse(input)
+    }
+
+    fn part1(&self, input: &Vec<u16>) -> Solution {
+        Ok(part1(input).to_string())
+    }
+
+    fn part2(&self, input: &Vec<u16>) -> Option<Solution> {
+        Some(Ok(part2(input).to_string()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::answers::expected;
+    use crate::solver::Part;
 
     #[test]
     fn test_with_given_input() {
@@ -84,12 +125,28 @@ mod tests {
     #[test]
     fn verify_part1() {
         let input = include_str!("../input/2020/day5.txt");
-        assert_eq!(part1(input), 906);
+        assert_eq!(
+            part1(&parse(input).unwrap()).to_string(),
+            expected(5, Part::One)
+        );
     }
 
     #[test]
     fn verify_part2() {
         let input = include_str!("../input/2020/day5.txt");
-        assert_eq!(part2(input), 519);
+        assert_eq!(
+            part2(&parse(input).unwrap()).to_string(),
+            expected(5, Part::Two)
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = parse("BFFFBBFRRR\nFFFBBBFRRX").unwrap_err();
+        assert_eq!((error.line, error.column), (Some(2), Some(10)));
+        assert_eq!(error.expected, "'L' or 'R'");
+
+        let error = parse("BFFFBBFRR").unwrap_err();
+        assert_eq!((error.line, error.column), (Some(1), Some(10)));
     }
 }
@@ -1,3 +1,6 @@
+use crate::error::ParseError;
+use crate::solver::{Part, Solution, Solver, Variant};
+use crate::util::parse_lines;
 use std::collections::{HashMap, HashSet};
 
 #[aoc(day6, part1)]
@@ -79,21 +82,96 @@ fn part2(input: &str) -> usize {
     total_questions
 }
 
+/// Every solution works straight on the text, so this only makes sure it's just answers a-z
+fn check_answers(input: &str) -> Result<(), ParseError> {
+    parse_lines(input, |person| {
+        match person
+            .chars()
+            .enumerate()
+            .find(|(_, answer)| !answer.is_ascii_lowercase())
+        {
+            Some((column, answer)) => {
+                Err(ParseError::new("an answer a-z", answer).at_column(column + 1))
+            }
+            None => Ok(()),
+        }
+    })?;
+    Ok(())
+}
+
+pub struct Day6;
+
+impl Solver for Day6 {
+    type Input = String;
+    const DAY: u32 = 6;
+
+    fn parse(&self, input: &str) -> Result<String, ParseError> {
+        check_answers(input)?;
+        Ok(input.to_owned())
+    }
+
+    fn part1(&self, input: &String) -> Solution {
+        Ok(part1(input).to_string())
+    }
+
+    fn part2(&self, input: &String) -> Option<Solution> {
+        Some(Ok(part2(input).to_string()))
+    }
+
+    fn variants(&self) -> Vec<Variant<String>> {
+        vec![
+            Variant::new(Part::One, "fp", |input| Ok(part1_fp(input).to_string())),
+            Variant::new(Part::One, "bit", |input| Ok(part1_bit(input).to_string())),
+        ]
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::answers::expected;
+    use crate::differential::{assert_variants_agree, lines};
+    use proptest::prelude::*;
+    use proptest::sample::subsequence;
 
     #[test]
     fn verify_part1() {
         let input = include_str!("../input/2020/day6.txt");
-        assert_eq!(part1(input), 6504);
-        assert_eq!(part1_fp(input), 6504);
-        assert_eq!(part1_bit(input), 6504);
+        assert_eq!(part1(input).to_string(), expected(6, Part::One));
+        assert_eq!(part1_fp(input).to_string(), expected(6, Part::One));
+        assert_eq!(part1_bit(input).to_string(), expected(6, Part::One));
     }
 
     #[test]
     fn verify_part2() {
         let input = include_str!("../input/2020/day6.txt");
-        assert_eq!(part2(input), 3351);
+        assert_eq!(part2(input).to_string(), expected(6, Part::Two));
+    }
+
+    #[test]
+    fn test_check_answers() {
+        assert_eq!(check_answers("abc\n\nab\nac"), Ok(()));
+
+        let error = check_answers("abc\n\nab\naC").unwrap_err();
+        assert_eq!((error.line, error.column), (Some(4), Some(2)));
+    }
+
+    /// The questions one person answered yes to, each at most once, in any order
+    fn person() -> impl Strategy<Value = String> {
+        subsequence(('a'..='z').collect::<Vec<_>>(), 1..=26)
+            .prop_shuffle()
+            .prop_map(|answers| answers.into_iter().collect())
+    }
+
+    fn groups() -> impl Strategy<Value = String> {
+        proptest::collection::vec(lines(person(), 1..6), 1..20)
+            .prop_map(|groups| groups.join("\n\n"))
+    }
+
+    proptest! {
+        #[test]
+        fn test_variants_agree(input in groups()) {
+            assert_variants_agree(&Day6, &input)?;
+        }
     }
 }
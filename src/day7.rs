@@ -1,18 +1,24 @@
-use crate::util::str_split;
+use crate::error::ParseError;
+use crate::solver::{Solution, Solver};
+use crate::util::{parse_lines, try_parse, try_str_split};
 use regex::Regex;
 use std::collections::HashMap;
 
-#[aoc(day7, part1)]
-fn part1(input: &str) -> usize {
-    let rules = input
-        .lines()
-        .map(line_to_bag_rule)
+type Bags = HashMap<String, Vec<Content>>;
+
+#[aoc_generator(day7)]
+fn parse(input: &str) -> Result<Bags, ParseError> {
+    Ok(parse_lines(input, line_to_bag_rule)?
+        .into_iter()
         .map(|rule| (rule.color, rule.contents))
-        .collect::<HashMap<String, Vec<Content>>>();
+        .collect())
+}
 
+#[aoc(day7, part1)]
+fn part1(rules: &Bags) -> usize {
     rules
         .keys()
-        .map(|bag| can_contain_golden_bag_rec(&rules, bag))
+        .map(|bag| can_contain_golden_bag_rec(rules, bag))
         .filter(|can_contain_golden_bag| *can_contain_golden_bag)
         .count()
 }
@@ -24,20 +30,13 @@ fn can_contain_golden_bag_rec(bags: &HashMap<String, Vec<Content>>, current_bag:
         Some(bags_inside) if bags_inside.iter().any(|bag| bag.color == *"shiny gold") => true,
         Some(bags_inside) => bags_inside
             .iter()
-            .map(|bag| can_contain_golden_bag_rec(bags, &bag.color))
-            .any(|has_golden_bag| has_golden_bag),
+            .any(|bag| can_contain_golden_bag_rec(bags, &bag.color)),
     }
 }
 
 #[aoc(day7, part2)]
-fn part2(input: &str) -> usize {
-    let rules = input
-        .lines()
-        .map(line_to_bag_rule)
-        .map(|rule| (rule.color, rule.contents))
-        .collect::<HashMap<String, Vec<Content>>>();
-
-    get_bags_inside_rec(&rules, &"shiny gold".to_string()) - 1
+fn part2(rules: &Bags) -> usize {
+    get_bags_inside_rec(rules, "shiny gold") - 1
 }
 
 fn get_bags_inside_rec(bags: &HashMap<String, Vec<Content>>, current_bag: &str) -> usize {
@@ -57,7 +56,7 @@ struct Rule {
     contents: Vec<Content>,
 }
 #[derive(Debug, Eq, PartialEq)]
-struct Content {
+pub struct Content {
     amount: usize,
     color: String,
 }
@@ -66,58 +65,95 @@ lazy_static! {
     static ref REGEX: Regex = Regex::new(r"^\s*(\d+)\s(.*)\sbag.*$").unwrap();
 }
 
-fn line_to_bag_rule(line: &str) -> Rule {
-    let (color, contents): (String, String) = str_split(line, "contain").unwrap();
+fn line_to_bag_rule(line: &str) -> Result<Rule, ParseError> {
+    let (color, contents): (String, String) = try_str_split(line, "contain")?;
 
-    let (color, _): (String, String) = str_split(&color, "bag").unwrap();
+    let (color, _): (String, String) = try_str_split(&color, "bag")?;
     let color = color.trim();
 
     if contents.contains("no other bag") {
-        return Rule {
+        return Ok(Rule {
             color: color.to_string(),
             contents: vec![],
-        };
+        });
     }
 
+    // where in the line the current content starts, for error messages
+    let mut offset = line.trim_end().len() - contents.len();
     let contents = contents
         .split(',')
         .map(|content| {
-            let capture = REGEX.captures_iter(content).next().unwrap();
-            let amount = capture.get(1).unwrap().as_str().parse().unwrap();
+            let content_offset = offset;
+            offset += content.len() + 1;
+
+            let capture = REGEX.captures(content).ok_or_else(|| {
+                let leading_whitespace = content.len() - content.trim_start().len();
+                ParseError::new("\"<amount> <color> bags\"", content.trim())
+                    .at_column(content_offset + leading_whitespace + 1)
+            })?;
+            let amount = capture.get(1).unwrap();
             let color = capture.get(2).unwrap().as_str();
 
-            Content {
-                amount,
+            Ok(Content {
+                amount: try_parse(amount.as_str())
+                    .map_err(|e| e.shifted(content_offset + amount.start()))?,
                 color: color.to_string(),
-            }
+            })
         })
-        .collect::<Vec<_>>();
+        .collect::<Result<Vec<_>, ParseError>>()?;
 
-    Rule {
+    Ok(Rule {
         color: color.to_string(),
         contents,
+    })
+}
+
+pub struct Day7;
+
+impl Solver for Day7 {
+    type Input = Bags;
+    const DAY: u32 = 7;
+
+    fn parse(&self, input: &str) -> Result<Bags, ParseError> {
+        parse(input)
+    }
+
+    fn part1(&self, input: &Bags) -> Solution {
+        Ok(part1(input).to_string())
+    }
+
+    fn part2(&self, input: &Bags) -> Option<Solution> {
+        Some(Ok(part2(input).to_string()))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::answers::expected;
+    use crate::solver::Part;
 
     #[test]
     fn verify_part1() {
         let input = include_str!("../input/2020/day7.txt");
-        assert_eq!(part1(input), 335);
+        assert_eq!(
+            part1(&parse(input).unwrap()).to_string(),
+            expected(7, Part::One)
+        );
     }
 
     #[test]
     fn verify_part2() {
         let input = include_str!("../input/2020/day7.txt");
-        assert_eq!(part2(input), 2431);
+        assert_eq!(
+            part2(&parse(input).unwrap()).to_string(),
+            expected(7, Part::Two)
+        );
     }
 
     #[test]
     fn test_parse_single_line_no_contents() {
-        let result = line_to_bag_rule("faded blue bags contain no other bags.");
+        let result = line_to_bag_rule("faded blue bags contain no other bags.").unwrap();
 
         assert_eq!(
             result,
@@ -130,7 +166,7 @@ mod tests {
 
     #[test]
     fn test_parse_single_line_one_contents() {
-        let result = line_to_bag_rule("bright white bags contain 1 shiny gold bag.");
+        let result = line_to_bag_rule("bright white bags contain 1 shiny gold bag.").unwrap();
 
         assert_eq!(
             result,
@@ -147,7 +183,8 @@ mod tests {
     #[test]
     fn test_parse_single_line_multiple_contents() {
         let result =
-            line_to_bag_rule("light red bags contain 1 bright white bag, 2 muted yellow bags.");
+            line_to_bag_rule("light red bags contain 1 bright white bag, 2 muted yellow bags.")
+                .unwrap();
 
         assert_eq!(
             result,
@@ -179,7 +216,7 @@ vibrant plum bags contain 5 faded blue bags, 6 dotted black bags.
 faded blue bags contain no other bags.
 dotted black bags contain no other bags.";
 
-        let result = part1(input);
+        let result = part1(&parse(input).unwrap());
 
         assert_eq!(result, 4);
     }
@@ -194,8 +231,20 @@ dark green bags contain 2 dark blue bags.
 dark blue bags contain 2 dark violet bags.
 dark violet bags contain no other bags.";
 
-        let result = part2(input);
+        let result = part2(&parse(input).unwrap());
 
         assert_eq!(result, 126);
     }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = line_to_bag_rule("light red bags hold 1 bright white bag").unwrap_err();
+        assert_eq!(error.expected, "\"contain\"");
+
+        let error =
+            line_to_bag_rule("light red bags contain 1 bright white bag, two muted yellow bags.")
+                .unwrap_err();
+        assert_eq!(error.column, Some(44));
+        assert_eq!(error.found, "two muted yellow bags.");
+    }
 }
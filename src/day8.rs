@@ -1,55 +1,86 @@
-use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
 
-use crate::util::{add, str_split};
+use crate::error::ParseError;
+use crate::solver::{Part, Solution, Solver, Variant};
+use crate::vm::cfg::Repair;
+use crate::vm::search::{search, Budget, Goal};
+use crate::vm::threaded::ThreadedCode;
+use crate::vm::{Cycle, Exit, Fault, Machine, Program};
+
+#[aoc_generator(day8)]
+fn parse(input: &str) -> Result<Program, ParseError> {
+    input.parse()
+}
 
 #[aoc(day8, part1)]
-fn part1(input: &str) -> i32 {
-    let instructions = input.lines().collect::<Vec<_>>();
-    let mut visitied_instructions = HashSet::new();
-    let mut instruction_pointer: usize = 0;
-    let mut accumulator = 0;
-    loop {
-        if visitied_instructions.contains(&instruction_pointer) {
-            return accumulator;
+fn part1(program: &Program) -> Result<i64, Fault> {
+    let mut machine = Machine::new(program.clone());
+    machine.run()?;
+    Ok(machine.acc())
+}
+
+/// Runs compiled to threaded code instead of interpreted
+#[aoc(day8, part1, threaded)]
+fn part1_threaded(program: &Program) -> Result<i64, Fault> {
+    let mut code = ThreadedCode::compile(program);
+    code.run()?;
+    Ok(code.acc())
+}
+
+/// Finds the flip through the control flow graph instead of trying them all
+#[aoc(day8, part2)]
+fn part2(program: &Program) -> Result<i64, ExitError> {
+    let repair = Repair::new(program);
+    let mut program = program.clone();
+    match repair.patch() {
+        Some(patch) => patch.apply(&mut program),
+        None if repair.already_terminates() => (),
+        None => return Err(ExitError::NoFlip),
+    }
+    get_exit_code(&program)
+}
+
+/// Tries every flip like [`part2_try_every_mutation`], but compiles once and patches the
+/// compiled code in place instead of copying the program for every flip
+#[aoc(day8, part2, brute_force_threaded)]
+fn part2_brute_force_threaded(program: &Program) -> Result<i64, ExitError> {
+    let mut code = ThreadedCode::compile(program);
+    if let Ok(Exit::Terminated) = code.run() {
+        return Ok(code.acc());
+    }
+    for (address, instruction) in program.instructions().iter().enumerate() {
+        if instruction.flipped() == *instruction {
+            continue;
         }
-        let instruction = instructions
-            .get(instruction_pointer)
-            .expect("should be an instruction here");
-        let (instruction, value): (String, i32) = str_split(instruction, " ").unwrap();
-        match instruction.as_str() {
-            "acc" => {
-                accumulator += value;
-            }
-            "jmp" => {
-                instruction_pointer = add(instruction_pointer, value - 1).unwrap();
-                // -1 to offset the normal jump ^^
-            }
-            "nop" => (),
-            inst => panic!("inst: {:?} val: {}", inst, value),
+        code.patch(address, instruction.flipped());
+        code.reset();
+        if let Ok(Exit::Terminated) = code.run() {
+            return Ok(code.acc());
         }
-        visitied_instructions.insert(instruction_pointer);
-        instruction_pointer += 1;
+        code.patch(address, *instruction);
     }
+    Err(ExitError::NoFlip)
 }
 
-#[aoc(day8, part2)]
-fn part2(input: &str) -> i32 {
-    let code = input
-        .lines()
-        .map(|line| {
-            let (instruction, value): (String, i32) = str_split(line, " ").unwrap();
-            (instruction, value)
-        })
-        .collect::<Vec<_>>();
-
-    match part2_inner(&*code) {
-        Ok(code) => code,
-        Err(msg) => panic!("Not like this: {:?}", msg),
+/// The general repair search, limited to the single flip the puzzle promises
+#[aoc(day8, part2, search)]
+fn part2_search(program: &Program) -> Result<i64, ExitError> {
+    let mut program = program.clone();
+    let repairs = search(&program, Goal::Terminate, Budget::flips(1));
+    for patch in repairs.first().ok_or(ExitError::NoFlip)? {
+        patch.apply(&mut program);
     }
+    get_exit_code(&program)
 }
 
-fn part2_inner(code: &[(String, i32)]) -> Result<i32, String> {
-    let mutations = generate_mutations(code);
+#[aoc(day8, part2, brute_force)]
+fn part2_try_every_mutation(program: &Program) -> Result<i64, ExitError> {
+    // a program that already terminates needs no flip, the same as with the control flow graph
+    if let Ok(code) = get_exit_code(program) {
+        return Ok(code);
+    }
+    let mutations = generate_mutations(program);
 
     for mutation in mutations {
         let exit_code = get_exit_code(&mutation);
@@ -57,19 +88,23 @@ fn part2_inner(code: &[(String, i32)]) -> Result<i32, String> {
             return Ok(code);
         }
     }
-    Err(String::from("No solution found"))
+    Err(ExitError::NoFlip)
 }
 
-fn generate_mutations(code: &[(String, i32)]) -> Vec<Vec<(String, i32)>> {
+/// Every copy of `program` with one `jmp` or `nop` flipped. Instructions the program never gets
+/// to are left alone, flipping them can't change what it does
+fn generate_mutations(program: &Program) -> Vec<Program> {
+    let mut machine = Machine::new(program.clone()).profiled();
+    // a fault stops the machine before the faulting jump, that one is still worth flipping
+    let _ = machine.run();
+    let coverage = machine.coverage().expect("the machine is profiled");
+    let reached = |address: usize| !coverage.is_dead(address) || address == machine.ip();
+
     let mut mutations = vec![];
-    for (i, (instruction, _)) in code.iter().enumerate() {
-        if instruction.as_str() == "nop" {
-            let mut mutation = code.to_owned();
-            let _ = std::mem::replace(&mut mutation[i].0, String::from("jmp"));
-            mutations.push(mutation);
-        } else if instruction.as_str() == "jmp" {
-            let mut mutation = code.to_owned();
-            let _ = std::mem::replace(&mut mutation[i].0, String::from("nop"));
+    for (address, instruction) in program.instructions().iter().enumerate() {
+        if instruction.flipped() != *instruction && reached(address) {
+            let mut mutation = program.clone();
+            mutation.patch(address, instruction.flipped());
             mutations.push(mutation);
         }
     }
@@ -77,115 +112,255 @@ fn generate_mutations(code: &[(String, i32)]) -> Vec<Vec<(String, i32)>> {
     mutations
 }
 
+/// Why a program has no exit code
 #[derive(Debug, Clone, Eq, PartialEq)]
-struct InfiniteLoopError;
-
-fn get_exit_code(code: &[(String, i32)]) -> Result<i32, InfiniteLoopError> {
-    let mut visitied_instructions = HashSet::new();
-    let mut instruction_pointer: usize = 0;
-    let mut accumulator = 0;
-    loop {
-        if visitied_instructions.contains(&instruction_pointer) {
-            return Err(InfiniteLoopError);
-        }
-        let (instruction, value) = match code.get(instruction_pointer as usize) {
-            None => return Ok(accumulator),
-            Some(v) => v,
-        };
-        visitied_instructions.insert(instruction_pointer);
-        match instruction.as_str() {
-            "acc" => {
-                accumulator += value;
-                instruction_pointer += 1;
-            }
-            "jmp" => {
-                if value == &0 {
-                    //jmp 0 -_-
-                    instruction_pointer += 1;
-                } else {
-                    instruction_pointer = add(instruction_pointer, *value).unwrap();
-                }
-            }
-            "nop" => {
-                instruction_pointer += 1;
-            }
-            inst => panic!("inst: {:?} val: {}", inst, value),
+enum ExitError {
+    InfiniteLoop(Cycle),
+    Fault(Fault),
+    /// Stopped at an instruction that halts, instead of running off the end
+    Halted {
+        address: usize,
+    },
+    /// Neither terminated nor looped within the step limit
+    OutOfSteps {
+        steps: u64,
+    },
+    /// None of the programs with one instruction flipped terminates
+    NoFlip,
+}
+
+impl fmt::Display for ExitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InfiniteLoop(cycle) => write!(
+                f,
+                "loops forever through {} instructions from {}",
+                cycle.addresses.len(),
+                cycle.entry()
+            ),
+            Self::Fault(fault) => fault.fmt(f),
+            Self::Halted { address } => write!(f, "halts at {} instead of terminating", address),
+            Self::OutOfSteps { steps } => write!(f, "still running after {} steps", steps),
+            Self::NoFlip => write!(f, "no flip makes the program terminate"),
         }
     }
 }
 
+impl Error for ExitError {}
+
+fn get_exit_code(program: &Program) -> Result<i64, ExitError> {
+    exit_code(&mut Machine::new(program.clone()))
+}
+
+/// The accumulator once `machine` runs off the end of its code, whatever table and step limit
+/// it has
+fn exit_code(machine: &mut Machine) -> Result<i64, ExitError> {
+    match machine.run() {
+        Ok(Exit::Terminated) => Ok(machine.acc()),
+        Ok(Exit::Loop(cycle)) => Err(ExitError::InfiniteLoop(cycle)),
+        Ok(Exit::Halted) => Err(ExitError::Halted {
+            address: machine.ip(),
+        }),
+        Ok(Exit::OutOfSteps) => Err(ExitError::OutOfSteps {
+            steps: machine.steps(),
+        }),
+        Err(fault) => Err(ExitError::Fault(fault)),
+    }
+}
+
+pub struct Day8;
+
+impl Solver for Day8 {
+    type Input = Program;
+    const DAY: u32 = 8;
+
+    fn parse(&self, input: &str) -> Result<Program, ParseError> {
+        parse(input)
+    }
+
+    fn part1(&self, input: &Program) -> Solution {
+        Ok(part1(input)?.to_string())
+    }
+
+    fn part2(&self, input: &Program) -> Option<Solution> {
+        Some(part2(input).map(|acc| acc.to_string()).map_err(Into::into))
+    }
+
+    fn variants(&self) -> Vec<Variant<Program>> {
+        vec![
+            Variant::new(Part::One, "threaded", |input| {
+                Ok(part1_threaded(input)?.to_string())
+            }),
+            Variant::new(Part::Two, "brute_force", |input| {
+                Ok(part2_try_every_mutation(input)?.to_string())
+            }),
+            Variant::new(Part::Two, "search", |input| {
+                Ok(part2_search(input)?.to_string())
+            }),
+            Variant::new(Part::Two, "brute_force_threaded", |input| {
+                Ok(part2_brute_force_threaded(input)?.to_string())
+            }),
+        ]
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::answers::expected;
+    use crate::vm::asm::assemble;
+    use crate::vm::opcode::OpcodeTable;
+    use crate::vm::testing::LOOPING;
+
+    fn program(code: &str) -> Program {
+        code.parse().unwrap()
+    }
 
     #[test]
     fn verify_part1() {
         let input = include_str!("../input/2020/day8.txt");
-        assert_eq!(part1(input), 1137);
+        assert_eq!(
+            part1(&parse(input).unwrap()).unwrap().to_string(),
+            expected(8, Part::One)
+        );
     }
 
     #[test]
     fn verify_part2() {
         let input = include_str!("../input/2020/day8.txt");
-        assert_eq!(part2(input), 1125);
+        assert_eq!(
+            part2(&parse(input).unwrap()).map(|acc| acc.to_string()),
+            Ok(expected(8, Part::Two))
+        );
+    }
+
+    #[test]
+    fn verify_threaded() {
+        let program = parse(include_str!("../input/2020/day8.txt")).unwrap();
+
+        assert_eq!(
+            part1_threaded(&program).unwrap().to_string(),
+            expected(8, Part::One)
+        );
+        assert_eq!(
+            part2_brute_force_threaded(&program).unwrap().to_string(),
+            expected(8, Part::Two)
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = parse("nop +0\nacc +1\njmp four").unwrap_err();
+        assert_eq!((error.line, error.column), (Some(3), Some(5)));
+        assert_eq!(error.expected, "i32");
+
+        let error = parse("nop +0\nxyz +3").unwrap_err();
+        assert_eq!((error.line, error.column), (Some(2), Some(1)));
     }
 
     #[test]
     fn test_detect_infinite_loop() {
-        let infinite_loop = vec![
-            ("nop", 0),
-            ("acc", 1),
-            ("jmp", 4),
-            ("acc", 3),
-            ("jmp", -3),
-            ("acc", -99),
-            ("acc", 1),
-            ("jmp", -4),
-            ("acc", 6),
-        ]
-        .into_iter()
-        .map(|(s, i)| (String::from(s), i))
-        .collect::<Vec<_>>();
+        let infinite_loop = program(LOOPING);
+
+        match get_exit_code(&infinite_loop) {
+            Err(ExitError::InfiniteLoop(cycle)) => {
+                assert_eq!(cycle.addresses, vec![1, 2, 6, 7, 3, 4])
+            }
+            other => panic!("expected an infinite loop, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_repair_assembled_program() {
+        let input = assemble(
+            "
+                nop +0
+            again:
+                acc +1
+                jmp skip
+            back:
+                acc +3
+                jmp again
+                acc -99
+            skip:
+                acc +1
+                jmp back    ; the corrupted instruction, should be a nop
+                acc +6
+            ",
+        )
+        .unwrap();
 
-        assert_eq!(get_exit_code(&infinite_loop), Err(InfiniteLoopError));
+        assert_eq!(input, program(LOOPING));
+        assert_eq!(part2(&input), Ok(8));
     }
 
     #[test]
     fn test_detect_correct_exit_value() {
-        let code = vec![
-            ("nop", 0),
-            ("acc", 1),
-            ("jmp", 4),
-            ("acc", 3),
-            ("jmp", -3),
-            ("acc", -99),
-            ("acc", 1),
-            ("nop", -4),
-            ("acc", 6),
-        ]
-        .into_iter()
-        .map(|(s, i)| (String::from(s), i))
-        .collect::<Vec<_>>();
+        let code =
+            program("nop +0\nacc +1\njmp +4\nacc +3\njmp -3\nacc -99\nacc +1\nnop -4\nacc +6");
 
         assert_eq!(get_exit_code(&code), Ok(8));
     }
 
+    #[test]
+    fn test_jump_out_of_bounds_is_an_error() {
+        let code = program("nop +0\njmp -2");
+
+        assert!(matches!(
+            part1(&code),
+            Err(Fault::JumpOutOfBounds { address: 1, .. })
+        ));
+        assert!(matches!(get_exit_code(&code), Err(ExitError::Fault(_))));
+    }
+
+    #[test]
+    fn test_no_flip_is_an_error() {
+        // the first two jumps loop whichever is flipped, and the last is never reached
+        let code = program("jmp +1\njmp -1\njmp -1");
+
+        assert_eq!(part2(&code), Err(ExitError::NoFlip));
+        assert_eq!(part2_try_every_mutation(&code), Err(ExitError::NoFlip));
+        assert_eq!(part2_brute_force_threaded(&code), Err(ExitError::NoFlip));
+        assert_eq!(part2_search(&code), Err(ExitError::NoFlip));
+        assert_eq!(
+            get_exit_code(&code).unwrap_err().to_string(),
+            "loops forever through 2 instructions from 0"
+        );
+    }
+
+    #[test]
+    fn test_halting_and_running_out_of_steps_are_errors() {
+        let mut machine = Machine::load(OpcodeTable::extended(), "acc +1\nhlt\nacc +1").unwrap();
+        assert_eq!(
+            exit_code(&mut machine),
+            Err(ExitError::Halted { address: 1 })
+        );
+
+        let mut machine = Machine::new(program(LOOPING)).step_limit(3);
+        let error = exit_code(&mut machine).unwrap_err();
+        assert_eq!(error, ExitError::OutOfSteps { steps: 3 });
+        assert_eq!(error.to_string(), "still running after 3 steps");
+    }
+
     #[test]
     fn test_generate_mutations() {
-        let input = vec![("nop", 1), ("acc", 2), ("jmp", 3)]
-            .into_iter()
-            .map(|(s, i)| (String::from(s), i))
-            .collect::<Vec<_>>();
+        let input = program("nop +1\nacc +2\njmp +3");
+
+        let expected = vec![
+            program("jmp +1\nacc +2\njmp +3"),
+            program("nop +1\nacc +2\nnop +3"),
+        ];
+
+        assert_eq!(generate_mutations(&input), expected);
+    }
+
+    #[test]
+    fn test_generate_mutations_skips_dead_code() {
+        let input = program("jmp +2\nnop +5\nacc +1\njmp -3");
 
         let expected = vec![
-            vec![("jmp", 1), ("acc", 2), ("jmp", 3)]
-                .into_iter()
-                .map(|(s, i)| (String::from(s), i))
-                .collect::<Vec<_>>(),
-            vec![("nop", 1), ("acc", 2), ("nop", 3)]
-                .into_iter()
-                .map(|(s, i)| (String::from(s), i))
-                .collect::<Vec<_>>(),
+            program("nop +2\nnop +5\nacc +1\njmp -3"),
+            program("jmp +2\nnop +5\nacc +1\nnop -3"),
         ];
 
         assert_eq!(generate_mutations(&input), expected);
@@ -193,37 +368,24 @@ mod tests {
 
     #[test]
     fn test_part2() {
-        let input = vec![("nop", 1), ("acc", 2), ("jmp", -1)]
-            .into_iter()
-            .map(|(s, i)| (String::from(s), i))
-            .collect::<Vec<_>>();
+        let input = program("nop +1\nacc +2\njmp -1");
 
-        assert_eq!(part2_inner(&input), Ok(2));
+        assert_eq!(part2(&input), Ok(2));
+        assert_eq!(part2_try_every_mutation(&input), Ok(2));
 
-        let input = vec![("nop", 1), ("acc", 2), ("nop", 3), ("jmp", -1), ("jmp", -1)]
-            .into_iter()
-            .map(|(s, i)| (String::from(s), i))
-            .collect::<Vec<_>>();
+        let input = program("nop +1\nacc +2\nnop +3\njmp -1\njmp -1");
 
-        assert_eq!(part2_inner(&input), Ok(2));
+        assert_eq!(part2(&input), Ok(2));
+        assert_eq!(part2_try_every_mutation(&input), Ok(2));
     }
     #[test]
     fn test_part22() {
-        let input = vec![
-            ("nop", 0),
-            ("acc", 1),
-            ("jmp", 4),
-            ("acc", 3),
-            ("jmp", -3),
-            ("acc", -99),
-            ("acc", 1),
-            ("nop", -4),
-            ("acc", 6),
-        ]
-        .into_iter()
-        .map(|(s, i)| (String::from(s), i))
-        .collect::<Vec<_>>();
+        let input =
+            program("nop +0\nacc +1\njmp +4\nacc +3\njmp -3\nacc -99\nacc +1\nnop -4\nacc +6");
 
-        assert_eq!(part2_inner(&input), Ok(8));
+        assert_eq!(part2(&input), Ok(8));
+        assert_eq!(part2_try_every_mutation(&input), Ok(8));
+        assert_eq!(part2_brute_force_threaded(&input), Ok(8));
+        assert_eq!(part2_search(&input), Ok(8));
     }
 }
@@ -1,13 +1,17 @@
+use crate::error::ParseError;
+use crate::solver::{Solution, Solver};
+use crate::util::{parse_lines, try_parse};
 use std::cmp;
 use std::collections::VecDeque;
 
+#[aoc_generator(day9)]
+fn parse(input: &str) -> Result<Vec<usize>, ParseError> {
+    parse_lines(input, try_parse)
+}
+
 #[aoc(day9, part1)]
-fn part1(input: &str) -> usize {
-    let input = input
-        .lines()
-        .map(|line| line.parse().unwrap())
-        .collect::<Vec<usize>>();
-    find_first_outlier(&input, 25)
+fn part1(input: &[usize]) -> usize {
+    find_first_outlier(input, 25)
 }
 
 fn find_first_outlier(input: &[usize], preamble_size: usize) -> usize {
@@ -54,13 +58,9 @@ fn check_sum(buffer: &VecDeque<usize>, xmas: usize) -> bool {
 }
 
 #[aoc(day9, part2)]
-fn part2(input: &str) -> usize {
-    let xmas = 22_477_624;
-    let input = input
-        .lines()
-        .map(|line| line.parse().unwrap())
-        .collect::<Vec<usize>>();
-    find_smallest_and_largest_from_cont_set(&input, xmas)
+fn part2(input: &[usize]) -> usize {
+    let xmas = find_first_outlier(input, 25);
+    find_smallest_and_largest_from_cont_set(input, xmas)
 }
 
 fn find_smallest_and_largest_from_cont_set(buffer: &[usize], sum: usize) -> usize {
@@ -86,6 +86,25 @@ fn find_smallest_and_largest_from_cont_set(buffer: &[usize], sum: usize) -> usiz
     panic!()
 }
 
+pub struct Day9;
+
+impl Solver for Day9 {
+    type Input = Vec<usize>;
+    const DAY: u32 = 9;
+
+    fn parse(&self, input: &str) -> Result<Vec<usize>, ParseError> {
+        parse(input)
+    }
+
+    fn part1(&self, input: &Vec<usize>) -> Solution {
+        Ok(part1(input).to_string())
+    }
+
+    fn part2(&self, input: &Vec<usize>) -> Option<Solution> {
+        Some(Ok(part2(input).to_string()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::VecDeque;
@@ -0,0 +1,93 @@
+//! Differential testing of solution variants: for any well-formed input every variant of a part
+//! has to give the same answer as the main solution. The inputs come from proptest strategies
+//! next to each day's tests, so a disagreement is shrunk down to a minimal input
+
+use proptest::prelude::*;
+use proptest::test_runner::TestCaseError;
+
+use crate::solver::{DynSolver, Part};
+
+/// Fails the test case if any variant of `solver` disagrees with the main solution on `input`
+pub fn assert_variants_agree(solver: &dyn DynSolver, input: &str) -> Result<(), TestCaseError> {
+    let parsed = solver
+        .parse_boxed(input)
+        .map_err(|e| TestCaseError::fail(format!("generated input didn't parse: {}", e)))?;
+
+    for part in &Part::BOTH {
+        // variants may explain a missing answer differently, but not give a different one
+        let expected = solver.solve(parsed.as_ref(), *part, None).map(Result::ok);
+        for variant in solver.variant_names(*part) {
+            let actual = solver
+                .solve(parsed.as_ref(), *part, Some(variant))
+                .map(Result::ok);
+            prop_assert_eq!(
+                &actual,
+                &expected,
+                "day {} part {} variant {:?} disagrees with the main solution",
+                solver.day(),
+                part,
+                variant
+            );
+        }
+    }
+    Ok(())
+}
+
+/// `lines` joined the way puzzle inputs are, without a trailing newline
+pub fn lines<S>(lines: S, len: std::ops::Range<usize>) -> impl Strategy<Value = String>
+where
+    S: Strategy<Value = String>,
+{
+    prop::collection::vec(lines, len).prop_map(|lines| lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ParseError;
+    use crate::solver::{Solution, Solver, Variant};
+    use crate::util::{parse_lines, try_parse};
+    use proptest::test_runner::{Config, TestError, TestRunner};
+
+    /// Sums numbers, with a variant that forgets everything after the third one
+    struct Sum;
+
+    impl Solver for Sum {
+        type Input = Vec<u32>;
+        const DAY: u32 = 26;
+
+        fn parse(&self, input: &str) -> Result<Vec<u32>, ParseError> {
+            parse_lines(input, try_parse)
+        }
+
+        fn part1(&self, input: &Vec<u32>) -> Solution {
+            Ok(input.iter().sum::<u32>().to_string())
+        }
+
+        fn part2(&self, input: &Vec<u32>) -> Option<Solution> {
+            Some(Ok(input.len().to_string()))
+        }
+
+        fn variants(&self) -> Vec<Variant<Vec<u32>>> {
+            vec![Variant::new(Part::One, "first_three", |input| {
+                Ok(input.iter().take(3).sum::<u32>().to_string())
+            })]
+        }
+    }
+
+    #[test]
+    fn test_divergence_is_shrunk_to_a_minimal_input() {
+        let mut runner = TestRunner::new(Config {
+            failure_persistence: None,
+            ..Config::default()
+        });
+        let numbers = lines((0..100_u32).prop_map(|n| n.to_string()), 0..20);
+
+        let result = runner.run(&numbers, |input| assert_variants_agree(&Sum, &input));
+
+        match result {
+            Err(TestError::Fail(_, input)) => assert_eq!(input, "0\n0\n0\n1"),
+            other => panic!("expected the variant to be caught, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use std::any::type_name;
+use std::error::Error;
+use std::fmt;
+
+///
+/// Error for puzzle input that doesn't look the way a day expects it to.
+/// Positions are 1-based, like in an editor
+/// example
+/// ```
+/// # use advent_of_code_2020::error::ParseError;
+/// let error = ParseError::expected_type::<u32>("abc")
+///     .at_column(5)
+///     .at_line(3)
+///     .on_day(14);
+///
+/// assert_eq!(
+///     error.to_string(),
+///     "day 14, line 3, column 5: expected u32, found \"abc\""
+/// );
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseError {
+    pub day: Option<u32>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub expected: String,
+    pub found: String,
+}
+
+impl ParseError {
+    pub fn new(expected: impl Into<String>, found: impl Into<String>) -> Self {
+        Self {
+            day: None,
+            line: None,
+            column: None,
+            expected: expected.into(),
+            found: found.into(),
+        }
+    }
+
+    /// Error for a value that couldn't be parsed into a `T`
+    pub fn expected_type<T>(found: impl Into<String>) -> Self {
+        let name = type_name::<T>();
+        // "alloc::string::String" -> "String", but keep generics like "Vec<u32>" intact
+        let name = match name.find('<') {
+            Some(generics) => {
+                let (path, generics) = name.split_at(generics);
+                format!("{}{}", path.rsplit("::").next().unwrap_or(path), generics)
+            }
+            None => name.rsplit("::").next().unwrap_or(name).to_string(),
+        };
+        Self::new(name, found)
+    }
+
+    /// Sets the column, unless a more precise one is already known
+    pub fn at_column(mut self, column: usize) -> Self {
+        self.column.get_or_insert(column);
+        self
+    }
+
+    /// Moves the column right by `offset`, for errors found in a substring of the line
+    pub fn shifted(mut self, offset: usize) -> Self {
+        self.column = Some(self.column.unwrap_or(1) + offset);
+        self
+    }
+
+    /// Sets the line, unless a more precise one is already known
+    pub fn at_line(mut self, line: usize) -> Self {
+        self.line.get_or_insert(line);
+        self
+    }
+
+    /// Moves the line down by `offset`, for errors found in a section of the input
+    pub fn lines_shifted(mut self, offset: usize) -> Self {
+        self.line = Some(self.line.unwrap_or(1) + offset);
+        self
+    }
+
+    pub fn on_day(mut self, day: u32) -> Self {
+        self.day.get_or_insert(day);
+        self
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let position = [
+            self.day.map(|day| format!("day {}", day)),
+            self.line.map(|line| format!("line {}", line)),
+            self.column.map(|column| format!("column {}", column)),
+        ]
+        .iter()
+        .flatten()
+        .cloned()
+        .collect::<Vec<_>>();
+
+        if !position.is_empty() {
+            write!(f, "{}: ", position.join(", "))?;
+        }
+        write!(f, "expected {}, found {:?}", self.expected, self.found)
+    }
+}
+
+impl Error for ParseError {}
+
+///
+/// Error for puzzle input that parsed, but that a part has no answer for, like a day 8 program
+/// that jumps out of bounds
+/// example
+/// ```
+/// # use advent_of_code_2020::error::SolveError;
+/// let error = SolveError::new("jump to 12 out of bounds").on_day(8);
+///
+/// assert_eq!(error.to_string(), "day 8: jump to 12 out of bounds");
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SolveError {
+    pub day: Option<u32>,
+    pub message: String,
+}
+
+impl SolveError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            day: None,
+            message: message.into(),
+        }
+    }
+
+    pub fn on_day(mut self, day: u32) -> Self {
+        self.day.get_or_insert(day);
+        self
+    }
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(day) = self.day {
+            write!(f, "day {}: ", day)?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Error for SolveError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expected_type_names() {
+        assert_eq!(ParseError::expected_type::<String>("").expected, "String");
+        assert_eq!(ParseError::expected_type::<i32>("").expected, "i32");
+        assert_eq!(
+            ParseError::expected_type::<Vec<u64>>("").expected,
+            "Vec<u64>"
+        );
+    }
+
+    #[test]
+    fn test_innermost_position_wins() {
+        let error = ParseError::new("x", "y")
+            .at_column(4)
+            .shifted(10)
+            .at_column(1)
+            .at_line(2)
+            .at_line(7);
+
+        assert_eq!(error.line, Some(2));
+        assert_eq!(error.column, Some(14));
+        assert_eq!(
+            error.to_string(),
+            "line 2, column 14: expected x, found \"y\""
+        );
+    }
+}
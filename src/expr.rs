@@ -0,0 +1,233 @@
+use std::fmt;
+use std::mem;
+
+pub mod diagnostic;
+pub mod explain;
+pub mod lexer;
+pub mod number;
+pub mod parser;
+pub mod precedence;
+
+use number::{EvalError, Number};
+
+/// Where something is in a line, as byte offsets from `start` up to but not including `end`
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub const fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    /// The 1-based column the span starts at, like [`ParseError`](crate::error::ParseError) uses
+    pub const fn column(self) -> usize {
+        self.start + 1
+    }
+
+    /// From the start of this span to the end of `other`
+    pub const fn to(self, other: Self) -> Self {
+        Self::new(self.start, other.end)
+    }
+}
+
+/// A binary operator of the homework
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Operator {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    /// The remainder of dividing
+    Rem,
+    /// The left side to the power of the right side
+    Pow,
+}
+
+impl Operator {
+    pub const fn symbol(self) -> char {
+        match self {
+            Self::Add => '+',
+            Self::Sub => '-',
+            Self::Mul => '*',
+            Self::Div => '/',
+            Self::Rem => '%',
+            Self::Pow => '^',
+        }
+    }
+
+    pub const fn from_symbol(symbol: char) -> Option<Self> {
+        match symbol {
+            '+' => Some(Self::Add),
+            '-' => Some(Self::Sub),
+            '*' => Some(Self::Mul),
+            '/' => Some(Self::Div),
+            '%' => Some(Self::Rem),
+            '^' => Some(Self::Pow),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
+///
+/// An expression of the homework as a tree, every node knowing where in the line it came from.
+/// Parentheses are kept as [`Expr::Group`], so it prints the way it was written
+/// example
+/// ```
+/// # use advent_of_code_2020::expr::parser::parse;
+/// # use advent_of_code_2020::expr::precedence::OperatorTable;
+/// let expr = parse("2 *  (3 + 4)", &OperatorTable::left_to_right()).unwrap();
+///
+/// assert_eq!(expr.evaluate::<u64>(), Ok(14));
+/// assert_eq!(expr.to_string(), "2 * (3 + 4)");
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Expr {
+    Number {
+        value: u64,
+        span: Span,
+    },
+    Binary {
+        operator: Operator,
+        left: Box<Expr>,
+        right: Box<Expr>,
+        span: Span,
+    },
+    /// An expression in parentheses, the span includes them
+    Group {
+        inner: Box<Expr>,
+        span: Span,
+    },
+}
+
+impl Expr {
+    pub fn binary(operator: Operator, left: Self, right: Self) -> Self {
+        let span = left.span().to(right.span());
+        Self::Binary {
+            operator,
+            left: Box::new(left),
+            right: Box::new(right),
+            span,
+        }
+    }
+
+    /// A node with nothing below it, to put in place of the parts of a tree that's taken apart
+    const fn leaf() -> Self {
+        Self::Number {
+            value: 0,
+            span: Span::new(0, 0),
+        }
+    }
+
+    /// Moves the operations and groups right below this node to `taken`, leaving leaves in their place
+    fn take_children(&mut self, taken: &mut Vec<Self>) {
+        fn take(child: &mut Expr, taken: &mut Vec<Expr>) {
+            if !matches!(child, Expr::Number { .. }) {
+                taken.push(mem::replace(child, Expr::leaf()));
+            }
+        }
+
+        match self {
+            Self::Binary { left, right, .. } => {
+                take(left, taken);
+                take(right, taken);
+            }
+            Self::Group { inner, .. } => take(inner, taken),
+            Self::Number { .. } => {}
+        }
+    }
+
+    pub const fn span(&self) -> Span {
+        match self {
+            Self::Number { span, .. } | Self::Binary { span, .. } | Self::Group { span, .. } => {
+                *span
+            }
+        }
+    }
+
+    /// The value of the expression in `N`, visiting every node once. The error is about the
+    /// innermost operation that has no value. Works on a stack of its own rather than recursing,
+    /// a line of many operations makes a tree as deep as it is long
+    pub fn evaluate<N: Number>(&self) -> Result<N, EvalError> {
+        let mut pending = vec![Visit::Enter(self)];
+        let mut values = vec![];
+        while let Some(visit) = pending.pop() {
+            match visit {
+                Visit::Enter(Self::Number { value, .. }) => values.push(N::from(*value)),
+                Visit::Enter(Self::Group { inner, .. }) => pending.push(Visit::Enter(inner)),
+                Visit::Enter(expr @ Self::Binary { left, right, .. }) => {
+                    pending.push(Visit::Leave(expr));
+                    pending.push(Visit::Enter(right));
+                    pending.push(Visit::Enter(left));
+                }
+                Visit::Leave(expr) => {
+                    let (operator, span) = match expr {
+                        Self::Binary { operator, span, .. } => (*operator, *span),
+                        _ => unreachable!("only operations are left after their operands"),
+                    };
+                    let right = values.pop().expect("the right operand was evaluated");
+                    let left = values.pop().expect("the left operand was evaluated");
+                    let value = N::apply(operator, &left, &right).map_err(|error| EvalError {
+                        line: None,
+                        span,
+                        error,
+                        expression: expr.to_string(),
+                        number: N::NAME,
+                    })?;
+                    values.push(value);
+                }
+            }
+        }
+        Ok(values.pop().expect("the expression was evaluated"))
+    }
+}
+
+/// A node of an [`Expr`] to go down into, or to come back to once everything below it is done
+enum Visit<'a> {
+    Enter(&'a Expr),
+    Leave(&'a Expr),
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut pending = vec![Visit::Enter(self)];
+        while let Some(visit) = pending.pop() {
+            match visit {
+                Visit::Enter(Self::Number { value, .. }) => write!(f, "{}", value)?,
+                Visit::Enter(expr @ Self::Binary { left, right, .. }) => {
+                    // leaving an operation writes its operator, between the two sides
+                    pending.push(Visit::Enter(right));
+                    pending.push(Visit::Leave(expr));
+                    pending.push(Visit::Enter(left));
+                }
+                Visit::Enter(expr @ Self::Group { inner, .. }) => {
+                    write!(f, "(")?;
+                    pending.push(Visit::Leave(expr));
+                    pending.push(Visit::Enter(inner));
+                }
+                Visit::Leave(Self::Binary { operator, .. }) => write!(f, " {} ", operator)?,
+                Visit::Leave(_) => write!(f, ")")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Expr {
+    /// Takes the tree apart one node at a time, dropping it the usual way recurses as deep as it is
+    fn drop(&mut self) {
+        let mut pending = vec![];
+        self.take_children(&mut pending);
+        while let Some(mut expr) = pending.pop() {
+            expr.take_children(&mut pending);
+        }
+    }
+}
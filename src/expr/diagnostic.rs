@@ -0,0 +1,141 @@
+use std::fmt;
+
+use crate::error::ParseError;
+use crate::expr::number::EvalError;
+use crate::expr::Span;
+
+///
+/// An error about one line, shown the way compilers do: the message, then the line with carets
+/// under the part it's about
+/// example
+/// ```
+/// # use advent_of_code_2020::expr::diagnostic::Diagnostic;
+/// # use advent_of_code_2020::expr::parser::parse;
+/// # use advent_of_code_2020::expr::precedence::OperatorTable;
+/// let line = "2 * (3 + 4))";
+/// let error = parse(line, &OperatorTable::left_to_right()).unwrap_err().at_line(2);
+///
+/// assert_eq!(
+///     Diagnostic::parse(&error, line).to_string(),
+///     "line 2, column 12: expected a matching '(', found \")\"
+///   2 | 2 * (3 + 4))
+///     |            ^"
+/// );
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Diagnostic {
+    pub line: usize,
+    /// The line the error is about
+    pub source: String,
+    /// What to put carets under
+    pub span: Span,
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// A caret under the column of `error`, on the line `source`
+    pub fn parse(error: &ParseError, source: &str) -> Self {
+        let start = error.column.unwrap_or(1) - 1;
+        let len = source
+            .get(start..)
+            .and_then(|rest| rest.chars().next())
+            .map_or(1, char::len_utf8);
+        Self {
+            line: error.line.unwrap_or(1),
+            source: source.to_string(),
+            span: Span::new(start, start + len),
+            message: format!("expected {}, found {:?}", error.expected, error.found),
+        }
+    }
+
+    /// Carets under the whole operation of `error`, on the line `source`
+    pub fn eval(error: &EvalError, source: &str) -> Self {
+        Self {
+            line: error.line.unwrap_or(1),
+            source: source.to_string(),
+            span: error.span,
+            message: error.message(),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // columns count characters, the span counts bytes
+        let width = |bytes: &str| bytes.chars().count();
+        let start = self.span.start.min(self.source.len());
+        let end = self.span.end.clamp(start, self.source.len());
+        let padding = self.source.get(..start).map_or(start, width);
+        let carets = self
+            .source
+            .get(start..end)
+            .map_or(end - start, width)
+            .max(1);
+
+        let number = self.line.to_string();
+        writeln!(
+            f,
+            "line {}, column {}: {}",
+            self.line,
+            padding + 1,
+            self.message
+        )?;
+        writeln!(f, "{:>w$} | {}", number, self.source, w = number.len() + 2)?;
+        write!(
+            f,
+            "{:>w$} | {}{}",
+            "",
+            " ".repeat(padding),
+            "^".repeat(carets),
+            w = number.len() + 2
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::parser::parse;
+    use crate::expr::precedence::OperatorTable;
+
+    #[test]
+    fn test_eval_errors_underline_the_operation() {
+        let line = "1 + (4294967296 * 4294967296)";
+        let error = parse(line, &OperatorTable::left_to_right())
+            .unwrap()
+            .evaluate::<u64>()
+            .unwrap_err()
+            .at_line(13);
+
+        assert_eq!(
+            Diagnostic::eval(&error, line).to_string(),
+            "line 13, column 6: 4294967296 * 4294967296 overflows u64
+  13 | 1 + (4294967296 * 4294967296)
+     |      ^^^^^^^^^^^^^^^^^^^^^^^"
+        );
+    }
+
+    #[test]
+    fn test_end_of_the_line() {
+        let line = "(1 +";
+        let error = parse(line, &OperatorTable::left_to_right()).unwrap_err();
+
+        assert_eq!(
+            Diagnostic::parse(&error, line).to_string(),
+            "line 1, column 5: expected a number or '(', found \"nothing\"
+  1 | (1 +
+    |     ^"
+        );
+    }
+
+    #[test]
+    fn test_columns_count_characters() {
+        let line = "1 × 2";
+        let error = parse(line, &OperatorTable::left_to_right()).unwrap_err();
+
+        assert_eq!(
+            Diagnostic::parse(&error, line).to_string().lines().last(),
+            Some("    |   ^")
+        );
+    }
+}
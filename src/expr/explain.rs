@@ -0,0 +1,374 @@
+use std::fmt;
+
+use crate::expr::number::{EvalError, Number};
+use crate::expr::precedence::OperatorTable;
+use crate::expr::{Expr, Operator, Span};
+
+///
+/// How an expression is grouped and evaluated: every operation in parentheses, in reverse Polish
+/// notation, and the expression after every single operation, like the puzzle text shows
+/// example
+/// ```
+/// # use advent_of_code_2020::expr::explain::Explanation;
+/// # use advent_of_code_2020::expr::parser::parse;
+/// # use advent_of_code_2020::expr::precedence::OperatorTable;
+/// let table = OperatorTable::addition_first();
+/// let expr = parse("1 + 2 * 3 + 4 * 5 + 6", &table).unwrap();
+///
+/// let explanation = Explanation::new::<u64>(&expr, &table).unwrap();
+/// assert_eq!(explanation.parenthesized, "((1 + 2) * (3 + 4)) * (5 + 6)");
+/// assert_eq!(explanation.rpn, "1 2 + 3 4 + * 5 6 + *");
+/// assert_eq!(
+///     explanation.steps,
+///     vec![
+///         "1 + 2 * 3 + 4 * 5 + 6",
+///         "3 * 3 + 4 * 5 + 6",
+///         "3 * 7 * 5 + 6",
+///         "3 * 7 * 11",
+///         "21 * 11",
+///         "231"
+///     ]
+/// );
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Explanation {
+    pub parenthesized: String,
+    pub rpn: String,
+    /// The expression as written, then after every operation, ending with its value
+    pub steps: Vec<String>,
+}
+
+impl Explanation {
+    /// Explains `expr` evaluated in `N`, `table` has to be the one it was parsed with
+    pub fn new<N: Number>(expr: &Expr, table: &OperatorTable) -> Result<Self, EvalError> {
+        Ok(Self {
+            parenthesized: parenthesized(expr),
+            rpn: rpn(expr),
+            steps: reductions::<N>(expr, table)?,
+        })
+    }
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "parenthesized: {}", self.parenthesized)?;
+        writeln!(f, "rpn: {}", self.rpn)?;
+        for (index, step) in self.steps.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            let arrow = if index == 0 { "  " } else { "->" };
+            write!(f, "{} {}", arrow, step)?;
+        }
+        Ok(())
+    }
+}
+
+/// `expr` with every operation but the outermost in parentheses, and none of its own
+pub fn parenthesized(expr: &Expr) -> String {
+    fn write(expr: &Expr, outermost: bool, out: &mut String) {
+        match expr {
+            Expr::Number { value, .. } => out.push_str(&value.to_string()),
+            Expr::Binary {
+                operator,
+                left,
+                right,
+                ..
+            } => {
+                if !outermost {
+                    out.push('(');
+                }
+                write(left, false, out);
+                out.push_str(&format!(" {} ", operator));
+                write(right, false, out);
+                if !outermost {
+                    out.push(')');
+                }
+            }
+            Expr::Group { inner, .. } => write(inner, outermost, out),
+        }
+    }
+
+    let mut out = String::new();
+    write(expr, true, &mut out);
+    out
+}
+
+/// `expr` in reverse Polish notation, every operator after its operands
+pub fn rpn(expr: &Expr) -> String {
+    fn write(expr: &Expr, out: &mut Vec<String>) {
+        match expr {
+            Expr::Number { value, .. } => out.push(value.to_string()),
+            Expr::Binary {
+                operator,
+                left,
+                right,
+                ..
+            } => {
+                write(left, out);
+                write(right, out);
+                out.push(operator.to_string());
+            }
+            Expr::Group { inner, .. } => write(inner, out),
+        }
+    }
+
+    let mut out = vec![];
+    write(expr, &mut out);
+    out.join(" ")
+}
+
+///
+/// `expr` after every single operation. The operation that binds tightest goes first, the
+/// leftmost of those if there are more, and parentheses go away once there's only a number in them
+pub fn reductions<N: Number>(expr: &Expr, table: &OperatorTable) -> Result<Vec<String>, EvalError> {
+    let mut node = Node::<N>::new(expr, table);
+    let mut steps = vec![node.to_string()];
+    loop {
+        node = node.collapsed();
+        let step = node.to_string();
+        if steps.last() != Some(&step) {
+            steps.push(step);
+        }
+        match node.tightest() {
+            Some(precedence) => node.reduce(precedence)?,
+            None => return Ok(steps),
+        };
+    }
+}
+
+/// An [`Expr`] that's partially evaluated in `N`
+enum Node<N> {
+    Value(N),
+    Binary {
+        operator: Operator,
+        precedence: u8,
+        left: Box<Node<N>>,
+        right: Box<Node<N>>,
+        span: Span,
+    },
+    Group(Box<Node<N>>),
+}
+
+impl<N: Number> Node<N> {
+    fn new(expr: &Expr, table: &OperatorTable) -> Self {
+        match expr {
+            Expr::Number { value, .. } => Self::Value(N::from(*value)),
+            Expr::Binary {
+                operator,
+                left,
+                right,
+                span,
+            } => Self::Binary {
+                operator: *operator,
+                precedence: table.get(*operator).map_or(0, |binding| binding.precedence),
+                left: Box::new(Self::new(left, table)),
+                right: Box::new(Self::new(right, table)),
+                span: *span,
+            },
+            Expr::Group { inner, .. } => Self::Group(Box::new(Self::new(inner, table))),
+        }
+    }
+
+    /// The precedence of the tightest binding operation on two numbers
+    fn tightest(&self) -> Option<u8> {
+        match self {
+            Self::Binary {
+                precedence,
+                left,
+                right,
+                ..
+            } => match (&**left, &**right) {
+                (Self::Value(_), Self::Value(_)) => Some(*precedence),
+                _ => left.tightest().max(right.tightest()),
+            },
+            Self::Group(inner) => inner.tightest(),
+            Self::Value(_) => None,
+        }
+    }
+
+    /// Evaluates the leftmost operation on two numbers with `precedence`, returns whether there
+    /// was one
+    fn reduce(&mut self, precedence: u8) -> Result<bool, EvalError> {
+        let value = match self {
+            Self::Binary {
+                operator,
+                precedence: own,
+                left,
+                right,
+                span,
+            } => match (&**left, &**right) {
+                (Self::Value(a), Self::Value(b)) if *own == precedence => N::apply(*operator, a, b)
+                    .map_err(|error| EvalError {
+                        line: None,
+                        span: *span,
+                        error,
+                        expression: format!("{} {} {}", a, operator, b),
+                        number: N::NAME,
+                    })?,
+                _ => return Ok(left.reduce(precedence)? || right.reduce(precedence)?),
+            },
+            Self::Group(inner) => return inner.reduce(precedence),
+            Self::Value(_) => return Ok(false),
+        };
+        *self = Self::Value(value);
+        Ok(true)
+    }
+
+    /// Without the parentheses around single numbers
+    fn collapsed(self) -> Self {
+        match self {
+            Self::Binary {
+                operator,
+                precedence,
+                left,
+                right,
+                span,
+            } => Self::Binary {
+                operator,
+                precedence,
+                left: Box::new(left.collapsed()),
+                right: Box::new(right.collapsed()),
+                span,
+            },
+            Self::Group(inner) => match inner.collapsed() {
+                value @ Self::Value(_) => value,
+                inner => Self::Group(Box::new(inner)),
+            },
+            Self::Value(_) => self,
+        }
+    }
+}
+
+impl<N: Number> fmt::Display for Node<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Value(value) => write!(f, "{}", value),
+            Self::Binary {
+                operator,
+                left,
+                right,
+                ..
+            } => write!(f, "{} {} {}", left, operator, right),
+            Self::Group(inner) => write!(f, "({})", inner),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::number::ArithmeticError;
+    use crate::expr::parser::parse;
+    use proptest::prelude::*;
+
+    fn steps(line: &str, table: &OperatorTable) -> Vec<String> {
+        reductions::<u64>(&parse(line, table).unwrap(), table).unwrap()
+    }
+
+    #[test]
+    fn test_puzzle_examples() {
+        assert_eq!(
+            steps(
+                "1 + (2 * 3) + (4 * (5 + 6))",
+                &OperatorTable::left_to_right()
+            ),
+            vec![
+                "1 + (2 * 3) + (4 * (5 + 6))",
+                "1 + 6 + (4 * (5 + 6))",
+                "7 + (4 * (5 + 6))",
+                "7 + (4 * 11)",
+                "7 + 44",
+                "51"
+            ]
+        );
+        assert_eq!(
+            steps("1 + 2 * 3 + 4 * 5 + 6", &OperatorTable::left_to_right()),
+            vec![
+                "1 + 2 * 3 + 4 * 5 + 6",
+                "3 * 3 + 4 * 5 + 6",
+                "9 + 4 * 5 + 6",
+                "13 * 5 + 6",
+                "65 + 6",
+                "71"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parentheses_around_numbers_go_away() {
+        let table = OperatorTable::left_to_right();
+
+        assert_eq!(steps("((4))", &table), vec!["((4))", "4"]);
+        assert_eq!(steps("((4 + 1))", &table), vec!["((4 + 1))", "5"]);
+    }
+
+    #[test]
+    fn test_renderings_follow_the_table() {
+        let table = OperatorTable::arithmetic();
+        let expr = parse("2 ^ 3 ^ 2 - (1 + 1) * 3 % 4", &table).unwrap();
+
+        assert_eq!(parenthesized(&expr), "(2 ^ (3 ^ 2)) - (((1 + 1) * 3) % 4)");
+        assert_eq!(rpn(&expr), "2 3 2 ^ ^ 1 1 + 3 * 4 % -");
+        assert_eq!(parenthesized(&parse("(7)", &table).unwrap()), "7");
+    }
+
+    #[test]
+    fn test_errors_point_at_the_operation() {
+        let table = OperatorTable::arithmetic();
+        let line = "1 + 2 * (3 - 3 * 1) + 4 / (2 - 2)";
+        let expr = parse(line, &table).unwrap();
+
+        let error = reductions::<u64>(&expr, &table).unwrap_err();
+        assert_eq!(error.error, ArithmeticError::DivisionByZero);
+        assert_eq!(error.expression, "4 / 0");
+        assert_eq!(&line[error.span.start..error.span.end], "4 / (2 - 2)");
+    }
+
+    #[test]
+    fn test_display() {
+        let table = OperatorTable::left_to_right();
+        let explanation =
+            Explanation::new::<u64>(&parse("2 * (3 + 4)", &table).unwrap(), &table).unwrap();
+
+        assert_eq!(
+            explanation.to_string(),
+            "parenthesized: 2 * (3 + 4)\nrpn: 2 3 4 + *\n   2 * (3 + 4)\n-> 2 * 7\n-> 14"
+        );
+    }
+
+    fn expr() -> impl Strategy<Value = String> {
+        let number = (0..100_u64).prop_map(|number| number.to_string());
+        number.prop_recursive(5, 30, 2, |inner| {
+            prop_oneof![
+                (
+                    inner.clone(),
+                    prop::sample::select(vec!['+', '*', '-']),
+                    inner.clone()
+                )
+                    .prop_map(|(left, operator, right)| format!("{} {} {}", left, operator, right)),
+                inner.prop_map(|inner| format!("({})", inner)),
+            ]
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn test_every_step_has_the_same_value(line in expr()) {
+            let table = OperatorTable::arithmetic();
+            let expr = parse(&line, &table).unwrap();
+
+            let steps = reductions::<i128>(&expr, &table).unwrap();
+
+            let value = expr.evaluate::<i128>().unwrap();
+            prop_assert_eq!(steps.last(), Some(&value.to_string()));
+            for step in &steps {
+                // negative numbers can't be written, so only check the steps that can be parsed
+                if let Ok(reparsed) = parse(step, &table) {
+                    prop_assert_eq!(reparsed.evaluate::<i128>(), Ok(value));
+                }
+            }
+            prop_assert_eq!(parenthesized(&parse(&parenthesized(&expr), &table).unwrap()), parenthesized(&expr));
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use crate::error::ParseError;
+use crate::expr::{Operator, Span};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TokenKind {
+    Number(u64),
+    Operator(Operator),
+    Open,
+    Close,
+}
+
+/// A number, operator or parenthesis and where it is in the line
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Span,
+}
+
+///
+/// Splits a line of homework into tokens, skipping whitespace. Numbers can have any number of
+/// digits as long as they fit a `u64`, operators are any of `+ - * / % ^`
+/// example
+/// ```
+/// # use advent_of_code_2020::expr::lexer::{tokenize, TokenKind};
+/// # use advent_of_code_2020::expr::{Operator, Span};
+/// let tokens = tokenize("12 *(3)").unwrap();
+///
+/// let kinds = tokens.iter().map(|token| token.kind).collect::<Vec<_>>();
+/// assert_eq!(
+///     kinds,
+///     vec![
+///         TokenKind::Number(12),
+///         TokenKind::Operator(Operator::Mul),
+///         TokenKind::Open,
+///         TokenKind::Number(3),
+///         TokenKind::Close
+///     ]
+/// );
+/// assert_eq!(tokens[0].span, Span::new(0, 2));
+/// ```
+pub fn tokenize(line: &str) -> Result<Vec<Token>, ParseError> {
+    let mut tokens = vec![];
+    let mut chars = line.char_indices().peekable();
+    while let Some((start, char)) = chars.next() {
+        let kind = match char {
+            '(' => TokenKind::Open,
+            ')' => TokenKind::Close,
+            '0'..='9' => {
+                let mut end = start + 1;
+                while let Some((index, _)) = chars.next_if(|(_, char)| char.is_ascii_digit()) {
+                    end = index + 1;
+                }
+                let digits = &line[start..end];
+                let number = digits.parse().map_err(|_| {
+                    ParseError::new("a number that fits u64", digits).at_column(start + 1)
+                })?;
+                tokens.push(Token {
+                    kind: TokenKind::Number(number),
+                    span: Span::new(start, end),
+                });
+                continue;
+            }
+            _ if char.is_whitespace() => continue,
+            _ => match Operator::from_symbol(char) {
+                Some(operator) => TokenKind::Operator(operator),
+                None => {
+                    return Err(ParseError::new("a digit, an operator, '(' or ')'", char)
+                        .at_column(start + 1))
+                }
+            },
+        };
+        tokens.push(Token {
+            kind,
+            span: Span::new(start, start + char.len_utf8()),
+        });
+    }
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spans() {
+        let spans = tokenize(" 123+ (45)")
+            .unwrap()
+            .iter()
+            .map(|token| (token.span.start, token.span.end))
+            .collect::<Vec<_>>();
+
+        assert_eq!(spans, vec![(1, 4), (4, 5), (6, 7), (7, 9), (9, 10)]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = tokenize("2 & 3").unwrap_err();
+        assert_eq!((error.column, error.found.as_str()), (Some(3), "&"));
+
+        let error = tokenize("1 + 99999999999999999999").unwrap_err();
+        assert_eq!(
+            (error.column, error.expected.as_str()),
+            (Some(5), "a number that fits u64")
+        );
+    }
+}
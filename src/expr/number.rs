@@ -0,0 +1,254 @@
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+
+use num_bigint::BigInt;
+use num_traits::{Signed, Zero};
+
+use crate::expr::{Operator, Span};
+
+/// Why an operation has no value
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ArithmeticError {
+    /// The result doesn't fit the number type, this includes negative results of unsigned types
+    Overflow,
+    DivisionByZero,
+    NegativeExponent,
+}
+
+///
+/// An operation of an expression that has no value, with the span and text of the operation
+/// example
+/// ```
+/// # use advent_of_code_2020::expr::number::ArithmeticError;
+/// # use advent_of_code_2020::expr::parser::parse;
+/// # use advent_of_code_2020::expr::precedence::OperatorTable;
+/// # use advent_of_code_2020::expr::Span;
+/// let expr = parse("1 + (4294967296 * 4294967296)", &OperatorTable::left_to_right()).unwrap();
+///
+/// let error = expr.evaluate::<u64>().unwrap_err();
+/// assert_eq!((error.error, error.span), (ArithmeticError::Overflow, Span::new(5, 28)));
+/// assert_eq!(
+///     error.to_string(),
+///     "column 6: 4294967296 * 4294967296 overflows u64"
+/// );
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct EvalError {
+    pub line: Option<usize>,
+    pub span: Span,
+    pub error: ArithmeticError,
+    /// The operation, written out
+    pub expression: String,
+    /// The number type it was evaluated in
+    pub number: &'static str,
+}
+
+impl EvalError {
+    /// Sets the line, unless it's already known
+    pub fn at_line(mut self, line: usize) -> Self {
+        self.line.get_or_insert(line);
+        self
+    }
+
+    /// What went wrong, without where
+    pub fn message(&self) -> String {
+        match self.error {
+            ArithmeticError::Overflow => format!("{} overflows {}", self.expression, self.number),
+            ArithmeticError::DivisionByZero => format!("{} divides by zero", self.expression),
+            ArithmeticError::NegativeExponent => {
+                format!("{} has a negative exponent", self.expression)
+            }
+        }
+    }
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "line {}, ", line)?;
+        }
+        write!(f, "column {}: {}", self.span.column(), self.message())
+    }
+}
+
+impl Error for EvalError {}
+
+///
+/// A number type expressions can be evaluated in. The fixed size ones check every operation,
+/// [`BigInt`] only fails on dividing by zero and negative exponents
+/// example
+/// ```
+/// # use advent_of_code_2020::expr::number::{ArithmeticError, Number};
+/// # use advent_of_code_2020::expr::Operator;
+/// # use num_bigint::BigInt;
+/// assert_eq!(u64::apply(Operator::Sub, &2, &3), Err(ArithmeticError::Overflow));
+/// assert_eq!(i128::apply(Operator::Sub, &2, &3), Ok(-1));
+///
+/// let big = BigInt::apply(Operator::Pow, &BigInt::from(2), &BigInt::from(100)).unwrap();
+/// assert_eq!(big.to_string(), "1267650600228229401496703205376");
+/// ```
+pub trait Number: From<u64> + fmt::Display {
+    /// How it's called in errors
+    const NAME: &'static str;
+
+    fn apply(operator: Operator, left: &Self, right: &Self) -> Result<Self, ArithmeticError>;
+}
+
+macro_rules! checked_number {
+    ($type:ty) => {
+        impl Number for $type {
+            const NAME: &'static str = stringify!($type);
+
+            #[allow(unused_comparisons)]
+            fn apply(
+                operator: Operator,
+                left: &Self,
+                right: &Self,
+            ) -> Result<Self, ArithmeticError> {
+                let (left, right) = (*left, *right);
+                let result = match operator {
+                    Operator::Add => left.checked_add(right),
+                    Operator::Sub => left.checked_sub(right),
+                    Operator::Mul => left.checked_mul(right),
+                    Operator::Div | Operator::Rem if right == 0 => {
+                        return Err(ArithmeticError::DivisionByZero)
+                    }
+                    Operator::Div => left.checked_div(right),
+                    Operator::Rem => left.checked_rem(right),
+                    Operator::Pow if right < 0 => return Err(ArithmeticError::NegativeExponent),
+                    Operator::Pow => u32::try_from(right)
+                        .ok()
+                        .and_then(|exponent| left.checked_pow(exponent)),
+                };
+                result.ok_or(ArithmeticError::Overflow)
+            }
+        }
+    };
+}
+
+checked_number!(u64);
+checked_number!(u128);
+checked_number!(i128);
+
+impl Number for BigInt {
+    const NAME: &'static str = "BigInt";
+
+    fn apply(operator: Operator, left: &Self, right: &Self) -> Result<Self, ArithmeticError> {
+        match operator {
+            Operator::Add => Ok(left + right),
+            Operator::Sub => Ok(left - right),
+            Operator::Mul => Ok(left * right),
+            Operator::Div | Operator::Rem if right.is_zero() => {
+                Err(ArithmeticError::DivisionByZero)
+            }
+            Operator::Div => Ok(left / right),
+            Operator::Rem => Ok(left % right),
+            Operator::Pow if right.is_negative() => Err(ArithmeticError::NegativeExponent),
+            // an exponent past u32 would take more memory than there is
+            Operator::Pow => u32::try_from(right)
+                .map(|exponent| left.pow(exponent))
+                .map_err(|_| ArithmeticError::Overflow),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::parser::parse;
+    use crate::expr::precedence::OperatorTable;
+    use crate::expr::Expr;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_overflow_points_at_the_operation() {
+        let line = "2 * (9999999999 * 9999999999 + 1)";
+        let expr = parse(line, &OperatorTable::left_to_right()).unwrap();
+
+        let error = expr.evaluate::<u64>().unwrap_err();
+        assert_eq!(
+            &line[error.span.start..error.span.end],
+            "9999999999 * 9999999999"
+        );
+        assert_eq!(error.number, "u64");
+
+        // i128 fits the product, but not once it's squared
+        assert_eq!(
+            expr.evaluate::<i128>().map(|value| value.to_string()),
+            expr.evaluate::<BigInt>().map(|value| value.to_string())
+        );
+        let squared = format!("({}) * ({})", line, line);
+        let expr = parse(&squared, &OperatorTable::left_to_right()).unwrap();
+        assert_eq!(
+            expr.evaluate::<i128>().unwrap_err().span,
+            Span::new(0, squared.len())
+        );
+        assert_eq!(
+            expr.evaluate::<BigInt>().unwrap().to_string(),
+            "39999999984000000003199999999680000000016"
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let table = OperatorTable::arithmetic();
+        let error = |line: &str| parse(line, &table).unwrap().evaluate::<i128>().unwrap_err();
+
+        let division = error("1 + 4 / (2 - 2)");
+        assert_eq!(division.error, ArithmeticError::DivisionByZero);
+        assert_eq!(
+            division.to_string(),
+            "column 5: 4 / (2 - 2) divides by zero"
+        );
+
+        let exponent = error("2 ^ (1 - 2)").at_line(7);
+        assert_eq!(exponent.error, ArithmeticError::NegativeExponent);
+        assert_eq!(
+            exponent.to_string(),
+            "line 7, column 1: 2 ^ (1 - 2) has a negative exponent"
+        );
+
+        assert_eq!(
+            u64::apply(Operator::Pow, &2, &(1 << 40)),
+            Err(ArithmeticError::Overflow)
+        );
+    }
+
+    fn expr() -> impl Strategy<Value = String> {
+        let number = (0..1_000_000_u64).prop_map(|number| number.to_string());
+        number.prop_recursive(6, 40, 2, |inner| {
+            prop_oneof![
+                (
+                    inner.clone(),
+                    prop::sample::select(vec!['+', '-', '*', '/', '%']),
+                    inner.clone(),
+                )
+                    .prop_map(|(left, operator, right)| format!(
+                        "({} {} {})",
+                        left, operator, right
+                    )),
+                // small exponents, so the big integers stay small enough to compute
+                (inner, 0..4_u32).prop_map(|(left, exponent)| format!("({} ^ {})", left, exponent)),
+            ]
+        })
+    }
+
+    fn evaluate<N: Number>(expr: &Expr) -> Option<String> {
+        expr.evaluate::<N>().ok().map(|value| value.to_string())
+    }
+
+    proptest! {
+        #[test]
+        fn test_backends_agree_when_they_fit(line in expr()) {
+            let expr = parse(&line, &OperatorTable::arithmetic()).unwrap();
+            let exact = evaluate::<BigInt>(&expr);
+
+            for value in [evaluate::<u64>(&expr), evaluate::<u128>(&expr), evaluate::<i128>(&expr)].iter() {
+                if value.is_some() {
+                    prop_assert_eq!(value, &exact);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,276 @@
+use crate::error::ParseError;
+use crate::expr::lexer::{tokenize, Token, TokenKind};
+use crate::expr::precedence::{Associativity, OperatorTable};
+use crate::expr::Expr;
+
+/// How deep parentheses and the right sides of operators can nest, parsing them recurses
+pub const MAX_NESTING: usize = 256;
+
+///
+/// Parses a line of homework into an [`Expr`], grouping operators the way `table` says.
+/// Operators that aren't in the table, and nesting deeper than [`MAX_NESTING`], are errors
+/// example
+/// ```
+/// # use advent_of_code_2020::expr::parser::parse;
+/// # use advent_of_code_2020::expr::precedence::OperatorTable;
+/// let line = "2 * 3 + 4";
+///
+/// assert_eq!(parse(line, &OperatorTable::left_to_right()).unwrap().evaluate::<u64>(), Ok(10));
+/// assert_eq!(parse(line, &OperatorTable::addition_first()).unwrap().evaluate::<u64>(), Ok(14));
+/// ```
+pub fn parse(line: &str, table: &OperatorTable) -> Result<Expr, ParseError> {
+    parse_tokens(&tokenize(line)?, table)
+}
+
+///
+/// Parses the tokens of a line that was already split by [`tokenize`], like [`parse`]
+/// example
+/// ```
+/// # use advent_of_code_2020::expr::lexer::tokenize;
+/// # use advent_of_code_2020::expr::parser::parse_tokens;
+/// # use advent_of_code_2020::expr::precedence::OperatorTable;
+/// let tokens = tokenize("2 * 3 + 4").unwrap();
+/// let table = OperatorTable::left_to_right();
+///
+/// assert_eq!(parse_tokens(&tokens, &table).unwrap().evaluate::<u64>(), Ok(10));
+/// assert_eq!(parse_tokens(&tokens[..2], &table).unwrap_err().column, Some(4));
+/// ```
+pub fn parse_tokens(tokens: &[Token], table: &OperatorTable) -> Result<Expr, ParseError> {
+    let mut parser = Parser {
+        tokens,
+        position: 0,
+        depth: 0,
+        table,
+    };
+    let expr = parser.expression(0)?;
+    match parser.peek() {
+        None => Ok(expr),
+        Some(Token {
+            kind: TokenKind::Close,
+            span,
+        }) => Err(ParseError::new("a matching '('", ")").at_column(span.column())),
+        Some(_) => Err(parser.unexpected("an operator")),
+    }
+}
+
+/// Precedence climbing over the tokens of a line
+struct Parser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+    /// How many expressions are being parsed, each inside the one before it
+    depth: usize,
+    table: &'a OperatorTable,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<Token> {
+        self.tokens.get(self.position).copied()
+    }
+
+    /// Parses operands joined by operators that bind at least as tight as `min_precedence`
+    fn expression(&mut self, min_precedence: u16) -> Result<Expr, ParseError> {
+        if self.depth == MAX_NESTING {
+            // the parenthesis or operator that opened one level too many
+            let token = self.tokens[self.position - 1];
+            return Err(ParseError::new(
+                format!("at most {} levels of nesting", MAX_NESTING),
+                text(token),
+            )
+            .at_column(token.span.column()));
+        }
+        self.depth += 1;
+        let expr = self.operations(min_precedence);
+        self.depth -= 1;
+        expr
+    }
+
+    fn operations(&mut self, min_precedence: u16) -> Result<Expr, ParseError> {
+        let mut left = self.operand()?;
+        while let Some(Token {
+            kind: TokenKind::Operator(operator),
+            ..
+        }) = self.peek()
+        {
+            let binding = self.table.get(operator).ok_or_else(|| {
+                let operators = self
+                    .table
+                    .operators()
+                    .map(|operator| format!("'{}'", operator))
+                    .collect::<Vec<_>>();
+                self.unexpected(&format!("one of {}", operators.join(", ")))
+            })?;
+            let precedence = u16::from(binding.precedence);
+            if precedence < min_precedence {
+                break;
+            }
+            self.position += 1;
+            // the right side of a left associative operator stops at the next one just like it
+            let right = match binding.associativity {
+                Associativity::Left => self.expression(precedence + 1)?,
+                Associativity::Right => self.expression(precedence)?,
+            };
+            left = Expr::binary(operator, left, right);
+        }
+        Ok(left)
+    }
+
+    fn operand(&mut self) -> Result<Expr, ParseError> {
+        let token = match self.peek() {
+            Some(token) => token,
+            None => return Err(self.unexpected("a number or '('")),
+        };
+        match token.kind {
+            TokenKind::Number(value) => {
+                self.position += 1;
+                Ok(Expr::Number {
+                    value,
+                    span: token.span,
+                })
+            }
+            TokenKind::Open => {
+                self.position += 1;
+                let inner = self.expression(0)?;
+                match self.peek() {
+                    Some(Token {
+                        kind: TokenKind::Close,
+                        span,
+                    }) => {
+                        self.position += 1;
+                        Ok(Expr::Group {
+                            inner: Box::new(inner),
+                            span: token.span.to(span),
+                        })
+                    }
+                    Some(_) => Err(self.unexpected("an operator or ')'")),
+                    None => {
+                        Err(ParseError::new("a matching ')'", "(").at_column(token.span.column()))
+                    }
+                }
+            }
+            TokenKind::Operator(_) | TokenKind::Close => Err(self.unexpected("a number or '('")),
+        }
+    }
+
+    /// Error for the next token, or the end of the line, not being what was `expected`
+    fn unexpected(&self, expected: &str) -> ParseError {
+        match self.peek() {
+            Some(token) => ParseError::new(expected, text(token)).at_column(token.span.column()),
+            // right after the last token, whitespace at the end of the line doesn't count
+            None => ParseError::new(expected, "nothing")
+                .at_column(self.tokens.last().map_or(1, |token| token.span.end + 1)),
+        }
+    }
+}
+
+/// How `token` is written
+fn text(token: Token) -> String {
+    match token.kind {
+        TokenKind::Number(value) => value.to_string(),
+        TokenKind::Operator(operator) => operator.to_string(),
+        TokenKind::Open => "(".to_string(),
+        TokenKind::Close => ")".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::{Operator, Span};
+
+    #[test]
+    fn test_left_to_right() {
+        let expr = parse("1 + 2 * 3", &OperatorTable::left_to_right()).unwrap();
+
+        assert!(matches!(
+            &expr,
+            Expr::Binary { operator: Operator::Mul, left, .. } if matches!(**left, Expr::Binary { .. })
+        ));
+        assert_eq!(expr.span(), Span::new(0, 9));
+    }
+
+    #[test]
+    fn test_group_spans_include_parentheses() {
+        let expr = parse("2 * (3 + 4)", &OperatorTable::left_to_right()).unwrap();
+
+        match &expr {
+            Expr::Binary { right, .. } => assert_eq!(right.span(), Span::new(4, 11)),
+            other => panic!("expected a product, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_deep_nesting() {
+        // the whole line is one level too
+        let depth = MAX_NESTING - 1;
+        let line = format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+
+        assert_eq!(
+            parse(&line, &OperatorTable::left_to_right())
+                .unwrap()
+                .evaluate::<u64>(),
+            Ok(1)
+        );
+    }
+
+    #[test]
+    fn test_nesting_too_deep() {
+        let line = format!("{}1{}", "(".repeat(MAX_NESTING), ")".repeat(MAX_NESTING));
+        let error = parse(&line, &OperatorTable::left_to_right()).unwrap_err();
+        assert_eq!(
+            (error.column, error.found.as_str()),
+            (Some(MAX_NESTING), "(")
+        );
+
+        let line = vec!["2"; 50_000].join(" ^ ");
+        let error = parse(&line, &OperatorTable::arithmetic()).unwrap_err();
+        assert_eq!(
+            error.expected,
+            format!("at most {} levels of nesting", MAX_NESTING)
+        );
+    }
+
+    #[test]
+    fn test_long_lines() {
+        let line = vec!["1"; 50_000].join(" + ");
+        let expr = parse(&line, &OperatorTable::left_to_right()).unwrap();
+
+        assert_eq!(expr.evaluate::<u64>(), Ok(50_000));
+        assert_eq!(expr.to_string(), line);
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = parse("2 * (3 + 4))", &OperatorTable::left_to_right()).unwrap_err();
+        assert_eq!(
+            (error.column, error.expected.as_str()),
+            (Some(12), "a matching '('")
+        );
+
+        let error = parse("2 * ((3 + 4)", &OperatorTable::left_to_right()).unwrap_err();
+        assert_eq!(
+            (error.column, error.expected.as_str()),
+            (Some(5), "a matching ')'")
+        );
+
+        let error = parse("2 3", &OperatorTable::left_to_right()).unwrap_err();
+        assert_eq!((error.column, error.found.as_str()), (Some(3), "3"));
+
+        let error = parse("(2 3)", &OperatorTable::left_to_right()).unwrap_err();
+        assert_eq!(error.expected, "an operator or ')'");
+
+        let error = parse("2 + * 3", &OperatorTable::left_to_right()).unwrap_err();
+        assert_eq!((error.column, error.found.as_str()), (Some(5), "*"));
+
+        let error = parse("2 +  ", &OperatorTable::left_to_right()).unwrap_err();
+        assert_eq!((error.column, error.found.as_str()), (Some(4), "nothing"));
+
+        let error = parse("", &OperatorTable::left_to_right()).unwrap_err();
+        assert_eq!(error.column, Some(1));
+
+        let error = parse("2 ^ 3", &OperatorTable::left_to_right()).unwrap_err();
+        assert_eq!(
+            (error.column, error.expected.as_str()),
+            (Some(3), "one of '+', '*'")
+        );
+    }
+}
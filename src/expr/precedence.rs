@@ -0,0 +1,186 @@
+use crate::expr::Operator;
+
+/// Which side operators of the same precedence are grouped from
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Associativity {
+    /// `a - b - c` is `(a - b) - c`
+    Left,
+    /// `a ^ b ^ c` is `a ^ (b ^ c)`
+    Right,
+}
+
+/// How tightly an operator binds, higher binds tighter
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Binding {
+    pub precedence: u8,
+    pub associativity: Associativity,
+}
+
+///
+/// The operators an expression may use and how they bind. Both puzzle rules are tables, see
+/// [`OperatorTable::left_to_right`] and [`OperatorTable::addition_first`], and other orders are
+/// made with [`OperatorTable::with`] or [`OperatorTable::levels`]
+/// example
+/// ```
+/// # use advent_of_code_2020::expr::parser::parse;
+/// # use advent_of_code_2020::expr::precedence::{Associativity, OperatorTable};
+/// # use advent_of_code_2020::expr::Operator;
+/// // multiplication before addition, the other way around from part 2
+/// let table = OperatorTable::empty()
+///     .levels(Associativity::Left, &[&[Operator::Add], &[Operator::Mul]]);
+///
+/// assert_eq!(parse("2 + 3 * 4", &table).unwrap().evaluate::<u64>(), Ok(14));
+/// assert!(parse("2 - 3", &table).is_err());
+/// ```
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct OperatorTable {
+    bindings: Vec<(Operator, Binding)>,
+}
+
+impl OperatorTable {
+    /// A table without any operators
+    pub const fn empty() -> Self {
+        Self { bindings: vec![] }
+    }
+
+    /// Part 1: `+` and `*` bind the same, so they're applied left to right
+    pub fn left_to_right() -> Self {
+        Self::empty().levels(Associativity::Left, &[&[Operator::Add, Operator::Mul]])
+    }
+
+    /// Part 2: `+` binds tighter than `*`
+    pub fn addition_first() -> Self {
+        Self::empty().levels(Associativity::Left, &[&[Operator::Mul], &[Operator::Add]])
+    }
+
+    /// The rules from school: `^` before `*`, `/` and `%`, which go before `+` and `-`.
+    /// `^` is right associative, everything else left
+    pub fn arithmetic() -> Self {
+        Self::empty()
+            .levels(
+                Associativity::Left,
+                &[
+                    &[Operator::Add, Operator::Sub],
+                    &[Operator::Mul, Operator::Div, Operator::Rem],
+                ],
+            )
+            .with(Operator::Pow, 3, Associativity::Right)
+    }
+
+    /// Adds `operator`, replacing how it binds if it's already in the table
+    pub fn with(
+        mut self,
+        operator: Operator,
+        precedence: u8,
+        associativity: Associativity,
+    ) -> Self {
+        let binding = Binding {
+            precedence,
+            associativity,
+        };
+        match self
+            .bindings
+            .iter_mut()
+            .find(|(known, _)| *known == operator)
+        {
+            Some((_, known)) => *known = binding,
+            None => self.bindings.push((operator, binding)),
+        }
+        self
+    }
+
+    /// Adds every level of operators, each binding tighter than the ones before it and the ones
+    /// already in the table
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn levels(self, associativity: Associativity, levels: &[&[Operator]]) -> Self {
+        let first = self
+            .bindings
+            .iter()
+            .map(|(_, binding)| binding.precedence.saturating_add(1))
+            .max()
+            .unwrap_or(0);
+        levels
+            .iter()
+            .enumerate()
+            .fold(self, |table, (level, operators)| {
+                operators.iter().fold(table, |table, operator| {
+                    table.with(*operator, first + level as u8, associativity)
+                })
+            })
+    }
+
+    pub fn get(&self, operator: Operator) -> Option<Binding> {
+        self.bindings
+            .iter()
+            .find(|(known, _)| *known == operator)
+            .map(|(_, binding)| *binding)
+    }
+
+    pub fn operators(&self) -> impl Iterator<Item = Operator> + '_ {
+        self.bindings.iter().map(|(operator, _)| *operator)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::parser::parse;
+
+    fn evaluate(line: &str, table: &OperatorTable) -> Option<u128> {
+        parse(line, table).unwrap().evaluate().ok()
+    }
+
+    #[test]
+    fn test_puzzle_rules() {
+        let line = "1 + 2 * 3 + 4 * 5 + 6";
+
+        assert_eq!(evaluate(line, &OperatorTable::left_to_right()), Some(71));
+        assert_eq!(evaluate(line, &OperatorTable::addition_first()), Some(231));
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let table = OperatorTable::arithmetic();
+
+        assert_eq!(evaluate("10 - 4 - 3", &table), Some(3));
+        assert_eq!(evaluate("2 ^ 3 ^ 2", &table), Some(512));
+        assert_eq!(evaluate("(2 ^ 3) ^ 2", &table), Some(64));
+        assert_eq!(evaluate("1 + 2 * 3 ^ 2 % 5", &table), Some(4));
+        assert_eq!(evaluate("100 / 10 / 5", &table), Some(2));
+        assert_eq!(evaluate("2 - 3", &table), None);
+        assert_eq!(evaluate("2 / (3 - 3)", &table), None);
+    }
+
+    #[test]
+    fn test_right_associative_below_left() {
+        // `^` binding looser than `+` and grouping from the right
+        let table = OperatorTable::empty()
+            .with(Operator::Pow, 0, Associativity::Right)
+            .with(Operator::Add, 1, Associativity::Left);
+
+        assert_eq!(evaluate("1 + 1 ^ 1 + 2 ^ 2", &table), Some(2_u128.pow(9)));
+    }
+
+    #[test]
+    fn test_with_replaces() {
+        let table = OperatorTable::left_to_right().with(Operator::Add, 5, Associativity::Right);
+
+        assert_eq!(table.operators().count(), 2);
+        assert_eq!(
+            table.get(Operator::Add),
+            Some(Binding {
+                precedence: 5,
+                associativity: Associativity::Right
+            })
+        );
+        assert_eq!(table.get(Operator::Sub), None);
+    }
+
+    #[test]
+    fn test_levels_go_above_what_is_there() {
+        let table = OperatorTable::arithmetic().levels(Associativity::Left, &[&[Operator::Add]]);
+
+        assert_eq!(table.get(Operator::Add).unwrap().precedence, 4);
+        assert_eq!(evaluate("2 ^ 1 + 2", &table), Some(8));
+    }
+}
@@ -0,0 +1,504 @@
+use std::collections::{HashMap, HashSet};
+
+use itertools::Itertools;
+
+use crate::solver::Answer;
+use crate::vm::{Instruction, Program};
+
+/// Days that have an input generator
+pub const DAYS: [u32; 5] = [5, 7, 8, 13, 16];
+
+/// A generated puzzle input, together with the answers it was built to have
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Puzzle {
+    pub day: u32,
+    pub seed: u64,
+    pub input: String,
+    pub part1: Answer,
+    pub part2: Answer,
+}
+
+///
+/// Generates a random but valid input for `day`, the same `seed` always gives the same puzzle.
+/// `None` if there's no generator for that day
+/// example
+/// ```
+/// # use advent_of_code_2020::generate::generate;
+/// # use advent_of_code_2020::solver::{registry, Part};
+/// let puzzle = generate(5, 42).unwrap();
+/// let day5 = &registry()[&5];
+///
+/// assert_eq!(day5.run(&puzzle.input, Part::One, None), Ok(Some(Ok(puzzle.part1))));
+/// assert_eq!(generate(5, 42), generate(5, 42));
+/// assert_eq!(generate(1, 42), None);
+/// ```
+pub fn generate(day: u32, seed: u64) -> Option<Puzzle> {
+    let mut rng = Rng::new(seed);
+    let (input, part1, part2) = match day {
+        5 => boarding_passes(&mut rng),
+        7 => bag_rules(&mut rng),
+        8 => handheld_program(&mut rng),
+        13 => bus_schedule(&mut rng),
+        16 => ticket_notes(&mut rng),
+        _ => return None,
+    };
+    Some(Puzzle {
+        day,
+        seed,
+        input,
+        part1,
+        part2,
+    })
+}
+
+/// SplitMix64, small and good enough for puzzle inputs, and the sequence can't change with a
+/// dependency update
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub const fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `low..=high`
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn between(&mut self, low: u64, high: u64) -> u64 {
+        let span = u128::from(high - low) + 1;
+        low + ((u128::from(self.next_u64()) * span) >> 64) as u64
+    }
+
+    /// An index in `0..len`
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn index(&mut self, len: usize) -> usize {
+        self.between(0, len as u64 - 1) as usize
+    }
+
+    /// `true` roughly `percent` out of 100 times
+    pub fn chance(&mut self, percent: u64) -> bool {
+        self.between(1, 100) <= percent
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.index(i + 1);
+            items.swap(i, j);
+        }
+    }
+}
+
+/// Every seat of a flight in a random order, except mine.
+/// The plane isn't full, the very first and last rows are missing
+fn boarding_passes(rng: &mut Rng) -> (String, Answer, Answer) {
+    let first = rng.between(8, 200);
+    let last = rng.between(first + 2, 127 * 8 - 8);
+    let mine = rng.between(first + 1, last - 1);
+
+    let mut ids = (first..=last).filter(|id| *id != mine).collect::<Vec<_>>();
+    rng.shuffle(&mut ids);
+
+    let mut passes = ids.into_iter().map(|id| {
+        let bits = |from: u32, len: u32, zero: char, one: char| {
+            (0..len)
+                .rev()
+                .map(|bit| {
+                    if id >> (from + bit) & 1 == 1 {
+                        one
+                    } else {
+                        zero
+                    }
+                })
+                .collect::<String>()
+        };
+        format!("{}{}", bits(3, 7, 'F', 'B'), bits(0, 3, 'L', 'R'))
+    });
+
+    (passes.join("\n"), last.to_string(), mine.to_string())
+}
+
+const ADJECTIVES: [&str; 12] = [
+    "light", "dark", "bright", "muted", "shiny", "faded", "dotted", "vibrant", "pale", "wavy",
+    "dim", "clear",
+];
+
+const COLORS: [&str; 12] = [
+    "red", "orange", "white", "yellow", "gold", "olive", "plum", "blue", "black", "teal", "violet",
+    "tan",
+];
+
+const MAX_BAG_DEPTH: u64 = 6;
+
+/// Bag rules without cycles. Every bag has a depth and only holds bags that are less deep,
+/// which keeps the number of bags inside shiny gold reasonable
+fn bag_rules(rng: &mut Rng) -> (String, Answer, Answer) {
+    let mut colors = ADJECTIVES
+        .iter()
+        .cartesian_product(COLORS.iter())
+        .map(|(adjective, color)| format!("{} {}", adjective, color))
+        .filter(|color| color != "shiny gold")
+        .collect::<Vec<_>>();
+    rng.shuffle(&mut colors);
+    colors.truncate(rng.index(40) + 20);
+    colors.push(String::from("shiny gold"));
+
+    let mut depths = colors
+        .iter()
+        .map(|_| rng.between(0, MAX_BAG_DEPTH))
+        .collect::<Vec<_>>();
+    // shiny gold somewhere in the middle so both parts have something to count
+    let gold = colors.len() - 1;
+    depths[gold] = rng.between(2, MAX_BAG_DEPTH - 1);
+
+    let mut rules: Vec<Vec<(u64, usize)>> = vec![vec![]; colors.len()];
+    for (bag, contents) in rules.iter_mut().enumerate() {
+        let mut smaller = (0..colors.len())
+            .filter(|other| depths[*other] < depths[bag])
+            .collect::<Vec<_>>();
+        rng.shuffle(&mut smaller);
+        let kinds = rng.index(smaller.len().min(4) + 1);
+        // the deeper bags always hold something, or shiny gold might end up empty
+        let kinds = if smaller.is_empty() { 0 } else { kinds.max(1) };
+        *contents = smaller
+            .into_iter()
+            .take(kinds)
+            .map(|other| (rng.between(1, 5), other))
+            .collect();
+    }
+
+    let mut can_hold_gold = HashSet::new();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (bag, contents) in rules.iter().enumerate() {
+            if !can_hold_gold.contains(&bag)
+                && contents
+                    .iter()
+                    .any(|(_, other)| *other == gold || can_hold_gold.contains(other))
+            {
+                can_hold_gold.insert(bag);
+                changed = true;
+            }
+        }
+    }
+
+    let inside_gold = bags_inside(&rules, gold, &mut HashMap::new());
+
+    let mut order = (0..colors.len()).collect::<Vec<_>>();
+    rng.shuffle(&mut order);
+    let mut lines = order.into_iter().map(|bag| {
+        let contents = if rules[bag].is_empty() {
+            String::from("no other bags")
+        } else {
+            rules[bag]
+                .iter()
+                .map(|(amount, other)| {
+                    let plural = if *amount == 1 { "" } else { "s" };
+                    format!("{} {} bag{}", amount, colors[*other], plural)
+                })
+                .join(", ")
+        };
+        format!("{} bags contain {}.", colors[bag], contents)
+    });
+
+    (
+        lines.join("\n"),
+        can_hold_gold.len().to_string(),
+        inside_gold.to_string(),
+    )
+}
+
+/// How many bags are inside `bag`, all the way down
+fn bags_inside(rules: &[Vec<(u64, usize)>], bag: usize, memo: &mut HashMap<usize, u64>) -> u64 {
+    if let Some(count) = memo.get(&bag) {
+        return *count;
+    }
+    let count = rules[bag]
+        .iter()
+        .map(|(amount, other)| amount * (1 + bags_inside(rules, *other, memo)))
+        .sum();
+    memo.insert(bag, count);
+    count
+}
+
+/// A handheld console program with one corrupted `nop`, thousands of instructions long. What
+/// runs is a single path from the start to the end, hopping over blocks of dead code that loop on
+/// themselves. The `nop`s on the path point into dead code, so that the corrupted one is the only
+/// flip that helps
+#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+fn handheld_program(rng: &mut Rng) -> (String, Answer, Answer) {
+    let len = rng.between(5_000, 20_000) as usize;
+    let offset = |from: usize, to: usize| to as i32 - from as i32;
+    let argument = |rng: &mut Rng| rng.between(0, 100) as i32 - 50;
+
+    let mut instructions = vec![];
+    let mut path = vec![];
+    let mut dead = vec![];
+    while instructions.len() < len {
+        let address = instructions.len();
+        path.push(address);
+        if dead.is_empty() || rng.chance(10) {
+            let block = rng.between(2, 6) as usize;
+            instructions.push(Instruction::Jmp(block as i32 + 1));
+            for _ in 1..block {
+                instructions.push(Instruction::Acc(argument(rng)));
+            }
+            instructions.push(Instruction::Jmp(offset(address + block, address + 1)));
+            dead.extend(address + 1..=address + block);
+        } else if rng.chance(30) {
+            let target = dead[rng.index(dead.len())];
+            instructions.push(Instruction::Nop(offset(address, target)));
+        } else {
+            instructions.push(Instruction::Acc(argument(rng)));
+        }
+    }
+
+    // the corrupted instruction jumps back somewhere on the path, far enough in to take a while
+    let corrupted = loop {
+        let corrupted = rng.between(path.len() as u64 / 2, path.len() as u64 - 1) as usize;
+        if !matches!(instructions[path[corrupted]], Instruction::Jmp(_)) {
+            break corrupted;
+        }
+    };
+    let back = offset(path[corrupted], path[rng.index(corrupted + 1)]);
+    instructions[path[corrupted]] = Instruction::Jmp(back);
+
+    let acc = |path: &[usize]| {
+        path.iter()
+            .map(|address| match instructions[*address] {
+                Instruction::Acc(argument) => i64::from(argument),
+                _ => 0,
+            })
+            .sum::<i64>()
+    };
+    let (part1, part2) = (acc(&path[..corrupted]), acc(&path));
+    (
+        Program::from(instructions).to_string(),
+        part1.to_string(),
+        part2.to_string(),
+    )
+}
+
+const PRIMES: [u64; 20] = [
+    7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83,
+];
+
+/// Bus ids are distinct primes, placed at offsets chosen so that a random timestamp is the
+/// answer to part 2
+#[allow(clippy::cast_possible_truncation)]
+fn bus_schedule(rng: &mut Rng) -> (String, Answer, Answer) {
+    let mut ids = PRIMES.to_vec();
+    rng.shuffle(&mut ids);
+    ids.truncate(rng.between(4, 9) as usize);
+
+    let product = ids.iter().map(|id| u128::from(*id)).product::<u128>();
+    let planted =
+        (u128::from(rng.next_u64()) << 64 | u128::from(rng.next_u64())) % (product - 1) + 1;
+
+    // buss `id` at offset `i` means (planted + i) % id == 0
+    let mut offsets: HashMap<u64, u64> = HashMap::new();
+    for id in &ids {
+        let mut offset = ((u128::from(*id) - planted % u128::from(*id)) % u128::from(*id)) as u64;
+        while offsets.contains_key(&offset) {
+            offset += id;
+        }
+        offsets.insert(offset, *id);
+    }
+    let length = offsets.keys().max().map_or(0, |max| max + 1);
+    let schedule = (0..length)
+        .map(|offset| match offsets.get(&offset) {
+            Some(id) => id.to_string(),
+            None => String::from("x"),
+        })
+        .join(",");
+
+    // a departure time where exactly one buss comes first
+    loop {
+        let timestamp = rng.between(100_000, 1_000_000);
+        let waits = ids
+            .iter()
+            .map(|id| ((id - timestamp % id) % id, *id))
+            .sorted()
+            .collect::<Vec<_>>();
+        if waits[0].0 != waits[1].0 {
+            let (wait, id) = waits[0];
+            return (
+                format!("{}\n{}", timestamp, schedule),
+                (id * wait).to_string(),
+                planted.to_string(),
+            );
+        }
+    }
+}
+
+const FIELDS: [&str; 20] = [
+    "departure location",
+    "departure station",
+    "departure platform",
+    "departure track",
+    "departure date",
+    "departure time",
+    "arrival location",
+    "arrival station",
+    "arrival platform",
+    "arrival track",
+    "class",
+    "duration",
+    "price",
+    "route",
+    "row",
+    "seat",
+    "train",
+    "type",
+    "wagon",
+    "zone",
+];
+
+/// Ticket rules where the valid values of each rule are nested inside the previous one's.
+/// Every field of a valid ticket is valid for the rule it belongs to and all rules less strict,
+/// and some ticket has it invalid for the next stricter one, so exactly one assignment of
+/// fields works and it can be found by elimination
+#[allow(clippy::cast_possible_truncation, clippy::too_many_lines)]
+fn ticket_notes(rng: &mut Rng) -> (String, Answer, Answer) {
+    let fields = rng.between(8, 20) as usize;
+    // all six departure fields, part 2 multiplies them
+    let mut names = FIELDS[..6].to_vec();
+    let mut others = FIELDS[6..].to_vec();
+    rng.shuffle(&mut others);
+    names.extend(others.into_iter().take(fields - 6));
+
+    // strictness[rule] is how deep its ranges are nested, position[strictness] the ticket field
+    let mut strictness = (0..fields).collect::<Vec<_>>();
+    rng.shuffle(&mut strictness);
+    let mut position = (0..fields).collect::<Vec<_>>();
+    rng.shuffle(&mut position);
+
+    // rule of strictness s is valid in low[s]..=gap_start and gap_end..=high[s]
+    let mut low = vec![rng.between(25, 50)];
+    for s in 1..fields {
+        low.push(low[s - 1] + rng.between(3, 15));
+    }
+    let gap_start = low[fields - 1] + rng.between(3, 15);
+    let gap_end = gap_start + rng.between(4, 20);
+    let mut high = vec![gap_end + rng.between(3, 15)];
+    for _ in 1..fields {
+        high.push(high[high.len() - 1] + rng.between(3, 15));
+    }
+    high.reverse();
+
+    let value_at_strictness = |rng: &mut Rng, s: usize| {
+        // only valid up to strictness s, not s + 1
+        let (low_end, high_start) = if s + 1 < fields {
+            (low[s + 1] - 1, high[s + 1] + 1)
+        } else {
+            (gap_start, gap_end)
+        };
+        if rng.chance(50) {
+            rng.between(low[s], low_end)
+        } else {
+            rng.between(high_start, high[s])
+        }
+    };
+    let valid_ticket = |rng: &mut Rng, exact: bool| {
+        let mut ticket = vec![0; fields];
+        for s in 0..fields {
+            let at_least = if exact {
+                s
+            } else {
+                rng.between(s as u64, fields as u64 - 1) as usize
+            };
+            ticket[position[s]] = value_at_strictness(rng, at_least);
+        }
+        ticket
+    };
+
+    let my_ticket = valid_ticket(rng, false);
+    let mut nearby = vec![valid_ticket(rng, true)];
+    let mut error_rate = 0;
+    for _ in 0..rng.between(20, 60) {
+        let mut ticket = valid_ticket(rng, false);
+        if rng.chance(25) {
+            let invalid = if rng.chance(50) {
+                rng.between(gap_start + 1, gap_end - 1)
+            } else {
+                rng.between(high[0] + 1, 999)
+            };
+            ticket[rng.index(fields)] = invalid;
+            error_rate += invalid;
+        }
+        nearby.push(ticket);
+    }
+    rng.shuffle(&mut nearby);
+
+    let departures = (0..6)
+        .map(|rule| my_ticket[position[strictness[rule]]])
+        .product::<u64>();
+
+    let mut rules = names.iter().enumerate().map(|(rule, name)| {
+        let s = strictness[rule];
+        format!(
+            "{}: {}-{} or {}-{}",
+            name, low[s], gap_start, gap_end, high[s]
+        )
+    });
+    let ticket = |ticket: &Vec<u64>| ticket.iter().join(",");
+    let input = format!(
+        "{}\n\nyour ticket:\n{}\n\nnearby tickets:\n{}",
+        rules.join("\n"),
+        ticket(&my_ticket),
+        nearby.iter().map(ticket).join("\n")
+    );
+
+    (input, error_rate.to_string(), departures.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::{registry, Part};
+
+    #[test]
+    fn test_generated_answers_match_the_solutions() {
+        let registry = registry();
+        for day in &DAYS {
+            for seed in 0..10 {
+                let puzzle = generate(*day, seed).unwrap();
+                for (part, expected) in Part::BOTH.iter().zip(&[&puzzle.part1, &puzzle.part2]) {
+                    let answer = registry[day].run(&puzzle.input, *part, None);
+                    assert_eq!(
+                        answer,
+                        Ok(Some(Ok((*expected).clone()))),
+                        "day {} part {} seed {}",
+                        day,
+                        part,
+                        seed
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_seed_changes_the_puzzle() {
+        for day in &DAYS {
+            assert_eq!(generate(*day, 1), generate(*day, 1));
+            assert_ne!(generate(*day, 1), generate(*day, 2));
+        }
+    }
+
+    #[test]
+    fn test_between_stays_in_range() {
+        let mut rng = Rng::new(7);
+        for _ in 0..1000 {
+            assert!((3..=5).contains(&rng.between(3, 5)));
+        }
+        assert_eq!(Rng::new(7).between(9, 9), 9);
+    }
+}
@@ -0,0 +1,355 @@
+use std::fmt;
+use std::ops::{Index, IndexMut};
+use std::str::FromStr;
+
+use crate::error::ParseError;
+
+/// (row, column), with (0, 0) in the top left corner
+pub type Position = (usize, usize);
+
+/// (row, column) step, e.g. `(-1, 0)` is up
+pub type Direction = (isize, isize);
+
+pub const DIRECTIONS4: [Direction; 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+
+pub const DIRECTIONS8: [Direction; 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+///
+/// A cell that can be read from, and written back as, a single character of a puzzle input
+/// example
+/// ```
+/// # use advent_of_code_2020::grid::{Cell, Grid};
+/// #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+/// enum Tile {
+///     Wall,
+///     Open,
+/// }
+///
+/// impl Cell for Tile {
+///     const EXPECTED: &'static str = "'#' or '.'";
+///
+///     fn from_char(char: char) -> Option<Self> {
+///         match char {
+///             '#' => Some(Self::Wall),
+///             '.' => Some(Self::Open),
+///             _ => None,
+///         }
+///     }
+///
+///     fn to_char(&self) -> char {
+///         match self {
+///             Self::Wall => '#',
+///             Self::Open => '.',
+///         }
+///     }
+/// }
+///
+/// let grid: Grid<Tile> = "#.\n.#".parse().unwrap();
+/// assert_eq!(grid[(1, 1)], Tile::Wall);
+/// assert_eq!(grid.to_string(), "#.\n.#");
+/// ```
+pub trait Cell: Sized {
+    /// Describes the valid characters, for parse errors
+    const EXPECTED: &'static str;
+
+    fn from_char(char: char) -> Option<Self>;
+    fn to_char(&self) -> char;
+}
+
+/// `#` is `true`, `.` is `false`
+impl Cell for bool {
+    const EXPECTED: &'static str = "'#' or '.'";
+
+    fn from_char(char: char) -> Option<Self> {
+        match char {
+            '#' => Some(true),
+            '.' => Some(false),
+            _ => None,
+        }
+    }
+
+    fn to_char(&self) -> char {
+        if *self {
+            '#'
+        } else {
+            '.'
+        }
+    }
+}
+
+///
+/// A dense, rectangular 2D grid stored row by row
+/// example
+/// ```
+/// # use advent_of_code_2020::grid::Grid;
+/// let grid: Grid<bool> = "..#\n#..".parse().unwrap();
+///
+/// assert_eq!((grid.height(), grid.width()), (2, 3));
+/// assert_eq!(grid.get((0, 2)), Some(&true));
+/// assert_eq!(grid.get((0, 3)), None);
+/// assert_eq!(grid.get_wrapping((0, 5)), &true);
+/// assert_eq!(grid.neighbours8((0, 0)).filter(|p| grid[*p]).count(), 1);
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn new(width: usize, height: usize, fill: T) -> Self
+    where
+        T: Clone,
+    {
+        Self {
+            width,
+            height,
+            cells: vec![fill; width * height],
+        }
+    }
+
+    /// Builds a grid from rows of cells, the error names the first row with a different width
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Result<Self, ParseError> {
+        let width = rows.first().map_or(0, Vec::len);
+        if let Some(row) = rows.iter().position(|row| row.len() != width) {
+            return Err(ParseError::new(
+                format!("{} columns", width),
+                format!("{} columns", rows[row].len()),
+            )
+            .at_line(row + 1));
+        }
+        Ok(Self {
+            width,
+            height: rows.len(),
+            cells: rows.into_iter().flatten().collect(),
+        })
+    }
+
+    pub const fn width(&self) -> usize {
+        self.width
+    }
+
+    pub const fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, (row, column): Position) -> Option<&T> {
+        if row < self.height && column < self.width {
+            self.cells.get(row * self.width + column)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, (row, column): Position) -> Option<&mut T> {
+        if row < self.height && column < self.width {
+            self.cells.get_mut(row * self.width + column)
+        } else {
+            None
+        }
+    }
+
+    /// Looks up a cell as if the grid repeated forever in every direction
+    ///
+    /// Panics if the grid is empty
+    #[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+    pub fn get_wrapping(&self, (row, column): (isize, isize)) -> &T {
+        assert!(!self.cells.is_empty(), "can't wrap around an empty grid");
+        let row = row.rem_euclid(self.height as isize) as usize;
+        let column = column.rem_euclid(self.width as isize) as usize;
+        &self[(row, column)]
+    }
+
+    /// The position one `direction` step away from `position`, if it's inside the grid
+    pub fn step(
+        &self,
+        (row, column): Position,
+        (row_step, column_step): Direction,
+    ) -> Option<Position> {
+        let row = row.checked_add_signed(row_step)?;
+        let column = column.checked_add_signed(column_step)?;
+        if row < self.height && column < self.width {
+            Some((row, column))
+        } else {
+            None
+        }
+    }
+
+    /// Up, left, right and down of `position`, skipping those outside the grid
+    pub fn neighbours4(&self, position: Position) -> impl Iterator<Item = Position> + '_ {
+        DIRECTIONS4
+            .iter()
+            .filter_map(move |direction| self.step(position, *direction))
+    }
+
+    /// Like [`Grid::neighbours4`] but including the diagonals
+    pub fn neighbours8(&self, position: Position) -> impl Iterator<Item = Position> + '_ {
+        DIRECTIONS8
+            .iter()
+            .filter_map(move |direction| self.step(position, *direction))
+    }
+
+    /// Every position from `start` (exclusive) in `direction` until the edge of the grid
+    pub fn ray(
+        &self,
+        start: Position,
+        direction: Direction,
+    ) -> impl Iterator<Item = Position> + '_ {
+        let mut current = Some(start);
+        std::iter::from_fn(move || {
+            current = self.step(current?, direction);
+            current
+        })
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = Position> {
+        let width = self.width;
+        (0..self.cells.len()).map(move |i| (i / width, i % width))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Position, &T)> {
+        self.positions().zip(self.cells.iter())
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        // chunks panics on 0, an empty grid has no rows anyway
+        self.cells.chunks(self.width.max(1))
+    }
+
+    /// A new grid of the same size, with every cell computed from the old one
+    pub fn map<U>(&self, mut f: impl FnMut(Position, &T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self
+                .iter()
+                .map(|(position, cell)| f(position, cell))
+                .collect(),
+        }
+    }
+}
+
+impl<T> Index<Position> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, position: Position) -> &T {
+        self.get(position).unwrap_or_else(|| {
+            panic!(
+                "{:?} is outside of the {}x{} grid",
+                position, self.height, self.width
+            )
+        })
+    }
+}
+
+impl<T> IndexMut<Position> for Grid<T> {
+    fn index_mut(&mut self, position: Position) -> &mut T {
+        let (height, width) = (self.height, self.width);
+        self.get_mut(position)
+            .unwrap_or_else(|| panic!("{:?} is outside of the {}x{} grid", position, height, width))
+    }
+}
+
+impl<T: Cell> FromStr for Grid<T> {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<Self, ParseError> {
+        let rows = input
+            .lines()
+            .enumerate()
+            .map(|(row, line)| {
+                line.chars()
+                    .enumerate()
+                    .map(|(column, char)| {
+                        T::from_char(char).ok_or_else(|| {
+                            ParseError::new(T::EXPECTED, char)
+                                .at_column(column + 1)
+                                .at_line(row + 1)
+                        })
+                    })
+                    .collect()
+            })
+            .collect::<Result<Vec<Vec<T>>, ParseError>>()?;
+        Self::from_rows(rows)
+    }
+}
+
+impl<T: Cell> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, row) in self.rows().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            for cell in row {
+                write!(f, "{}", cell.to_char())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_errors() {
+        let error = "..#\n.x.".parse::<Grid<bool>>().unwrap_err();
+        assert_eq!((error.line, error.column), (Some(2), Some(2)));
+
+        let error = "..#\n.#\n...".parse::<Grid<bool>>().unwrap_err();
+        assert_eq!(error.line, Some(2));
+        assert_eq!(error.expected, "3 columns");
+    }
+
+    #[test]
+    fn test_neighbours_at_the_edges() {
+        let grid = Grid::new(3, 2, 0);
+
+        assert_eq!(
+            grid.neighbours4((0, 0)).collect::<Vec<_>>(),
+            vec![(0, 1), (1, 0)]
+        );
+        assert_eq!(grid.neighbours8((0, 1)).count(), 5);
+        assert_eq!(grid.neighbours8((1, 1)).count(), 5);
+    }
+
+    #[test]
+    fn test_ray_stops_at_the_edge() {
+        let grid = Grid::new(4, 4, 0);
+
+        assert_eq!(
+            grid.ray((0, 0), (1, 1)).collect::<Vec<_>>(),
+            vec![(1, 1), (2, 2), (3, 3)]
+        );
+        assert_eq!(grid.ray((0, 0), (-1, 0)).count(), 0);
+    }
+
+    #[test]
+    fn test_wrapping() {
+        let grid: Grid<bool> = "#..\n..#".parse().unwrap();
+
+        assert!(grid.get_wrapping((0, 3)));
+        assert!(grid.get_wrapping((-1, -1)));
+        assert!(!grid.get_wrapping((2, 4)));
+    }
+
+    #[test]
+    fn test_map_and_display_round_trip() {
+        let input = "#..\n.#.";
+        let grid: Grid<bool> = input.parse().unwrap();
+
+        assert_eq!(grid.to_string(), input);
+        assert_eq!(grid.map(|_, cell| !cell).to_string(), ".##\n#.#");
+    }
+}
@@ -0,0 +1,358 @@
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_BASE_URL: &str = "https://adventofcode.com";
+
+/// Where inputs are cached, the same place `cargo aoc input` puts them
+pub const DEFAULT_CACHE_DIR: &str = "input/2020";
+
+/// Environment variable holding the session token, the `session` cookie of a logged in browser
+pub const SESSION_VARIABLE: &str = "AOC_SESSION";
+
+const YEAR: u32 = 2020;
+
+#[derive(Debug)]
+pub enum FetchError {
+    /// Advent of Code only has days 1 to 25
+    InvalidDay(u32),
+    /// The session token was rejected or has expired
+    Unauthorized,
+    /// The puzzle isn't unlocked yet, or the base URL is wrong
+    NotFound(u32),
+    /// The server asked to back off for longer than the client is willing to wait
+    RateLimited { retry_after: Option<Duration> },
+    /// Any other unexpected status
+    Status { status: u16, body: String },
+    /// The server couldn't be reached
+    Transport(String),
+    /// Reading or writing the cache failed
+    Io(io::Error),
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidDay(day) => write!(f, "there's no day {}, only 1 to 25", day),
+            Self::Unauthorized => write!(f, "the session token was rejected, it may have expired"),
+            Self::NotFound(day) => write!(f, "day {} isn't unlocked yet", day),
+            Self::RateLimited {
+                retry_after: Some(retry_after),
+            } => write!(f, "rate limited, try again in {:?}", retry_after),
+            Self::RateLimited { retry_after: None } => write!(f, "rate limited, try again later"),
+            Self::Status { status, body } => {
+                write!(f, "unexpected status {}: {}", status, body.trim())
+            }
+            Self::Transport(message) => write!(f, "couldn't reach the server: {}", message),
+            Self::Io(error) => write!(f, "couldn't access the cache: {}", error),
+        }
+    }
+}
+
+impl Error for FetchError {}
+
+impl From<io::Error> for FetchError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+///
+/// Downloads puzzle inputs and caches them, so that every input is only ever fetched once.
+/// Requests are spaced out by at least `min_interval`, and a `429 Too Many Requests` is retried
+/// once if the server asks to wait no longer than `max_retry_wait`
+/// example
+/// ```no_run
+/// # use advent_of_code_2020::input::Client;
+/// let mut client = Client::new("53616c7465645f5f...").cache_dir("/tmp/inputs");
+///
+/// let path = client.fetch(1).unwrap();
+/// assert!(path.ends_with("day1.txt"));
+/// ```
+#[derive(Debug)]
+pub struct Client {
+    session: String,
+    base_url: String,
+    cache_dir: PathBuf,
+    min_interval: Duration,
+    max_retry_wait: Duration,
+    last_request: Option<Instant>,
+    agent: ureq::Agent,
+}
+
+impl Client {
+    pub fn new(session: impl Into<String>) -> Self {
+        Self {
+            session: session.into(),
+            base_url: String::from(DEFAULT_BASE_URL),
+            cache_dir: PathBuf::from(DEFAULT_CACHE_DIR),
+            min_interval: Duration::from_secs(3),
+            max_retry_wait: Duration::from_secs(60),
+            last_request: None,
+            agent: ureq::AgentBuilder::new()
+                .timeout(Duration::from_secs(30))
+                .user_agent(concat!(
+                    env!("CARGO_PKG_REPOSITORY"),
+                    " by ",
+                    env!("CARGO_PKG_AUTHORS")
+                ))
+                .build(),
+        }
+    }
+
+    /// A client with the session token from the `AOC_SESSION` environment variable
+    pub fn from_env() -> Option<Self> {
+        std::env::var(SESSION_VARIABLE)
+            .ok()
+            .map(|session| session.trim().to_owned())
+            .filter(|session| !session.is_empty())
+            .map(Self::new)
+    }
+
+    /// Fetches from somewhere other than adventofcode.com, like a mirror or a test server
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_owned();
+        self
+    }
+
+    pub fn cache_dir(mut self, cache_dir: impl Into<PathBuf>) -> Self {
+        self.cache_dir = cache_dir.into();
+        self
+    }
+
+    pub fn min_interval(mut self, min_interval: Duration) -> Self {
+        self.min_interval = min_interval;
+        self
+    }
+
+    pub fn max_retry_wait(mut self, max_retry_wait: Duration) -> Self {
+        self.max_retry_wait = max_retry_wait;
+        self
+    }
+
+    pub fn cache_path(&self, day: u32) -> PathBuf {
+        self.cache_dir.join(format!("day{}.txt", day))
+    }
+
+    /// Path to the input of `day`, downloading it first unless it's already cached
+    pub fn fetch(&mut self, day: u32) -> Result<PathBuf, FetchError> {
+        if !(1..=25).contains(&day) {
+            return Err(FetchError::InvalidDay(day));
+        }
+        let path = self.cache_path(day);
+        if path.is_file() {
+            return Ok(path);
+        }
+
+        let input = self.download(day)?;
+        fs::create_dir_all(&self.cache_dir)?;
+        write_atomically(&path, &input)?;
+        Ok(path)
+    }
+
+    fn download(&mut self, day: u32) -> Result<String, FetchError> {
+        let url = format!("{}/{}/day/{}/input", self.base_url, YEAR, day);
+        let mut retried = false;
+        loop {
+            self.wait_for_turn();
+            let response = self
+                .agent
+                .get(&url)
+                .set("Cookie", &format!("session={}", self.session))
+                .call();
+
+            return match response {
+                Ok(response) => Ok(response.into_string()?),
+                Err(ureq::Error::Status(429, response)) => {
+                    let retry_after = response
+                        .header("Retry-After")
+                        .and_then(|seconds| seconds.trim().parse().ok())
+                        .map(Duration::from_secs);
+                    match retry_after {
+                        Some(wait) if !retried && wait <= self.max_retry_wait => {
+                            thread::sleep(wait);
+                            retried = true;
+                            continue;
+                        }
+                        _ => Err(FetchError::RateLimited { retry_after }),
+                    }
+                }
+                // adventofcode.com answers an unknown or expired session with a 400
+                Err(ureq::Error::Status(400, _))
+                | Err(ureq::Error::Status(401, _))
+                | Err(ureq::Error::Status(403, _)) => Err(FetchError::Unauthorized),
+                Err(ureq::Error::Status(404, _)) => Err(FetchError::NotFound(day)),
+                Err(ureq::Error::Status(status, response)) => Err(FetchError::Status {
+                    status,
+                    body: response.into_string().unwrap_or_default(),
+                }),
+                Err(ureq::Error::Transport(transport)) => {
+                    Err(FetchError::Transport(transport.to_string()))
+                }
+            };
+        }
+    }
+
+    /// Sleeps until `min_interval` has passed since the previous request
+    fn wait_for_turn(&mut self) {
+        if let Some(last_request) = self.last_request {
+            let since = last_request.elapsed();
+            if since < self.min_interval {
+                thread::sleep(self.min_interval - since);
+            }
+        }
+        self.last_request = Some(Instant::now());
+    }
+}
+
+/// Writes to a temporary file first, so an interrupted download never leaves half an input behind
+fn write_atomically(path: &Path, contents: &str) -> io::Result<()> {
+    let temporary = path.with_extension("txt.part");
+    fs::write(&temporary, contents)?;
+    fs::rename(&temporary, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+
+    /// A canned response: status, extra headers and body
+    type Reply = (u16, &'static str, &'static str);
+
+    /// Serves `replies` in order, one per connection, and records the request heads it got
+    fn stub_server(replies: Vec<Reply>) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(vec![]));
+        let recorded = Arc::clone(&requests);
+
+        thread::spawn(move || {
+            for (status, headers, body) in replies {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut head = String::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    head.push_str(&line);
+                }
+                recorded.lock().unwrap().push(head);
+                write!(
+                    stream,
+                    "HTTP/1.1 {} Stub\r\nContent-Length: {}\r\nConnection: close\r\n{}\r\n{}",
+                    status,
+                    body.len(),
+                    headers,
+                    body
+                )
+                .unwrap();
+            }
+        });
+        (base_url, requests)
+    }
+
+    fn cache_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("aoc2020-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn client(base_url: &str, cache_dir: &Path) -> Client {
+        Client::new("secret")
+            .base_url(base_url)
+            .cache_dir(cache_dir)
+            .min_interval(Duration::from_millis(0))
+    }
+
+    #[test]
+    fn test_downloads_once_and_caches() {
+        let (base_url, requests) = stub_server(vec![(200, "", "1721\n979\n")]);
+        let dir = cache_dir("cache");
+        let mut client = client(&base_url, &dir);
+
+        let path = client.fetch(1).unwrap();
+        assert_eq!(path, dir.join("day1.txt"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "1721\n979\n");
+        assert_eq!(client.fetch(1).unwrap(), path);
+        fs::remove_dir_all(&dir).unwrap();
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].starts_with("GET /2020/day/1/input HTTP/1.1"));
+        assert!(requests[0].contains("Cookie: session=secret"));
+        assert!(requests[0].contains("User-Agent: https://github.com/RichoDemus"));
+    }
+
+    #[test]
+    fn test_errors() {
+        let (base_url, _) = stub_server(vec![
+            (
+                400,
+                "",
+                "Puzzle inputs differ by user.  Please log in to get your puzzle input.",
+            ),
+            (
+                404,
+                "",
+                "Please don't repeatedly request this endpoint before it unlocks!",
+            ),
+            (500, "", "oops"),
+        ]);
+        let dir = cache_dir("errors");
+        let mut client = client(&base_url, &dir);
+
+        assert!(matches!(client.fetch(2), Err(FetchError::Unauthorized)));
+        assert!(matches!(client.fetch(25), Err(FetchError::NotFound(25))));
+        assert!(matches!(
+            client.fetch(3),
+            Err(FetchError::Status { status: 500, .. })
+        ));
+        assert!(matches!(client.fetch(26), Err(FetchError::InvalidDay(26))));
+        assert!(!dir.exists(), "failed downloads must not be cached");
+    }
+
+    #[test]
+    fn test_retries_when_rate_limited() {
+        let (base_url, requests) = stub_server(vec![
+            (429, "Retry-After: 0\r\n", ""),
+            (200, "", "abc"),
+            (429, "Retry-After: 3600\r\n", ""),
+        ]);
+        let dir = cache_dir("rate-limit");
+        let mut client = client(&base_url, &dir);
+
+        assert!(client.fetch(6).is_ok());
+        match client.fetch(7) {
+            Err(FetchError::RateLimited { retry_after }) => {
+                assert_eq!(retry_after, Some(Duration::from_secs(3600)))
+            }
+            other => panic!("expected to be rate limited, got {:?}", other),
+        }
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(requests.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_requests_are_spaced_out() {
+        let (base_url, _) = stub_server(vec![(200, "", "a"), (200, "", "b")]);
+        let dir = cache_dir("interval");
+        let mut client = client(&base_url, &dir).min_interval(Duration::from_millis(200));
+
+        let start = Instant::now();
+        client.fetch(1).unwrap();
+        client.fetch(2).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(start.elapsed() >= Duration::from_millis(200));
+    }
+}
@@ -6,25 +6,43 @@ extern crate aoc_runner_derive;
 #[macro_use]
 extern crate lazy_static;
 
-mod day1;
-mod day10;
-mod day11;
-mod day12;
-mod day13;
-mod day14;
-mod day15;
-mod day16;
-mod day17_3d_game_of_life;
-mod day18_math_homework;
-mod day2;
-mod day3;
-mod day4;
-mod day5;
-mod day6;
-mod day7;
-mod day8;
-mod day9;
+pub mod alloc;
+pub mod answers;
+pub mod bench;
+pub mod day1;
+pub mod day10;
+pub mod day11;
+pub mod day12;
+pub mod day13;
+pub mod day14;
+pub mod day15;
+pub mod day16;
+pub mod day17_3d_game_of_life;
+pub mod day18_math_homework;
+pub mod day19_message_rules;
+pub mod day2;
+pub mod day3;
+pub mod day4;
+pub mod day5;
+pub mod day6;
+pub mod day7;
+pub mod day8;
+pub mod day9;
+#[cfg(test)]
+mod differential;
+pub mod error;
+pub mod expr;
+pub mod generate;
+pub mod grid;
+pub mod input;
+pub mod profile;
+pub mod solver;
 pub mod util;
-mod day19_message_rules;
+pub mod vm;
+
+// lets the tests check allocation counts
+#[cfg(test)]
+#[global_allocator]
+static ALLOCATOR: alloc::CountingAllocator = alloc::CountingAllocator;
 
 aoc_lib! { year = 2020 }
@@ -0,0 +1,200 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::alloc::{self, Stats};
+use crate::error::{ParseError, SolveError};
+use crate::solver::{Answer, DynSolver, Part};
+
+/// Time and heap usage of parsing, or of solving one part
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct Phase {
+    pub time: Duration,
+    pub stats: Stats,
+}
+
+impl Phase {
+    fn measure<T>(f: impl FnOnce() -> T) -> (T, Self) {
+        let start = Instant::now();
+        let (result, stats) = alloc::measure(f);
+        let time = start.elapsed();
+        (result, Self { time, stats })
+    }
+}
+
+/// Solving one part with the main solution or a variant
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Run {
+    pub part: Part,
+    /// `None` for the main solution
+    pub variant: Option<&'static str>,
+    pub answer: Result<Answer, SolveError>,
+    pub solve: Phase,
+}
+
+impl Run {
+    pub fn name(&self) -> &'static str {
+        self.variant.unwrap_or("main")
+    }
+
+    /// The answer, or why there is none
+    fn outcome(&self) -> String {
+        match &self.answer {
+            Ok(answer) => answer.clone(),
+            Err(error) => format!("failed: {}", error.message),
+        }
+    }
+}
+
+/// Parsing a day's input once, then solving every part and variant on it
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Profile {
+    pub day: u32,
+    pub parse: Phase,
+    pub runs: Vec<Run>,
+    /// Whether [`alloc::CountingAllocator`] was installed, all stats are zero otherwise
+    pub counting: bool,
+}
+
+///
+/// Times parsing and solving separately and counts their allocations, for every variant of
+/// `part` or of both parts. Allocations are only counted when the global allocator is
+/// [`alloc::CountingAllocator`]
+/// example
+/// ```
+/// # use advent_of_code_2020::profile::profile;
+/// # use advent_of_code_2020::solver::{registry, Part};
+/// let registry = registry();
+///
+/// let profile = profile(registry[&6].as_ref(), "abc\n\nab\nac", Some(Part::Two)).unwrap();
+///
+/// assert_eq!(profile.runs.len(), 1);
+/// assert_eq!(profile.runs[0].answer, Ok("4".into()));
+/// ```
+pub fn profile(
+    solver: &dyn DynSolver,
+    input: &str,
+    part: Option<Part>,
+) -> Result<Profile, ParseError> {
+    let (parsed, parse) = Phase::measure(|| solver.parse_boxed(input));
+    let parsed = parsed?;
+
+    let runs = Part::BOTH
+        .iter()
+        .filter(|p| part.is_none_or(|part| part == **p))
+        .flat_map(|part| {
+            std::iter::once(None)
+                .chain(solver.variant_names(*part).into_iter().map(Some))
+                .map(move |variant| (*part, variant))
+        })
+        .filter_map(|(part, variant)| {
+            let (answer, solve) = Phase::measure(|| solver.solve(parsed.as_ref(), part, variant));
+            Some(Run {
+                part,
+                variant,
+                answer: answer?,
+                solve,
+            })
+        })
+        .collect();
+
+    Ok(Profile {
+        day: solver.day(),
+        parse,
+        runs,
+        counting: alloc::is_counting(),
+    })
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Day {}", self.day)?;
+        if !self.counting {
+            writeln!(
+                f,
+                "  (allocations aren't counted without the counting allocator)"
+            )?;
+        }
+        let names = std::iter::once(String::from("parse"))
+            .chain(
+                self.runs
+                    .iter()
+                    .map(|run| format!("part {} {}", run.part, run.name())),
+            )
+            .collect::<Vec<_>>();
+        let width = names.iter().map(String::len).max().unwrap_or(0);
+        writeln!(
+            f,
+            "  {:<width$}  {:>12}  {:>12}  {:>12}  {:>12}  answer",
+            "phase",
+            "time",
+            "allocations",
+            "allocated",
+            "peak",
+            width = width
+        )?;
+        let phases = std::iter::once((self.parse, String::new()))
+            .chain(self.runs.iter().map(|run| (run.solve, run.outcome())));
+        for (name, (phase, answer)) in names.iter().zip(phases) {
+            writeln!(
+                f,
+                "  {:<width$}  {:>12}  {:>12}  {:>12}  {:>12}  {}",
+                name,
+                format!("{:.1?}", phase.time),
+                phase.stats.allocations,
+                alloc::bytes(phase.stats.allocated_bytes),
+                alloc::bytes(phase.stats.peak_bytes),
+                answer,
+                width = width
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::registry;
+
+    #[test]
+    fn test_parse_and_solve_are_measured_separately() {
+        let registry = registry();
+        let input = "nop +0\nacc +1\njmp +4\nacc +3\njmp -3\nacc -99\nacc +1\njmp -4\nacc +6";
+
+        let profile = profile(registry[&8].as_ref(), input, None).unwrap();
+
+        assert!(profile.counting);
+        // one String per instruction, plus the Vec holding them
+        assert!(profile.parse.stats.allocations >= 9);
+        let brute_force = profile
+            .runs
+            .iter()
+            .find(|run| run.variant == Some("brute_force"))
+            .unwrap();
+        assert_eq!(brute_force.answer, Ok("8".into()));
+        // every candidate patch is a full copy of the program
+        assert!(brute_force.solve.stats.allocations > profile.parse.stats.allocations);
+    }
+
+    #[test]
+    fn test_solve_errors_are_kept_per_run() {
+        let registry = registry();
+
+        let profile = profile(registry[&8].as_ref(), "jmp +3\nnop +0", Some(Part::One)).unwrap();
+
+        assert_eq!(profile.runs.len(), 2);
+        assert!(profile.runs.iter().all(|run| run.answer.is_err()));
+        assert!(profile
+            .to_string()
+            .contains("failed: jmp +3 at 0 jumps to 3"));
+    }
+
+    #[test]
+    fn test_parse_errors_are_passed_on() {
+        let registry = registry();
+
+        let error = profile(registry[&8].as_ref(), "nop +0\nfoo", None).unwrap_err();
+
+        assert_eq!(error.day, Some(8));
+    }
+}
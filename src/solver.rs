@@ -0,0 +1,295 @@
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+
+use crate::error::{ParseError, SolveError};
+use crate::{
+    day1, day10, day11, day12, day13, day14, day15, day16, day17_3d_game_of_life,
+    day18_math_homework, day19_message_rules, day2, day3, day4, day5, day6, day7, day8, day9,
+};
+
+/// The answer to a puzzle part, as it would be typed into the website
+pub type Answer = String;
+
+/// The answer to a puzzle part, or why the parsed input has none
+pub type Solution = Result<Answer, Box<dyn Error>>;
+
+/// Every solved day, keyed by day number
+pub type Registry = BTreeMap<u32, Box<dyn DynSolver>>;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Part {
+    One,
+    Two,
+}
+
+impl Part {
+    pub const BOTH: [Self; 2] = [Self::One, Self::Two];
+
+    pub const fn from_number(number: u32) -> Option<Self> {
+        match number {
+            1 => Some(Self::One),
+            2 => Some(Self::Two),
+            _ => None,
+        }
+    }
+
+    pub const fn number(self) -> u32 {
+        match self {
+            Self::One => 1,
+            Self::Two => 2,
+        }
+    }
+}
+
+impl fmt::Display for Part {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.number())
+    }
+}
+
+/// Parsed input that keeps the original text around, for variants that do their own parsing
+pub struct WithText<T> {
+    pub text: String,
+    pub parsed: T,
+}
+
+/// An alternative solution to one of the parts, kept around to compare against the main one
+pub struct Variant<I> {
+    pub part: Part,
+    pub name: &'static str,
+    pub solve: fn(&I) -> Solution,
+}
+
+impl<I> Variant<I> {
+    pub const fn new(part: Part, name: &'static str, solve: fn(&I) -> Solution) -> Self {
+        Self { part, name, solve }
+    }
+}
+
+///
+/// A single day of advent of code
+/// example
+/// ```
+/// # use advent_of_code_2020::error::ParseError;
+/// # use advent_of_code_2020::solver::{Solution, Solver};
+/// # use advent_of_code_2020::util::{parse_lines, try_parse};
+/// struct Sum;
+///
+/// impl Solver for Sum {
+///     type Input = Vec<u32>;
+///     const DAY: u32 = 26;
+///
+///     fn parse(&self, input: &str) -> Result<Vec<u32>, ParseError> {
+///         parse_lines(input, try_parse)
+///     }
+///
+///     fn part1(&self, input: &Vec<u32>) -> Solution {
+///         Ok(input.iter().sum::<u32>().to_string())
+///     }
+///
+///     fn part2(&self, input: &Vec<u32>) -> Option<Solution> {
+///         let product = input.iter().try_fold(1_u32, |product, n| product.checked_mul(*n));
+///         Some(product.map(|n| n.to_string()).ok_or_else(|| "the product overflows".into()))
+///     }
+/// }
+///
+/// let numbers = Sum.parse("2\n3\n4").unwrap();
+/// assert_eq!(Sum.part1(&numbers).unwrap(), "9");
+/// assert_eq!(Sum.part2(&numbers).unwrap().unwrap(), "24");
+/// assert!(Sum.part2(&vec![1 << 20, 1 << 20]).unwrap().is_err());
+/// assert_eq!(Sum.parse("2\nthree").unwrap_err().line, Some(2));
+/// ```
+pub trait Solver {
+    /// What the puzzle input is parsed into, shared by both parts and all variants
+    type Input;
+    const DAY: u32;
+
+    fn parse(&self, input: &str) -> Result<Self::Input, ParseError>;
+    fn part1(&self, input: &Self::Input) -> Solution;
+    /// `None` for days where only part 1 is solved
+    fn part2(&self, _input: &Self::Input) -> Option<Solution> {
+        None
+    }
+
+    fn variants(&self) -> Vec<Variant<Self::Input>> {
+        Vec::new()
+    }
+}
+
+/// Type erased version of [`Solver`] so that days with different inputs can live in the same [`Registry`]
+pub trait DynSolver {
+    fn day(&self) -> u32;
+    fn parse_boxed(&self, input: &str) -> Result<Box<dyn Any>, ParseError>;
+    /// Solves `part` using the main solution, or the named variant.
+    /// Returns `None` if there's no variant with that name, or the part isn't solved
+    fn solve(
+        &self,
+        parsed: &dyn Any,
+        part: Part,
+        variant: Option<&str>,
+    ) -> Option<Result<Answer, SolveError>>;
+    fn variant_names(&self, part: Part) -> Vec<&'static str>;
+
+    fn run(
+        &self,
+        input: &str,
+        part: Part,
+        variant: Option<&str>,
+    ) -> Result<Option<Result<Answer, SolveError>>, ParseError> {
+        let parsed = self.parse_boxed(input)?;
+        Ok(self.solve(parsed.as_ref(), part, variant))
+    }
+}
+
+impl<S> DynSolver for S
+where
+    S: Solver,
+    S::Input: 'static,
+{
+    fn day(&self) -> u32 {
+        S::DAY
+    }
+
+    fn parse_boxed(&self, input: &str) -> Result<Box<dyn Any>, ParseError> {
+        match self.parse(input) {
+            Ok(parsed) => Ok(Box::new(parsed)),
+            Err(e) => Err(e.on_day(S::DAY)),
+        }
+    }
+
+    fn solve(
+        &self,
+        parsed: &dyn Any,
+        part: Part,
+        variant: Option<&str>,
+    ) -> Option<Result<Answer, SolveError>> {
+        let input = parsed
+            .downcast_ref::<S::Input>()
+            .unwrap_or_else(|| panic!("input wasn't parsed by day {}", S::DAY));
+        let solution = match (variant, part) {
+            (None, Part::One) => Some(self.part1(input)),
+            (None, Part::Two) => self.part2(input),
+            (Some(name), part) => self
+                .variants()
+                .into_iter()
+                .find(|variant| variant.part == part && variant.name == name)
+                .map(|variant| (variant.solve)(input)),
+        }?;
+        Some(solution.map_err(|error| SolveError::new(error.to_string()).on_day(S::DAY)))
+    }
+
+    fn variant_names(&self, part: Part) -> Vec<&'static str> {
+        self.variants()
+            .into_iter()
+            .filter(|variant| variant.part == part)
+            .map(|variant| variant.name)
+            .collect()
+    }
+}
+
+/// All days that have at least part 1 solved
+pub fn registry() -> Registry {
+    let solvers: Vec<Box<dyn DynSolver>> = vec![
+        Box::new(day1::Day1),
+        Box::new(day2::Day2),
+        Box::new(day3::Day3),
+        Box::new(day4::Day4),
+        Box::new(day5::Day5),
+        Box::new(day6::Day6),
+        Box::new(day7::Day7),
+        Box::new(day8::Day8),
+        Box::new(day9::Day9),
+        Box::new(day10::Day10),
+        Box::new(day11::Day11),
+        Box::new(day12::Day12),
+        Box::new(day13::Day13),
+        Box::new(day14::Day14),
+        Box::new(day15::Day15),
+        Box::new(day16::Day16),
+        Box::new(day17_3d_game_of_life::Day17),
+        Box::new(day18_math_homework::Day18),
+        Box::new(day19_message_rules::Day19),
+    ];
+
+    solvers
+        .into_iter()
+        .map(|solver| (solver.day(), solver))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_is_keyed_by_day() {
+        let registry = registry();
+
+        assert_eq!(registry.len(), 19);
+        for (day, solver) in &registry {
+            assert_eq!(*day, solver.day());
+        }
+    }
+
+    #[test]
+    fn test_run_variant() {
+        let registry = registry();
+        let day6 = &registry[&6];
+
+        assert_eq!(day6.variant_names(Part::One), vec!["fp", "bit"]);
+        assert_eq!(
+            day6.run("abc\n\nab\nac", Part::One, None),
+            Ok(Some(Ok("6".into())))
+        );
+        assert_eq!(
+            day6.run("abc\n\nab\nac", Part::One, Some("bit")),
+            Ok(Some(Ok("6".into())))
+        );
+        assert_eq!(
+            day6.run("abc\n\nab\nac", Part::Two, None),
+            Ok(Some(Ok("4".into())))
+        );
+        assert_eq!(day6.run("abc", Part::Two, Some("bit")), Ok(None));
+    }
+
+    #[test]
+    fn test_part2_is_optional() {
+        let registry = registry();
+        let input = "0: 1 1\n1: \"a\"\n\naa\nab";
+
+        assert_eq!(
+            registry[&19].run(input, Part::One, None),
+            Ok(Some(Ok("1".into())))
+        );
+        assert_eq!(registry[&19].run(input, Part::Two, None), Ok(None));
+    }
+
+    #[test]
+    fn test_solve_errors_know_the_day() {
+        let registry = registry();
+
+        let solution = registry[&8].run("jmp +3\nnop +0", Part::One, None);
+
+        assert_eq!(
+            solution,
+            Ok(Some(Err(SolveError::new(
+                "jmp +3 at 0 jumps to 3, outside of the program of 2 instructions"
+            )
+            .on_day(8))))
+        );
+    }
+
+    #[test]
+    fn test_parse_error_knows_the_day() {
+        let registry = registry();
+
+        let error = registry[&6].run("abc\n\naB", Part::One, None).unwrap_err();
+
+        assert_eq!(
+            (error.day, error.line, error.column),
+            (Some(6), Some(3), Some(2))
+        );
+    }
+}
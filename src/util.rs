@@ -1,5 +1,9 @@
 use std::fmt::Debug;
+use std::path::Path;
 use std::str::FromStr;
+use std::{fs, io};
+
+use crate::error::ParseError;
 
 ///
 /// Helper method to split and parse a string based on a delimiter
@@ -36,6 +40,123 @@ where
     }
 }
 
+///
+/// Fallible version of [`str_split`], the error points at the part of `input` that didn't parse.
+/// Unlike [`str_split`] everything after the first `delimiter` ends up in the right side
+/// example
+/// ```
+/// # use advent_of_code_2020::util::try_str_split;
+///
+/// let (left, right): (u32, String) = try_str_split("10::hello", "::").unwrap();
+/// assert_eq!(left, 10);
+/// assert_eq!(right, "hello");
+///
+/// let error = try_str_split::<String, u32>("mem = abc", "=").unwrap_err();
+/// assert_eq!(error.column, Some(7));
+/// assert_eq!(error.expected, "u32");
+/// ```
+pub fn try_str_split<L: FromStr, R: FromStr>(
+    input: &str,
+    delimiter: &str,
+) -> Result<(L, R), ParseError> {
+    let mut iter = input.splitn(2, delimiter);
+    match (iter.next(), iter.next()) {
+        (Some(left), Some(right)) => {
+            let right_offset = left.len() + delimiter.len();
+            let left = try_parse(left)?;
+            let right = try_parse(right).map_err(|e| e.shifted(right_offset))?;
+            Ok((left, right))
+        }
+        _ => Err(ParseError::new(format!("{:?}", delimiter), input).at_column(input.len() + 1)),
+    }
+}
+
+///
+/// Parses a trimmed `T` from `input`, the error points at where the value starts
+/// example
+/// ```
+/// # use advent_of_code_2020::util::try_parse;
+/// assert_eq!(try_parse::<i32>(" -4 "), Ok(-4));
+/// assert_eq!(try_parse::<i32>("  x").unwrap_err().column, Some(3));
+/// ```
+pub fn try_parse<T: FromStr>(input: &str) -> Result<T, ParseError> {
+    let trimmed = input.trim();
+    trimmed.parse().map_err(|_| {
+        let leading_whitespace = input.len() - input.trim_start().len();
+        ParseError::expected_type::<T>(trimmed).at_column(leading_whitespace + 1)
+    })
+}
+
+///
+/// Parses every line of `input` with `parse_line`, adding the line number to any error
+/// example
+/// ```
+/// # use advent_of_code_2020::util::{parse_lines, try_parse};
+/// assert_eq!(parse_lines("1\n2", try_parse::<u8>), Ok(vec![1, 2]));
+/// assert_eq!(parse_lines("1\n2\nx", try_parse::<u8>).unwrap_err().line, Some(3));
+/// ```
+pub fn parse_lines<T>(
+    input: &str,
+    parse_line: impl Fn(&str) -> Result<T, ParseError>,
+) -> Result<Vec<T>, ParseError> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| parse_line(line).map_err(|e| e.at_line(i + 1)))
+        .collect()
+}
+
+///
+/// Parses every `delimiter` separated item of `input`, the error points at the item that didn't parse
+/// example
+/// ```
+/// # use advent_of_code_2020::util::try_parse_separated;
+/// assert_eq!(try_parse_separated("7,13,59", ","), Ok(vec![7_u32, 13, 59]));
+/// assert_eq!(try_parse_separated::<u32>("7,13,x", ",").unwrap_err().column, Some(6));
+/// ```
+pub fn try_parse_separated<T: FromStr>(input: &str, delimiter: &str) -> Result<Vec<T>, ParseError> {
+    let mut offset = 0;
+    input
+        .split(delimiter)
+        .map(|item| {
+            let parsed = try_parse(item).map_err(|e| e.shifted(offset));
+            offset += item.len() + delimiter.len();
+            parsed
+        })
+        .collect()
+}
+
+///
+/// Whitespace separated words and the 1-based columns they start at
+/// example
+/// ```
+/// # use advent_of_code_2020::util::words;
+/// assert_eq!(words(" set a  +5"), vec![(2, "set"), (6, "a"), (9, "+5")]);
+/// ```
+pub fn words(line: &str) -> Vec<(usize, &str)> {
+    let mut words = vec![];
+    let mut start = None;
+    for (index, char) in line
+        .char_indices()
+        .chain(std::iter::once((line.len(), ' ')))
+    {
+        match (start, char.is_whitespace()) {
+            (None, false) => start = Some(index),
+            (Some(from), true) => {
+                words.push((from + 1, &line[from..index]));
+                start = None;
+            }
+            _ => (),
+        }
+    }
+    words
+}
+
+/// Reads a puzzle input, trimming trailing newlines the same way cargo-aoc does
+pub fn read_input(path: &Path) -> io::Result<String> {
+    fs::read_to_string(path).map(|input| input.trim_end_matches('\n').to_owned())
+}
+
 ///
 /// Helper method to add a signed integer to a usize
 /// example
@@ -70,4 +191,16 @@ mod tests {
         assert_eq!(left, 10);
         assert_eq!(right, 'A');
     }
+
+    #[test]
+    fn test_try_str_split_reports_position() {
+        let error = try_str_split::<u32, u32>("  x: 4", ":").unwrap_err();
+        assert_eq!((error.column, error.found.as_str()), (Some(3), "x"));
+
+        let error = try_str_split::<u32, u32>("4:  y", ":").unwrap_err();
+        assert_eq!((error.column, error.found.as_str()), (Some(5), "y"));
+
+        let error = try_str_split::<u32, u32>("4 4", ":").unwrap_err();
+        assert_eq!((error.column, error.expected.as_str()), (Some(4), "\":\""));
+    }
 }
@@ -0,0 +1,904 @@
+use std::error::Error;
+use std::fmt;
+use std::ops::Index;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::error::ParseError;
+use crate::util::{parse_lines, try_str_split};
+
+pub mod asm;
+pub mod cfg;
+pub mod coverage;
+pub mod debugger;
+pub mod journal;
+pub mod opcode;
+pub mod search;
+pub mod threaded;
+
+use coverage::Coverage;
+use journal::{Entry, Snapshot};
+use opcode::{Flow, Op, OpcodeTable, Operand, REGISTERS};
+
+/// One line of a handheld console program, like `acc +3`
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Instruction {
+    /// Adds the argument to the accumulator
+    Acc(i32),
+    /// Jumps relative to itself
+    Jmp(i32),
+    /// Does nothing, the argument is ignored
+    Nop(i32),
+}
+
+impl Instruction {
+    pub const fn mnemonic(self) -> &'static str {
+        match self {
+            Self::Acc(_) => "acc",
+            Self::Jmp(_) => "jmp",
+            Self::Nop(_) => "nop",
+        }
+    }
+
+    pub const fn argument(self) -> i32 {
+        match self {
+            Self::Acc(argument) | Self::Jmp(argument) | Self::Nop(argument) => argument,
+        }
+    }
+
+    /// `jmp` becomes `nop` and the other way around, `acc` stays the same
+    pub const fn flipped(self) -> Self {
+        match self {
+            Self::Acc(_) => self,
+            Self::Jmp(argument) => Self::Nop(argument),
+            Self::Nop(argument) => Self::Jmp(argument),
+        }
+    }
+}
+
+impl FromStr for Instruction {
+    type Err = ParseError;
+
+    fn from_str(line: &str) -> Result<Self, ParseError> {
+        let (mnemonic, argument): (String, i32) = try_str_split(line, " ")?;
+        match mnemonic.as_str() {
+            "acc" => Ok(Self::Acc(argument)),
+            "jmp" => Ok(Self::Jmp(argument)),
+            "nop" => Ok(Self::Nop(argument)),
+            _ => Err(ParseError::new("one of acc, jmp or nop", mnemonic).at_column(1)),
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {:+}", self.mnemonic(), self.argument())
+    }
+}
+
+///
+/// A parsed program, one instruction per line. Clones share the instructions until one of them
+/// is patched, so copying a program is cheap
+/// example
+/// ```
+/// # use advent_of_code_2020::vm::{Instruction, Program};
+/// let program: Program = "nop +0\nacc +1\njmp -2".parse().unwrap();
+///
+/// assert_eq!(program.len(), 3);
+/// assert_eq!(program[1], Instruction::Acc(1));
+/// assert_eq!(program.to_string(), "nop +0\nacc +1\njmp -2");
+/// ```
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Program {
+    instructions: Arc<Vec<Instruction>>,
+}
+
+impl Program {
+    pub fn len(&self) -> usize {
+        self.instructions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instructions.is_empty()
+    }
+
+    pub fn get(&self, address: usize) -> Option<Instruction> {
+        self.instructions.get(address).copied()
+    }
+
+    pub fn instructions(&self) -> &[Instruction] {
+        &self.instructions
+    }
+
+    /// Replaces the instruction at `address`, returning the old one
+    ///
+    /// Panics if `address` is outside the program
+    pub fn patch(&mut self, address: usize, instruction: Instruction) -> Instruction {
+        std::mem::replace(
+            &mut Arc::make_mut(&mut self.instructions)[address],
+            instruction,
+        )
+    }
+}
+
+impl From<Vec<Instruction>> for Program {
+    fn from(instructions: Vec<Instruction>) -> Self {
+        Self {
+            instructions: Arc::new(instructions),
+        }
+    }
+}
+
+impl Index<usize> for Program {
+    type Output = Instruction;
+
+    fn index(&self, address: usize) -> &Instruction {
+        &self.instructions[address]
+    }
+}
+
+impl FromStr for Program {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<Self, ParseError> {
+        parse_lines(input, str::parse).map(Self::from)
+    }
+}
+
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (address, instruction) in self.instructions.iter().enumerate() {
+            if address > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", instruction)?;
+        }
+        Ok(())
+    }
+}
+
+/// Why [`Machine::run`] stopped
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Exit {
+    /// The instruction pointer moved to just after the last instruction
+    Terminated,
+    /// A `hlt` was executed
+    Halted,
+    /// The next instruction has been executed before, running on would loop forever
+    Loop(Cycle),
+    /// The step limit was reached before the program terminated or looped
+    OutOfSteps,
+}
+
+/// The instructions a looping program repeats forever
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Cycle {
+    /// Addresses in the order they're executed, starting with the one the loop is entered at
+    pub addresses: Vec<usize>,
+    /// The step at which the entry address was first executed
+    pub entered_at_step: u64,
+    /// The accumulator just before the entry address was first executed
+    pub acc_at_entry: i64,
+}
+
+impl Cycle {
+    /// The first address of the loop, the one that's about to be executed a second time
+    pub fn entry(&self) -> usize {
+        self.addresses[0]
+    }
+}
+
+/// Something the program did that the machine can't execute
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Fault {
+    /// A jump to before the first instruction, or further than just past the last one
+    JumpOutOfBounds {
+        address: usize,
+        instruction: Op,
+        target: i64,
+        len: usize,
+    },
+    /// The result of the instruction at `address` doesn't fit in a register
+    Overflow { address: usize },
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::JumpOutOfBounds {
+                address,
+                instruction,
+                target,
+                len,
+            } => write!(
+                f,
+                "{} at {} jumps to {}, outside of the program of {} instructions",
+                instruction, address, target, len
+            ),
+            Self::Overflow { address } => write!(f, "the instruction at {} overflows", address),
+        }
+    }
+}
+
+impl Error for Fault {}
+
+///
+/// Executes the instructions of an [`OpcodeTable`] one at a time, with registers, an output
+/// stream and code that can modify itself. A [`Program`] runs on the handheld's table
+/// example
+/// ```
+/// # use advent_of_code_2020::vm::{Exit, Machine};
+/// let mut machine = Machine::new("acc +2\njmp +2\nacc +5\nacc -1".parse().unwrap());
+///
+/// machine.step().unwrap();
+/// assert_eq!((machine.ip(), machine.acc()), (1, 2));
+///
+/// assert_eq!(machine.run(), Ok(Exit::Terminated));
+/// assert_eq!((machine.ip(), machine.acc(), machine.steps()), (4, 1, 3));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Machine {
+    table: Arc<OpcodeTable>,
+    /// Shared with snapshots until either is patched
+    code: Arc<Vec<Op>>,
+    ip: usize,
+    registers: [i64; REGISTERS],
+    output: Vec<i64>,
+    halted: bool,
+    steps: u64,
+    step_limit: Option<u64>,
+    /// Changes every time the code is modified
+    revision: u64,
+    journal: Option<Vec<Entry>>,
+    coverage: Option<Coverage>,
+    /// What the instruction being executed toggled, and what was there before
+    toggled: Option<(usize, Op)>,
+}
+
+impl Machine {
+    /// `program` on the handheld's instructions
+    pub fn new(program: Program) -> Self {
+        let code = program.instructions().iter().copied().map(Op::from);
+        Self::with_code(OpcodeTable::handheld(), code.collect())
+    }
+
+    ///
+    /// Parses `input` with `table`
+    /// example
+    /// ```
+    /// # use advent_of_code_2020::vm::opcode::OpcodeTable;
+    /// # use advent_of_code_2020::vm::{Exit, Machine};
+    /// // counts down from 3
+    /// let input = "set a +3\nout a\nadd a -1\njnz a -2\nhlt";
+    /// let mut machine = Machine::load(OpcodeTable::extended(), input).unwrap();
+    ///
+    /// assert_eq!(machine.run_to_end(), Ok(Exit::Halted));
+    /// assert_eq!(machine.output(), &[3, 2, 1]);
+    /// assert_eq!(machine.register("a"), Some(0));
+    /// ```
+    pub fn load(table: OpcodeTable, input: &str) -> Result<Self, ParseError> {
+        let code = table.parse(input)?;
+        Ok(Self::with_code(table, code))
+    }
+
+    /// Runs `code`, which has to come from `table`
+    pub fn with_code(table: OpcodeTable, code: Vec<Op>) -> Self {
+        Self::start(Arc::new(table), Arc::new(code))
+    }
+
+    fn start(table: Arc<OpcodeTable>, code: Arc<Vec<Op>>) -> Self {
+        Self {
+            table,
+            code,
+            ip: 0,
+            registers: [0; REGISTERS],
+            output: vec![],
+            halted: false,
+            steps: 0,
+            step_limit: None,
+            revision: 0,
+            journal: None,
+            coverage: None,
+            toggled: None,
+        }
+    }
+
+    /// Makes the machine record every step it executes, so that it can be rewound
+    pub fn journaled(mut self) -> Self {
+        self.journal.get_or_insert_with(Vec::new);
+        self
+    }
+
+    /// Makes the machine count how often it executes every instruction and takes every jump
+    pub fn profiled(mut self) -> Self {
+        let len = self.code.len();
+        self.coverage.get_or_insert_with(|| Coverage::new(len));
+        self
+    }
+
+    /// Makes running give up after `step_limit` steps in total
+    pub fn step_limit(mut self, step_limit: u64) -> Self {
+        self.step_limit = Some(step_limit);
+        self
+    }
+
+    /// The same code from the start, with nothing executed, journaled and profiled if it was
+    pub fn restarted(&self) -> Self {
+        Self {
+            step_limit: self.step_limit,
+            journal: self.journal.as_ref().map(|_| vec![]),
+            coverage: self
+                .coverage
+                .as_ref()
+                .map(|_| Coverage::new(self.code.len())),
+            ..Self::start(Arc::clone(&self.table), Arc::clone(&self.code))
+        }
+    }
+
+    pub fn table(&self) -> &OpcodeTable {
+        &self.table
+    }
+
+    /// The instructions as they are now, patches and toggles included
+    pub fn code(&self) -> &[Op] {
+        &self.code
+    }
+
+    /// The code the way it's written, one instruction per line
+    pub fn listing(&self) -> String {
+        self.code
+            .iter()
+            .map(Op::to_string)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// The instruction pointer, the address of the next instruction to execute
+    pub const fn ip(&self) -> usize {
+        self.ip
+    }
+
+    pub const fn acc(&self) -> i64 {
+        self.registers[0]
+    }
+
+    /// The value of the register called `name`, `None` if there's no such register
+    pub fn register(&self, name: &str) -> Option<i64> {
+        match name.parse() {
+            Ok(Operand::Register(register)) => Some(self.registers[register]),
+            _ => None,
+        }
+    }
+
+    /// Sets a register before running, like an input to the program
+    ///
+    /// Panics if there's no register called `name`
+    pub fn set_register(&mut self, name: &str, value: i64) {
+        match name.parse() {
+            Ok(register @ Operand::Register(_)) => self.write(register, value),
+            _ => panic!("there's no register {}", name),
+        }
+    }
+
+    pub fn output(&self) -> &[i64] {
+        &self.output
+    }
+
+    /// Number of instructions executed so far
+    pub const fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// Changes every time the code is patched or toggled
+    pub const fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    pub const fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    pub fn is_terminated(&self) -> bool {
+        self.ip >= self.code.len()
+    }
+
+    /// Replaces an instruction of the running program, returning the old one
+    ///
+    /// Panics if `address` is outside the program
+    pub fn patch(&mut self, address: usize, instruction: Op) -> Op {
+        self.revision += 1;
+        std::mem::replace(&mut Arc::make_mut(&mut self.code)[address], instruction)
+    }
+
+    pub const fn read(&self, operand: Operand) -> i64 {
+        match operand {
+            Operand::Register(register) => self.registers[register],
+            Operand::Immediate(value) => value,
+        }
+    }
+
+    /// Sets a register, writes to immediates are ignored
+    pub fn write(&mut self, operand: Operand, value: i64) {
+        if let Operand::Register(register) = operand {
+            self.registers[register] = value;
+        }
+    }
+
+    /// Combines a register with `value`, faulting if the result doesn't fit
+    pub fn update(
+        &mut self,
+        register: Operand,
+        value: i64,
+        combine: fn(i64, i64) -> Option<i64>,
+    ) -> Result<Flow, Fault> {
+        let result =
+            combine(self.read(register), value).ok_or(Fault::Overflow { address: self.ip })?;
+        self.write(register, result);
+        Ok(Flow::Next)
+    }
+
+    /// Appends to the output stream
+    pub fn out(&mut self, value: i64) {
+        self.output.push(value);
+    }
+
+    /// Toggles the instruction `offset` away from the current one, nothing happens if there's
+    /// no instruction there
+    #[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+    pub fn toggle(&mut self, offset: i64) {
+        let address = match (self.ip as i64).checked_add(offset) {
+            Some(address) if (0..self.code.len() as i64).contains(&address) => address as usize,
+            _ => return,
+        };
+        if let Some(toggled) = self.table.toggled(self.code[address]) {
+            let before = self.patch(address, toggled);
+            self.toggled.get_or_insert((address, before));
+        }
+    }
+
+    /// Executes the next instruction and returns it, or `None` if the program has terminated or
+    /// halted. A faulting instruction leaves the machine as it was. Toggling can leave a number
+    /// where a register is expected, like `add 1 2`, those instructions are skipped
+    pub fn step(&mut self) -> Result<Option<Op>, Fault> {
+        let instruction = match self.code.get(self.ip) {
+            Some(instruction) if !self.halted => *instruction,
+            _ => return Ok(None),
+        };
+        let (address, registers, output) = (self.ip, self.registers, self.output.len());
+        let operands = instruction.operands();
+        let valid = instruction
+            .kinds
+            .iter()
+            .zip(operands)
+            .all(|(kind, operand)| kind.accepts(*operand));
+        let flow = if valid {
+            (self.table.execute(instruction))(self, operands)
+        } else {
+            Ok(Flow::Next)
+        };
+        let next = flow.and_then(|flow| match flow {
+            Flow::Next => Ok((flow, self.ip + 1)),
+            Flow::Jump(offset) => Ok((flow, self.jump_target(instruction, offset)?)),
+            Flow::Halt => Ok((flow, self.ip)),
+        });
+        let toggled = self.toggled.take();
+        let (flow, next) = match next {
+            Ok(next) => next,
+            Err(fault) => {
+                self.undo(registers, output, toggled);
+                return Err(fault);
+            }
+        };
+        self.halted = flow == Flow::Halt;
+        self.ip = next;
+        if let Some(coverage) = &mut self.coverage {
+            coverage.record(address, next, matches!(flow, Flow::Jump(_)));
+        }
+        if let Some(journal) = &mut self.journal {
+            journal.push(Entry {
+                step: self.steps,
+                ip: address,
+                instruction,
+                registers_before: registers,
+                acc: self.registers[0],
+                output_before: output,
+                toggled,
+            });
+        }
+        self.steps += 1;
+        Ok(Some(instruction))
+    }
+
+    /// Puts back what executing an instruction changed
+    fn undo(&mut self, registers: [i64; REGISTERS], output: usize, toggled: Option<(usize, Op)>) {
+        self.registers = registers;
+        self.output.truncate(output);
+        if let Some((address, before)) = toggled {
+            self.patch(address, before);
+        }
+    }
+
+    /// Every step executed since journaling was turned on, empty if it's off
+    pub fn journal(&self) -> &[Entry] {
+        self.journal.as_deref().unwrap_or_default()
+    }
+
+    /// What has been executed since profiling was turned on, `None` if it's off. Rewinding
+    /// doesn't take hits back, they count all the work done
+    pub const fn coverage(&self) -> Option<&Coverage> {
+        self.coverage.as_ref()
+    }
+
+    /// Undoes up to `steps` steps of the journal, returns how many were undone
+    pub fn rewind(&mut self, steps: u64) -> u64 {
+        let mut rewound = 0;
+        while rewound < steps {
+            match self.journal.as_mut().and_then(Vec::pop) {
+                Some(entry) => {
+                    self.undo(entry.registers_before, entry.output_before, entry.toggled);
+                    self.ip = entry.ip;
+                    self.steps = entry.step;
+                    self.halted = false;
+                    rewound += 1;
+                }
+                None => break,
+            }
+        }
+        rewound
+    }
+
+    ///
+    /// The current state, to go back to with [`Machine::restore`]
+    /// example
+    /// ```
+    /// # use advent_of_code_2020::vm::Machine;
+    /// let mut machine = Machine::new("acc +1\nacc +2\nacc +3".parse().unwrap()).journaled();
+    /// machine.step().unwrap();
+    ///
+    /// let snapshot = machine.snapshot();
+    /// machine.run().unwrap();
+    /// assert_eq!(machine.acc(), 6);
+    ///
+    /// machine.restore(&snapshot);
+    /// assert_eq!((machine.ip(), machine.acc(), machine.journal().len()), (1, 1, 1));
+    /// ```
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            code: Arc::clone(&self.code),
+            journal: self.journal.clone(),
+            registers: self.registers,
+            output: self.output.clone(),
+            halted: self.halted,
+            ip: self.ip,
+            steps: self.steps,
+        }
+    }
+
+    /// Goes back, or forward, to `snapshot`. The journal becomes the steps that led to it, even if
+    /// they were taken after a restore this machine has since gone back on
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        self.code = Arc::clone(&snapshot.code);
+        self.revision += 1;
+        self.registers = snapshot.registers;
+        self.output.clone_from(&snapshot.output);
+        self.halted = snapshot.halted;
+        self.ip = snapshot.ip;
+        self.steps = snapshot.steps;
+        if let Some(journal) = &mut self.journal {
+            journal.clone_from(snapshot.journal.as_ref().unwrap_or(&Vec::new()));
+        }
+    }
+
+    #[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+    fn jump_target(&self, instruction: Op, offset: i64) -> Result<usize, Fault> {
+        let len = self.code.len();
+        match (self.ip as i64).checked_add(offset) {
+            Some(target) if (0..=len as i64).contains(&target) => Ok(target as usize),
+            target => Err(Fault::JumpOutOfBounds {
+                address: self.ip,
+                instruction,
+                target: target.unwrap_or(i64::MAX),
+                len,
+            }),
+        }
+    }
+
+    /// Whether the program has stopped by itself, by halting or running off the end
+    fn exit(&self) -> Option<Exit> {
+        if self.halted {
+            Some(Exit::Halted)
+        } else if self.is_terminated() {
+            Some(Exit::Terminated)
+        } else {
+            None
+        }
+    }
+
+    fn is_out_of_steps(&self) -> bool {
+        self.step_limit.is_some_and(|limit| self.steps >= limit)
+    }
+
+    /// Runs until the program terminates, halts, is about to execute an instruction for the
+    /// second time since the code last changed, or runs out of steps. Without conditional jumps
+    /// a repeated instruction means the program loops forever, like on day 8
+    pub fn run(&mut self) -> Result<Exit, Fault> {
+        // the step and accumulator of the first visit of every address, and the order of visits
+        let mut visits: Vec<Option<(u64, i64)>> = vec![None; self.code.len()];
+        let mut trace = vec![];
+        let mut revision = self.revision;
+        loop {
+            if let Some(exit) = self.exit() {
+                return Ok(exit);
+            }
+            if revision != self.revision {
+                visits.iter_mut().for_each(|visit| *visit = None);
+                trace.clear();
+                revision = self.revision;
+            }
+            let visit = &mut visits[self.ip];
+            if let Some((step, acc)) = *visit {
+                let start = trace.len() - (self.steps - step) as usize;
+                return Ok(Exit::Loop(Cycle {
+                    addresses: trace.split_off(start),
+                    entered_at_step: step,
+                    acc_at_entry: acc,
+                }));
+            }
+            *visit = Some((self.steps, self.acc()));
+            if self.is_out_of_steps() {
+                return Ok(Exit::OutOfSteps);
+            }
+            trace.push(self.ip);
+            self.step()?;
+        }
+    }
+
+    /// Runs like [`Machine::run`], but doesn't stop at repeated instructions, for programs that
+    /// loop on conditional jumps. A step limit keeps ones that never end from running forever
+    pub fn run_to_end(&mut self) -> Result<Exit, Fault> {
+        loop {
+            if let Some(exit) = self.exit() {
+                return Ok(exit);
+            }
+            if self.is_out_of_steps() {
+                return Ok(Exit::OutOfSteps);
+            }
+            self.step()?;
+        }
+    }
+
+    ///
+    /// The values the program outputs from now on, running it as far as needed for each one.
+    /// Ends when the program is done or out of steps
+    /// example
+    /// ```
+    /// # use advent_of_code_2020::vm::opcode::OpcodeTable;
+    /// # use advent_of_code_2020::vm::Machine;
+    /// // outputs 0, 1, 0, 1, ... forever
+    /// let mut machine = Machine::load(OpcodeTable::extended(), "out +0\nout +1\njmp -2").unwrap();
+    ///
+    /// let signal = machine.outputs().take(5).collect::<Result<Vec<_>, _>>().unwrap();
+    /// assert_eq!(signal, vec![0, 1, 0, 1, 0]);
+    /// ```
+    pub fn outputs(&mut self) -> Outputs<'_> {
+        let next = self.output.len();
+        Outputs {
+            machine: self,
+            next,
+        }
+    }
+}
+
+/// Iterator over the output of a running [`Machine`], see [`Machine::outputs`]
+#[derive(Debug)]
+pub struct Outputs<'a> {
+    machine: &'a mut Machine,
+    next: usize,
+}
+
+impl Iterator for Outputs<'_> {
+    type Item = Result<i64, Fault>;
+
+    fn next(&mut self) -> Option<Result<i64, Fault>> {
+        while self.machine.output.len() <= self.next {
+            if self.machine.exit().is_some() || self.machine.is_out_of_steps() {
+                return None;
+            }
+            if let Err(fault) = self.machine.step() {
+                return Some(Err(fault));
+            }
+        }
+        self.next += 1;
+        Some(Ok(self.machine.output[self.next - 1]))
+    }
+}
+
+/// Programs to test with, shared by the tests of the vm and everything built on it
+#[cfg(test)]
+pub(crate) mod testing {
+    use std::ops::Range;
+
+    use proptest::prelude::*;
+
+    use super::{Instruction, Program};
+
+    /// The example from the puzzle text, it loops back to `acc +1` before reaching the end
+    pub(crate) const LOOPING: &str =
+        "nop +0\nacc +1\njmp +4\nacc +3\njmp -3\nacc -99\nacc +1\njmp -4\nacc +6";
+
+    /// Programs with a length in `len`, jumps may land up to `overshoot` instructions outside
+    pub(crate) fn programs(len: Range<i32>, overshoot: i32) -> impl Strategy<Value = Program> {
+        len.prop_flat_map(move |len| {
+            prop::collection::vec(instruction(len, overshoot), len as usize).prop_map(Program::from)
+        })
+    }
+
+    fn instruction(len: i32, overshoot: i32) -> impl Strategy<Value = Instruction> {
+        prop_oneof![
+            (-3..3).prop_map(Instruction::Acc),
+            (-len - overshoot..=len + overshoot).prop_map(Instruction::Jmp),
+            (-len..=len).prop_map(Instruction::Nop),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::testing::LOOPING;
+    use super::*;
+
+    #[test]
+    fn test_parse_errors() {
+        let error = "nop +0\nacc +1\njmp four".parse::<Program>().unwrap_err();
+        assert_eq!((error.line, error.column), (Some(3), Some(5)));
+        assert_eq!(error.expected, "i32");
+
+        let error = "nop +0\nxyz +3".parse::<Program>().unwrap_err();
+        assert_eq!((error.line, error.column), (Some(2), Some(1)));
+    }
+
+    #[test]
+    fn test_stops_before_repeating_an_instruction() {
+        let mut machine = Machine::new(LOOPING.parse().unwrap());
+
+        let cycle = match machine.run() {
+            Ok(Exit::Loop(cycle)) => cycle,
+            other => panic!("expected a loop, got {:?}", other),
+        };
+        assert_eq!((machine.ip(), machine.acc()), (1, 5));
+        assert_eq!(cycle.addresses, vec![1, 2, 6, 7, 3, 4]);
+        assert_eq!(
+            (cycle.entry(), cycle.entered_at_step, cycle.acc_at_entry),
+            (1, 1, 0)
+        );
+    }
+
+    #[test]
+    fn test_step_after_termination() {
+        let mut machine = Machine::new("acc +7".parse().unwrap());
+
+        assert_eq!(machine.step(), Ok(Some(Instruction::Acc(7).into())));
+        assert!(machine.is_terminated());
+        assert_eq!(machine.step(), Ok(None));
+        assert_eq!(machine.acc(), 7);
+    }
+
+    #[test]
+    fn test_jmp_zero_loops() {
+        let mut machine = Machine::new("acc +1\njmp +0".parse().unwrap());
+
+        match machine.run() {
+            Ok(Exit::Loop(cycle)) => assert_eq!(cycle.addresses, vec![1]),
+            other => panic!("expected a loop, got {:?}", other),
+        }
+        assert_eq!((machine.ip(), machine.acc()), (1, 1));
+    }
+
+    #[test]
+    fn test_cycle_entered_after_a_prefix() {
+        let mut machine = Machine::new("acc +1\nacc +2\nnop +0\nacc +3\njmp -2".parse().unwrap());
+
+        match machine.run() {
+            Ok(Exit::Loop(cycle)) => {
+                assert_eq!(cycle.addresses, vec![2, 3, 4]);
+                assert_eq!((cycle.entered_at_step, cycle.acc_at_entry), (2, 3));
+            }
+            other => panic!("expected a loop, got {:?}", other),
+        }
+        assert_eq!(machine.acc(), 6);
+    }
+
+    #[test]
+    fn test_jumps_out_of_bounds() {
+        let mut machine = Machine::new("acc +1\njmp -2".parse().unwrap());
+
+        let fault = machine.run().unwrap_err();
+        assert_eq!(
+            fault,
+            Fault::JumpOutOfBounds {
+                address: 1,
+                instruction: Instruction::Jmp(-2).into(),
+                target: -1,
+                len: 2,
+            }
+        );
+        assert_eq!((machine.ip(), machine.steps()), (1, 1));
+
+        let mut machine = Machine::new("jmp +2\nnop +0".parse().unwrap());
+        assert_eq!(machine.run(), Ok(Exit::Terminated));
+        let mut machine = Machine::new("jmp +3\nnop +0".parse().unwrap());
+        assert!(machine.run().is_err());
+    }
+
+    #[test]
+    fn test_rewind() {
+        let mut machine = Machine::new(LOOPING.parse().unwrap()).journaled();
+        machine.run().unwrap();
+        let end = (machine.ip(), machine.acc(), machine.steps());
+
+        assert_eq!(machine.rewind(3), 3);
+        assert_eq!((machine.ip(), machine.acc(), machine.steps()), (7, 2, 4));
+        for _ in 0..3 {
+            machine.step().unwrap();
+        }
+        assert_eq!((machine.ip(), machine.acc(), machine.steps()), end);
+
+        assert_eq!(machine.rewind(100), 7);
+        assert_eq!((machine.ip(), machine.acc(), machine.steps()), (0, 0, 0));
+    }
+
+    #[test]
+    fn test_snapshots_keep_their_program() {
+        let mut machine = Machine::new(LOOPING.parse().unwrap()).journaled();
+        let start = machine.snapshot();
+
+        machine.patch(7, Instruction::Nop(-4).into());
+        assert_eq!(machine.run(), Ok(Exit::Terminated));
+        let patched = machine.snapshot();
+
+        machine.restore(&start);
+        assert!(machine.journal().is_empty());
+        assert!(matches!(machine.run(), Ok(Exit::Loop(_))));
+
+        machine.restore(&patched);
+        assert_eq!(
+            (machine.acc(), machine.code()[7]),
+            (8, Instruction::Nop(-4).into())
+        );
+    }
+
+    #[test]
+    fn test_rewind_after_restoring_forward() {
+        let mut machine = Machine::new(LOOPING.parse().unwrap()).journaled();
+        let start = machine.snapshot();
+
+        machine.patch(7, Instruction::Nop(-4).into());
+        machine.run().unwrap();
+        let patched = machine.snapshot();
+
+        machine.restore(&start);
+        machine.run().unwrap();
+        machine.restore(&patched);
+        assert_eq!(machine.journal(), patched.journal.as_deref().unwrap());
+
+        assert_eq!(machine.rewind(1), 1);
+        assert_eq!((machine.ip(), machine.acc(), machine.steps()), (8, 2, 5));
+    }
+
+    #[test]
+    fn test_journal_is_off_by_default() {
+        let mut machine = Machine::new(LOOPING.parse().unwrap());
+        machine.run().unwrap();
+
+        assert!(machine.journal().is_empty());
+        assert_eq!(machine.rewind(1), 0);
+    }
+
+    #[test]
+    fn test_step_limit() {
+        let mut machine = Machine::new(LOOPING.parse().unwrap()).step_limit(3);
+
+        assert_eq!(machine.run(), Ok(Exit::OutOfSteps));
+        assert_eq!((machine.ip(), machine.steps()), (6, 3));
+    }
+}
@@ -0,0 +1,245 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::error::ParseError;
+use crate::util::words;
+use crate::vm::{Instruction, Program};
+
+/// Starts a comment that runs to the end of the line
+pub const COMMENT: char = ';';
+
+/// An instruction before its label, if it has one, is resolved
+struct Unresolved<'a> {
+    line: usize,
+    address: usize,
+    mnemonic: (usize, &'a str),
+    argument: (usize, &'a str),
+}
+
+///
+/// Assembles a program written with labels, comments and blank lines into the plain format,
+/// where every jump is relative. `jmp` and `nop` take a label or an offset, `acc` only numbers.
+/// A label is a name followed by `:`, before an instruction or on its own line, and a label
+/// after the last instruction is where the program terminates
+/// example
+/// ```
+/// # use advent_of_code_2020::vm::asm::assemble;
+/// let program = assemble(
+///     "
+///     ; adds 1 and 3 forever
+///     start:
+///         acc +1
+///         jmp skip
+///         acc -99
+///     skip: acc +3
+///         jmp start
+///     ",
+/// )
+/// .unwrap();
+///
+/// assert_eq!(program.to_string(), "acc +1\njmp +2\nacc -99\nacc +3\njmp -4");
+/// ```
+pub fn assemble(source: &str) -> Result<Program, ParseError> {
+    let mut labels = HashMap::new();
+    let mut unresolved = vec![];
+    for (index, line) in source.lines().enumerate() {
+        let at_line = |error: ParseError| error.at_line(index + 1);
+        let code = line.split(COMMENT).next().unwrap_or_default();
+        let mut words = words(code).into_iter().peekable();
+
+        while let Some((column, label)) = words
+            .peek()
+            .and_then(|(column, word)| word.strip_suffix(':').map(|label| (*column, label)))
+        {
+            if !is_label(label) {
+                return Err(at_line(ParseError::new("a label", label).at_column(column)));
+            }
+            if labels.insert(label, unresolved.len()).is_some() {
+                return Err(at_line(
+                    ParseError::new("a label that isn't defined yet", label).at_column(column),
+                ));
+            }
+            words.next();
+        }
+
+        let mnemonic = match words.next() {
+            Some(mnemonic) => mnemonic,
+            None => continue,
+        };
+        let argument = words.next().ok_or_else(|| {
+            at_line(ParseError::new("an argument", "nothing").at_column(code.trim_end().len() + 2))
+        })?;
+        if let Some((column, extra)) = words.next() {
+            return Err(at_line(
+                ParseError::new("the end of the line", extra).at_column(column),
+            ));
+        }
+        unresolved.push(Unresolved {
+            line: index + 1,
+            address: unresolved.len(),
+            mnemonic,
+            argument,
+        });
+    }
+
+    unresolved
+        .iter()
+        .map(|instruction| resolve(instruction, &labels).map_err(|e| e.at_line(instruction.line)))
+        .collect::<Result<Vec<_>, _>>()
+        .map(Program::from)
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+fn resolve(
+    instruction: &Unresolved,
+    labels: &HashMap<&str, usize>,
+) -> Result<Instruction, ParseError> {
+    let (mnemonic_column, mnemonic) = instruction.mnemonic;
+    let (column, argument) = instruction.argument;
+    let offset = match argument.parse::<i32>() {
+        Ok(number) => Some(number),
+        Err(_) if is_label(argument) => None,
+        Err(_) => return Err(ParseError::new("a label or a number", argument).at_column(column)),
+    };
+    let jump = || match offset {
+        Some(offset) => Ok(offset),
+        None => labels
+            .get(argument)
+            .map(|target| *target as i32 - instruction.address as i32)
+            .ok_or_else(|| ParseError::new("a defined label", argument).at_column(column)),
+    };
+    match mnemonic {
+        "acc" => offset
+            .map(Instruction::Acc)
+            .ok_or_else(|| ParseError::new("a number", argument).at_column(column)),
+        "jmp" => jump().map(Instruction::Jmp),
+        "nop" => jump().map(Instruction::Nop),
+        _ => Err(ParseError::new("one of acc, jmp or nop", mnemonic).at_column(mnemonic_column)),
+    }
+}
+
+/// A name starting with a letter or `_`, followed by letters, digits and `_`
+fn is_label(word: &str) -> bool {
+    let mut chars = word.chars();
+    chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|char| char.is_ascii_alphanumeric() || char == '_')
+}
+
+///
+/// Writes `program` with a label on every jump target, named after its address. Jumps out of
+/// the program keep their offset, and so do `nop`s. Assembling the result gives back `program`
+/// example
+/// ```
+/// # use advent_of_code_2020::vm::asm::{assemble, disassemble};
+/// let program = "nop +0\nacc +1\njmp +2\nacc +3\njmp -3".parse().unwrap();
+///
+/// let source = disassemble(&program);
+///
+/// assert_eq!(
+///     source,
+///     "    nop +0\nL1:\n    acc +1\n    jmp L4\n    acc +3\nL4:\n    jmp L1\n"
+/// );
+/// assert_eq!(assemble(&source), Ok(program));
+/// ```
+#[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+pub fn disassemble(program: &Program) -> String {
+    let target = |address: usize, offset: i32| {
+        Some(address as i64 + i64::from(offset))
+            .filter(|target| (0..=program.len() as i64).contains(target))
+            .map(|target| target as usize)
+    };
+    let targets = program
+        .instructions()
+        .iter()
+        .enumerate()
+        .filter_map(|(address, instruction)| match instruction {
+            Instruction::Jmp(offset) => target(address, *offset),
+            _ => None,
+        })
+        .collect::<BTreeSet<_>>();
+
+    let mut source = String::new();
+    for address in 0..=program.len() {
+        if targets.contains(&address) {
+            source.push_str(&format!("L{}:\n", address));
+        }
+        match program.get(address) {
+            Some(Instruction::Jmp(offset)) if target(address, offset).is_some() => {
+                source.push_str(&format!(
+                    "    jmp L{}\n",
+                    address as i64 + i64::from(offset)
+                ));
+            }
+            Some(instruction) => source.push_str(&format!("    {}\n", instruction)),
+            None => (),
+        }
+    }
+    source
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::testing::programs;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_labels_at_the_end_and_on_jumps_to_themselves() {
+        let program = assemble("start: jmp end\nspin: jmp spin\nnop start\nend:").unwrap();
+
+        assert_eq!(program.to_string(), "jmp +3\njmp +0\nnop -2");
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = assemble("a: nop +0\n  jmp b").unwrap_err();
+        assert_eq!((error.line, error.column), (Some(2), Some(7)));
+        assert_eq!(error.expected, "a defined label");
+
+        let error = assemble("a: nop +0\na:").unwrap_err();
+        assert_eq!((error.line, error.column), (Some(2), Some(1)));
+
+        let error = assemble("acc start\nstart:").unwrap_err();
+        assert_eq!(
+            (error.expected.as_str(), error.column),
+            ("a number", Some(5))
+        );
+
+        let error = assemble("\n\nmov +1").unwrap_err();
+        assert_eq!((error.line, error.column), (Some(3), Some(1)));
+
+        let error = assemble("jmp ; where to?").unwrap_err();
+        assert_eq!(
+            (error.expected.as_str(), error.column),
+            ("an argument", Some(5))
+        );
+
+        let error = assemble("1x: nop +0").unwrap_err();
+        assert_eq!((error.found.as_str(), error.column), ("1x", Some(1)));
+
+        let error = assemble("jmp +1 +2").unwrap_err();
+        assert_eq!(error.column, Some(8));
+    }
+
+    #[test]
+    fn test_out_of_bounds_jumps_keep_their_offset() {
+        let program: Program = "jmp -1\nnop +1\njmp +3\njmp -3".parse().unwrap();
+
+        assert_eq!(
+            disassemble(&program),
+            "L0:\n    jmp -1\n    nop +1\n    jmp +3\n    jmp L0\n"
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn test_round_trips(program in programs(0..20, 2)) {
+            let source = disassemble(&program);
+            let assembled = assemble(&source).unwrap();
+
+            prop_assert_eq!(&assembled, &program);
+            prop_assert_eq!(disassemble(&assembled), source);
+        }
+    }
+}
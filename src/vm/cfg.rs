@@ -0,0 +1,309 @@
+use crate::vm::{Instruction, Program};
+
+///
+/// Where control goes after each instruction of a program. Every address is a node, plus one
+/// terminal node just past the last instruction that the program terminates at
+/// example
+/// ```
+/// # use advent_of_code_2020::vm::cfg::ControlFlowGraph;
+/// let graph = ControlFlowGraph::new(&"nop +0\njmp +2\nacc +1".parse().unwrap());
+///
+/// assert_eq!(graph.terminal(), 3);
+/// assert_eq!(graph.successor(0), Some(1));
+/// assert_eq!(graph.successor(1), Some(3));
+/// assert_eq!(graph.predecessors(3), &[1, 2]);
+/// assert!(graph.predecessors(2).is_empty());
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ControlFlowGraph {
+    /// `None` for the terminal node, and for jumps out of the program
+    successors: Vec<Option<usize>>,
+    /// Predecessors of every node, stored back to back and sliced by `offsets`
+    predecessors: Vec<usize>,
+    offsets: Vec<usize>,
+}
+
+impl ControlFlowGraph {
+    pub fn new(program: &Program) -> Self {
+        let mut successors = program
+            .instructions()
+            .iter()
+            .enumerate()
+            .map(|(address, instruction)| successor(program, address, *instruction))
+            .collect::<Vec<_>>();
+        successors.push(None);
+
+        // counting sort the edges by target, so every node's predecessors end up in one slice
+        let mut offsets = vec![0; successors.len() + 1];
+        for target in successors.iter().flatten() {
+            offsets[target + 1] += 1;
+        }
+        for node in 1..offsets.len() {
+            offsets[node] += offsets[node - 1];
+        }
+        let mut next = offsets.clone();
+        let mut predecessors = vec![0; offsets[successors.len()]];
+        for (address, target) in successors.iter().enumerate() {
+            if let Some(target) = target {
+                predecessors[next[*target]] = address;
+                next[*target] += 1;
+            }
+        }
+
+        Self {
+            successors,
+            predecessors,
+            offsets,
+        }
+    }
+
+    /// Number of nodes, one per instruction plus the terminal node
+    pub fn len(&self) -> usize {
+        self.successors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.successors.is_empty()
+    }
+
+    /// The node the program terminates at, just past the last instruction
+    pub fn terminal(&self) -> usize {
+        self.successors.len() - 1
+    }
+
+    pub fn successor(&self, node: usize) -> Option<usize> {
+        self.successors[node]
+    }
+
+    pub fn predecessors(&self, node: usize) -> &[usize] {
+        &self.predecessors[self.offsets[node]..self.offsets[node + 1]]
+    }
+}
+
+/// The address after `instruction` at `address`, `None` if it jumps out of the program
+pub(super) fn successor(
+    program: &Program,
+    address: usize,
+    instruction: Instruction,
+) -> Option<usize> {
+    match instruction {
+        Instruction::Jmp(offset) => address
+            .checked_add_signed(offset as isize)
+            .filter(|target| *target <= program.len()),
+        Instruction::Acc(_) | Instruction::Nop(_) => Some(address + 1),
+    }
+}
+
+/// Replacing the instruction at `address`
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Patch {
+    pub address: usize,
+    pub original: Instruction,
+    pub replacement: Instruction,
+}
+
+impl Patch {
+    pub fn apply(&self, program: &mut Program) {
+        program.patch(self.address, self.replacement);
+    }
+}
+
+///
+/// Finds the single `jmp`/`nop` flip that makes a looping program terminate, in time linear in
+/// the size of the program. Every node that can reach the terminal node is found by walking the
+/// graph backwards from it. Then only the instructions the program actually executes are
+/// candidates, and a flip fixes the program if it sends control to one of those nodes
+/// example
+/// ```
+/// # use advent_of_code_2020::vm::cfg::Repair;
+/// # use advent_of_code_2020::vm::{Instruction, Program};
+/// let program: Program = "nop +0\nacc +1\njmp +4\nacc +3\njmp -3\nacc -99\nacc +1\njmp -4\nacc +6"
+///     .parse()
+///     .unwrap();
+///
+/// let repair = Repair::new(&program);
+///
+/// assert!(!repair.is_terminating(0));
+/// assert!(repair.is_reachable(7) && !repair.is_reachable(5));
+/// let patch = repair.patch().unwrap();
+/// assert_eq!((patch.address, patch.replacement), (7, Instruction::Nop(-4)));
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Repair {
+    graph: ControlFlowGraph,
+    /// Addresses the unpatched program executes, in order
+    reachable: Vec<usize>,
+    terminating: Vec<bool>,
+    patch: Option<Patch>,
+}
+
+impl Repair {
+    pub fn new(program: &Program) -> Self {
+        let graph = ControlFlowGraph::new(program);
+
+        let mut terminating = vec![false; graph.len()];
+        let mut stack = vec![graph.terminal()];
+        terminating[graph.terminal()] = true;
+        while let Some(node) = stack.pop() {
+            for predecessor in graph.predecessors(node) {
+                if !terminating[*predecessor] {
+                    terminating[*predecessor] = true;
+                    stack.push(*predecessor);
+                }
+            }
+        }
+
+        let mut reachable = vec![];
+        let mut seen = vec![false; graph.len()];
+        let mut node = Some(0);
+        while let Some(address) = node.filter(|address| *address < graph.terminal()) {
+            if seen[address] {
+                break;
+            }
+            seen[address] = true;
+            reachable.push(address);
+            node = graph.successor(address);
+        }
+
+        let patch = if terminating[0] {
+            None
+        } else {
+            reachable.iter().find_map(|address| {
+                let original = program[*address];
+                let replacement = original.flipped();
+                let target = successor(program, *address, replacement)?;
+                if replacement != original && terminating[target] {
+                    Some(Patch {
+                        address: *address,
+                        original,
+                        replacement,
+                    })
+                } else {
+                    None
+                }
+            })
+        };
+
+        Self {
+            graph,
+            reachable,
+            terminating,
+            patch,
+        }
+    }
+
+    pub const fn graph(&self) -> &ControlFlowGraph {
+        &self.graph
+    }
+
+    /// Addresses the unpatched program executes, in the order it first executes them
+    pub fn reachable(&self) -> &[usize] {
+        &self.reachable
+    }
+
+    pub fn is_reachable(&self, node: usize) -> bool {
+        self.reachable.contains(&node)
+    }
+
+    /// Nodes that lead to the terminal node, including itself
+    pub fn terminating(&self) -> impl Iterator<Item = usize> + '_ {
+        self.terminating
+            .iter()
+            .enumerate()
+            .filter(|(_, terminating)| **terminating)
+            .map(|(node, _)| node)
+    }
+
+    pub fn is_terminating(&self, node: usize) -> bool {
+        self.terminating[node]
+    }
+
+    /// The flip that makes the program terminate, `None` if it already does or no flip helps
+    pub const fn patch(&self) -> Option<Patch> {
+        self.patch
+    }
+
+    /// Whether the program terminates as is
+    pub fn already_terminates(&self) -> bool {
+        self.terminating[0]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::testing::programs;
+    use crate::vm::{Exit, Machine};
+    use proptest::prelude::*;
+
+    fn terminates(program: &Program) -> bool {
+        matches!(Machine::new(program.clone()).run(), Ok(Exit::Terminated))
+    }
+
+    #[test]
+    fn test_jumps_out_of_the_program_have_no_successor() {
+        let graph = ControlFlowGraph::new(&"jmp -1\njmp +3\njmp +2".parse().unwrap());
+
+        assert_eq!(graph.successor(0), None);
+        assert_eq!(graph.successor(1), None);
+        assert_eq!(graph.successor(2), None);
+        assert!(graph.predecessors(graph.terminal()).is_empty());
+    }
+
+    #[test]
+    fn test_terminating_program_needs_no_patch() {
+        let repair = Repair::new(&"nop +0\nacc +1".parse().unwrap());
+
+        assert!(repair.already_terminates());
+        assert_eq!(repair.patch(), None);
+        assert_eq!(repair.terminating().collect::<Vec<_>>(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_scales_to_large_programs() {
+        // every nop is a candidate, but only the flip near the end escapes the loop back to 0
+        let len = 300_000;
+        let fix = len - 10;
+        let instructions = (0..len)
+            .map(|address| match address {
+                _ if address == fix => Instruction::Nop((len - fix) as i32),
+                _ if address == len - 1 => Instruction::Jmp(-(address as i32)),
+                _ if address % 2 == 0 => Instruction::Nop(1),
+                _ => Instruction::Acc(1),
+            })
+            .collect::<Vec<_>>();
+        let mut program = Program::from(instructions);
+
+        let repair = Repair::new(&program);
+
+        let patch = repair.patch().unwrap();
+        assert_eq!(patch.address, fix);
+        assert_eq!(repair.reachable().len(), len);
+        patch.apply(&mut program);
+        let mut machine = Machine::new(program);
+        assert_eq!(machine.run(), Ok(Exit::Terminated));
+        assert_eq!(machine.acc(), (fix / 2) as i64);
+    }
+
+    proptest! {
+        #[test]
+        fn test_agrees_with_trying_every_flip(program in programs(1..20, 0)) {
+            let repair = Repair::new(&program);
+
+            prop_assert_eq!(repair.already_terminates(), terminates(&program));
+            let fixable = (0..program.len()).any(|address| {
+                let mut flipped = program.clone();
+                flipped.patch(address, program[address].flipped());
+                terminates(&flipped)
+            });
+            match repair.patch() {
+                Some(patch) => {
+                    let mut patched = program.clone();
+                    patch.apply(&mut patched);
+                    prop_assert!(terminates(&patched));
+                }
+                None => prop_assert!(repair.already_terminates() || !fixable),
+            }
+        }
+    }
+}
@@ -0,0 +1,227 @@
+use std::collections::BTreeMap;
+
+use crate::bench::json_string;
+use crate::vm::opcode::Op;
+
+/// A jump that was taken `count` times
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Edge {
+    pub from: usize,
+    pub to: usize,
+    pub count: u64,
+}
+
+///
+/// How often every instruction of a program was executed, and which jumps were taken, collected
+/// by a [`Machine`](crate::vm::Machine) that's [`profiled`](crate::vm::Machine::profiled)
+/// example
+/// ```
+/// # use advent_of_code_2020::vm::Machine;
+/// let mut machine = Machine::new("acc +1\njmp +2\nacc -99\nacc +3".parse().unwrap()).profiled();
+/// machine.run().unwrap();
+///
+/// let coverage = machine.coverage().unwrap();
+/// assert_eq!(coverage.hits(), &[1, 1, 0, 1]);
+/// assert_eq!(coverage.dead().collect::<Vec<_>>(), vec![2]);
+/// ```
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Coverage {
+    hits: Vec<u64>,
+    /// How often every taken jump was taken, keyed by where from and where to
+    jumps: BTreeMap<(usize, usize), u64>,
+}
+
+impl Coverage {
+    /// No hits yet, for a program of `len` instructions
+    pub fn new(len: usize) -> Self {
+        Self {
+            hits: vec![0; len],
+            jumps: BTreeMap::new(),
+        }
+    }
+
+    /// Counts executing the instruction at `address`, after which execution continued at `next`,
+    /// by jumping there or not
+    pub(super) fn record(&mut self, address: usize, next: usize, jumped: bool) {
+        self.hits[address] += 1;
+        if jumped {
+            *self.jumps.entry((address, next)).or_default() += 1;
+        }
+    }
+
+    /// How often the instruction at every address was executed
+    pub fn hits(&self) -> &[u64] {
+        &self.hits
+    }
+
+    pub fn is_dead(&self, address: usize) -> bool {
+        self.hits[address] == 0
+    }
+
+    /// Addresses that were never executed
+    pub fn dead(&self) -> impl Iterator<Item = usize> + '_ {
+        self.hits
+            .iter()
+            .enumerate()
+            .filter(|(_, hits)| **hits == 0)
+            .map(|(address, _)| address)
+    }
+
+    /// The `count` most taken jumps, most taken first
+    pub fn hottest_jumps(&self, count: usize) -> Vec<Edge> {
+        let mut edges = self
+            .jumps
+            .iter()
+            .map(|((from, to), count)| Edge {
+                from: *from,
+                to: *to,
+                count: *count,
+            })
+            .collect::<Vec<_>>();
+        edges.sort_by(|a, b| b.count.cmp(&a.count).then(a.from.cmp(&b.from)));
+        edges.truncate(count);
+        edges
+    }
+
+    ///
+    /// `code` with the hits of every instruction in front of it, and where taken jumps went
+    /// example
+    /// ```
+    /// # use advent_of_code_2020::vm::Machine;
+    /// let mut machine = Machine::new("acc +1\njmp +2\nacc -99\njmp -3".parse().unwrap()).profiled();
+    /// machine.run().unwrap();
+    ///
+    /// assert_eq!(
+    ///     machine.coverage().unwrap().annotate(machine.code()),
+    ///     "    hits  address\n       1        0: acc +1\n       1        1: jmp +2    -> 3\n       0        2: acc -99   dead\n       1        3: jmp -3    -> 0"
+    /// );
+    /// ```
+    pub fn annotate(&self, code: &[Op]) -> String {
+        let mut lines = vec![String::from("    hits  address")];
+        for (address, instruction) in code.iter().enumerate() {
+            let targets = self
+                .jumps
+                .range((address, 0)..=(address, usize::MAX))
+                .map(|((_, to), _)| to.to_string())
+                .collect::<Vec<_>>();
+            let note = if self.is_dead(address) {
+                String::from("dead")
+            } else if targets.is_empty() {
+                String::new()
+            } else {
+                format!("-> {}", targets.join(", "))
+            };
+            let line = format!(
+                "{:>8} {:>8}: {:<9} {}",
+                self.hits[address],
+                address,
+                instruction.to_string(),
+                note
+            );
+            lines.push(line.trim_end().to_owned());
+        }
+        lines.join("\n")
+    }
+
+    /// Every instruction with its hits, the dead addresses and the jumps, most taken first
+    pub fn to_json(&self, code: &[Op]) -> String {
+        let instructions = code
+            .iter()
+            .enumerate()
+            .map(|(address, instruction)| {
+                format!(
+                    "{{\"address\":{},\"instruction\":{},\"hits\":{}}}",
+                    address,
+                    json_string(&instruction.to_string()),
+                    self.hits[address]
+                )
+            })
+            .collect::<Vec<_>>();
+        let dead = self
+            .dead()
+            .map(|address| address.to_string())
+            .collect::<Vec<_>>();
+        let jumps = self
+            .hottest_jumps(self.jumps.len())
+            .iter()
+            .map(|edge| {
+                format!(
+                    "{{\"from\":{},\"to\":{},\"count\":{}}}",
+                    edge.from, edge.to, edge.count
+                )
+            })
+            .collect::<Vec<_>>();
+        format!(
+            "{{\"instructions\":[{}],\"dead\":[{}],\"jumps\":[{}]}}",
+            instructions.join(","),
+            dead.join(","),
+            jumps.join(",")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::testing::LOOPING;
+    use crate::vm::{Exit, Machine};
+
+    #[test]
+    fn test_hits_and_dead_code() {
+        let mut machine = Machine::new(LOOPING.parse().unwrap()).profiled();
+        assert!(matches!(machine.run(), Ok(Exit::Loop(_))));
+
+        let coverage = machine.coverage().unwrap();
+        assert_eq!(coverage.hits(), &[1, 1, 1, 1, 1, 0, 1, 1, 0]);
+        assert_eq!(coverage.dead().collect::<Vec<_>>(), vec![5, 8]);
+    }
+
+    #[test]
+    fn test_hottest_jumps() {
+        let mut machine = Machine::new("jmp +1\njmp +1\njmp -1".parse().unwrap()).profiled();
+        for _ in 0..10 {
+            machine.step().unwrap();
+        }
+
+        let hottest = machine.coverage().unwrap().hottest_jumps(2);
+        assert_eq!(
+            hottest,
+            vec![
+                Edge {
+                    from: 1,
+                    to: 2,
+                    count: 5
+                },
+                Edge {
+                    from: 2,
+                    to: 1,
+                    count: 4
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        let mut machine = Machine::new("jmp +2\nacc +1\nnop +0".parse().unwrap()).profiled();
+        machine.run().unwrap();
+
+        assert_eq!(
+            machine.coverage().unwrap().to_json(machine.code()),
+            concat!(
+                r#"{"instructions":[{"address":0,"instruction":"jmp +2","hits":1},"#,
+                r#"{"address":1,"instruction":"acc +1","hits":0},"#,
+                r#"{"address":2,"instruction":"nop +0","hits":1}],"#,
+                r#""dead":[1],"jumps":[{"from":0,"to":2,"count":1}]}"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_off_by_default() {
+        let mut machine = Machine::new(LOOPING.parse().unwrap());
+        machine.run().unwrap();
+
+        assert_eq!(machine.coverage(), None);
+    }
+}
@@ -0,0 +1,741 @@
+use std::fmt;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::error::ParseError;
+use crate::util::{try_parse, words};
+use crate::vm::journal::{self, Entry, Snapshot};
+use crate::vm::{Fault, Machine, Program};
+
+pub const HELP: &str = "Commands:
+    step [n]                 execute the next n instructions, 1 by default, or until one is about to repeat (s)
+    back [n]                 undo the last n instructions, 1 by default
+    continue                 run until a breakpoint, the end, or an instruction is about to repeat (c)
+    break <address>          stop before executing the instruction at address (b)
+    break acc <op> <value>   stop when acc changes to satisfy the condition, op is one of == != < <= > >=
+    delete <n>               remove breakpoint n (d)
+    breakpoints              list breakpoints
+    registers                show ip, acc and the number of steps executed (r)
+    list [address] [count]   show the program around address, the current instruction by default (l)
+    patch <address> <instr>  replace the instruction at address, like `patch 7 nop -4`
+    history [n]              show the last n executed instructions, 10 by default (h)
+    snapshot                 save the program, ip and acc to restore later
+    restore <n>              go back to snapshot n, undoing later patches
+    trace <path>             write every executed instruction to path as CSV
+    reset                    start over, keeping patches and breakpoints
+    help                     show this
+    quit                     exit the debugger (q)";
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    pub const fn holds(self, left: i64, right: i64) -> bool {
+        match self {
+            Self::Equal => left == right,
+            Self::NotEqual => left != right,
+            Self::Less => left < right,
+            Self::LessOrEqual => left <= right,
+            Self::Greater => left > right,
+            Self::GreaterOrEqual => left >= right,
+        }
+    }
+
+    const fn symbol(self) -> &'static str {
+        match self {
+            Self::Equal => "==",
+            Self::NotEqual => "!=",
+            Self::Less => "<",
+            Self::LessOrEqual => "<=",
+            Self::Greater => ">",
+            Self::GreaterOrEqual => ">=",
+        }
+    }
+}
+
+impl FromStr for Comparison {
+    type Err = ParseError;
+
+    fn from_str(symbol: &str) -> Result<Self, ParseError> {
+        match symbol {
+            "==" => Ok(Self::Equal),
+            "!=" => Ok(Self::NotEqual),
+            "<" => Ok(Self::Less),
+            "<=" => Ok(Self::LessOrEqual),
+            ">" => Ok(Self::Greater),
+            ">=" => Ok(Self::GreaterOrEqual),
+            _ => Err(ParseError::new("one of == != < <= > >=", symbol)),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Breakpoint {
+    /// Stops before the instruction at the address is executed
+    Address(usize),
+    /// Stops after a step that made the accumulator satisfy the condition
+    Acc(Comparison, i64),
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Address(address) => write!(f, "address {}", address),
+            Self::Acc(comparison, value) => write!(f, "acc {} {}", comparison.symbol(), value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Command {
+    Step(u64),
+    Back(u64),
+    Continue,
+    Break(Breakpoint),
+    Delete(usize),
+    Breakpoints,
+    Registers,
+    List {
+        from: Option<usize>,
+        count: usize,
+    },
+    /// The instruction is parsed with the table of the machine, `column` is where it starts
+    Patch {
+        address: usize,
+        instruction: String,
+        column: usize,
+    },
+    History(usize),
+    Snapshot,
+    Restore(usize),
+    Trace(PathBuf),
+    Reset,
+    Help,
+    Quit,
+}
+
+///
+/// Parses a debugger command, columns in errors are relative to the start of the line
+/// example
+/// ```
+/// # use advent_of_code_2020::vm::debugger::{Breakpoint, Command, Comparison};
+/// assert_eq!("s 3".parse(), Ok(Command::Step(3)));
+/// assert_eq!(
+///     "break acc >= 10".parse(),
+///     Ok(Command::Break(Breakpoint::Acc(Comparison::GreaterOrEqual, 10)))
+/// );
+/// assert_eq!(
+///     "patch 7 nop -4".parse(),
+///     Ok(Command::Patch {
+///         address: 7,
+///         instruction: String::from("nop -4"),
+///         column: 9
+///     })
+/// );
+/// assert_eq!("patch x nop -4".parse::<Command>().unwrap_err().column, Some(7));
+/// ```
+impl FromStr for Command {
+    type Err = ParseError;
+
+    fn from_str(line: &str) -> Result<Self, ParseError> {
+        let words = words(line);
+        let argument = |index: usize| words.get(index).copied();
+        // points just past the end of the line
+        let missing = |expected: &str| {
+            ParseError::new(expected, "nothing").at_column(line.trim_end().len() + 2)
+        };
+        let number = |index: usize, default: Option<usize>| match (argument(index), default) {
+            (Some((column, word)), _) => try_parse(word).map_err(|e| e.shifted(column - 1)),
+            (None, Some(default)) => Ok(default),
+            (None, None) => Err(missing("a number")),
+        };
+
+        let (_, name) = match argument(0) {
+            Some(word) => word,
+            None => return Err(ParseError::new("a command", "nothing").at_column(1)),
+        };
+        let command = match name {
+            "step" | "s" => Self::Step(number(1, Some(1))? as u64),
+            "back" => Self::Back(number(1, Some(1))? as u64),
+            "continue" | "c" => Self::Continue,
+            "break" | "b" => match argument(1) {
+                Some((_, "acc")) => {
+                    let comparison = match argument(2) {
+                        Some((column, symbol)) => symbol
+                            .parse()
+                            .map_err(|e: ParseError| e.at_column(column))?,
+                        None => return Err(missing("a comparison")),
+                    };
+                    let value = match argument(3) {
+                        Some((column, word)) => {
+                            try_parse(word).map_err(|e| e.shifted(column - 1))?
+                        }
+                        None => return Err(missing("a number")),
+                    };
+                    Self::Break(Breakpoint::Acc(comparison, value))
+                }
+                _ => Self::Break(Breakpoint::Address(number(1, None)?)),
+            },
+            "delete" | "d" => Self::Delete(number(1, None)?),
+            "breakpoints" => Self::Breakpoints,
+            "registers" | "r" => Self::Registers,
+            "list" | "l" => Self::List {
+                from: argument(1).map(|_| number(1, None)).transpose()?,
+                count: number(2, Some(7))?,
+            },
+            "patch" => {
+                let address = number(1, None)?;
+                let (column, _) = argument(2).ok_or_else(|| missing("an instruction"))?;
+                Self::Patch {
+                    address,
+                    instruction: line[column - 1..].trim_end().to_string(),
+                    column,
+                }
+            }
+            "history" | "h" => Self::History(number(1, Some(10))?),
+            "snapshot" => Self::Snapshot,
+            "restore" => Self::Restore(number(1, None)?),
+            "trace" => match argument(1) {
+                Some((column, _)) => Self::Trace(PathBuf::from(line[column - 1..].trim_end())),
+                None => return Err(missing("a path")),
+            },
+            "reset" => Self::Reset,
+            "help" => Self::Help,
+            "quit" | "q" => Self::Quit,
+            _ => return Err(ParseError::new("a command, see help", name).at_column(words[0].0)),
+        };
+        Ok(command)
+    }
+}
+
+///
+/// Steps through a program on any opcode table with breakpoints and a journal of everything it
+/// executed, that it can step back through
+/// example
+/// ```
+/// # use advent_of_code_2020::vm::debugger::Debugger;
+/// let mut debugger = Debugger::new("acc +1\njmp +2\nacc +5\nacc -3".parse().unwrap());
+///
+/// debugger.execute(&"break 3".parse().unwrap());
+/// let output = debugger.execute(&"continue".parse().unwrap());
+///
+/// assert!(output.starts_with("Breakpoint 1 (address 3) hit"));
+/// assert_eq!((debugger.machine().ip(), debugger.machine().acc()), (3, 1));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Debugger {
+    machine: Machine,
+    breakpoints: Vec<Breakpoint>,
+    snapshots: Vec<Snapshot>,
+}
+
+impl Debugger {
+    /// Debugs `program` on the handheld's instructions
+    pub fn new(program: Program) -> Self {
+        Self::attach(Machine::new(program))
+    }
+
+    /// Debugs whatever `machine` runs, from where it is now
+    pub fn attach(machine: Machine) -> Self {
+        Self {
+            machine: machine.journaled(),
+            breakpoints: vec![],
+            snapshots: vec![],
+        }
+    }
+
+    pub const fn machine(&self) -> &Machine {
+        &self.machine
+    }
+
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    pub fn history(&self) -> &[Entry] {
+        self.machine.journal()
+    }
+
+    /// Runs `command` and returns what to show the user
+    pub fn execute(&mut self, command: &Command) -> String {
+        match command {
+            Command::Step(count) => self.step(*count),
+            Command::Back(count) => {
+                let rewound = self.machine.rewind(*count);
+                format!("Stepped back {} instructions\n{}", rewound, self.location())
+            }
+            Command::Continue => self.resume(),
+            Command::Break(breakpoint) => {
+                if let Breakpoint::Address(address) = breakpoint {
+                    if *address >= self.machine.code().len() {
+                        return format!("There's no instruction at {}", address);
+                    }
+                }
+                self.breakpoints.push(*breakpoint);
+                format!("Breakpoint {} at {}", self.breakpoints.len(), breakpoint)
+            }
+            Command::Delete(number) => {
+                if (1..=self.breakpoints.len()).contains(number) {
+                    let breakpoint = self.breakpoints.remove(number - 1);
+                    format!("Deleted breakpoint {} at {}", number, breakpoint)
+                } else {
+                    format!("There's no breakpoint {}", number)
+                }
+            }
+            Command::Breakpoints if self.breakpoints.is_empty() => String::from("No breakpoints"),
+            Command::Breakpoints => self
+                .breakpoints
+                .iter()
+                .enumerate()
+                .map(|(i, breakpoint)| format!("{}: {}", i + 1, breakpoint))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Command::Registers => self.registers(),
+            Command::List { from, count } => self.list(*from, *count),
+            Command::Patch {
+                address,
+                instruction,
+                column,
+            } => {
+                if *address >= self.machine.code().len() {
+                    return format!("There's no instruction at {}", address);
+                }
+                match self.machine.table().parse_op(instruction) {
+                    Ok(instruction) => {
+                        let old = self.machine.patch(*address, instruction);
+                        format!("{}: {} -> {}", address, old, instruction)
+                    }
+                    Err(error) => format!("Can't patch in {}", error.shifted(column - 1)),
+                }
+            }
+            Command::History(count) => {
+                let history = self.history();
+                let skip = history.len().saturating_sub(*count);
+                let lines = history[skip..]
+                    .iter()
+                    .map(Entry::to_string)
+                    .collect::<Vec<_>>();
+                if lines.is_empty() {
+                    String::from("Nothing executed yet")
+                } else {
+                    lines.join("\n")
+                }
+            }
+            Command::Snapshot => {
+                self.snapshots.push(self.machine.snapshot());
+                format!(
+                    "Snapshot {} at step {}",
+                    self.snapshots.len(),
+                    self.machine.steps()
+                )
+            }
+            Command::Restore(number) => match number
+                .checked_sub(1)
+                .and_then(|index| self.snapshots.get(index).cloned())
+            {
+                Some(snapshot) => {
+                    self.machine.restore(&snapshot);
+                    format!("Restored snapshot {}\n{}", number, self.location())
+                }
+                None => format!("There's no snapshot {}", number),
+            },
+            Command::Trace(path) => {
+                let written = File::create(path).and_then(|file| {
+                    journal::write_trace(self.machine.journal(), BufWriter::new(file))
+                });
+                match written {
+                    Ok(()) => format!(
+                        "Wrote {} steps to {}",
+                        self.machine.journal().len(),
+                        path.display()
+                    ),
+                    Err(error) => format!("Couldn't write {}: {}", path.display(), error),
+                }
+            }
+            Command::Reset => {
+                self.machine = self.machine.restarted();
+                String::from("Restarted from the beginning")
+            }
+            Command::Help => String::from(HELP),
+            Command::Quit => String::new(),
+        }
+    }
+
+    /// Executes one instruction and returns its journal entry, `None` if the program is done
+    fn step_once(&mut self) -> Result<Option<Entry>, Fault> {
+        Ok(self
+            .machine
+            .step()?
+            .and_then(|_| self.machine.journal().last().copied()))
+    }
+
+    fn step(&mut self, count: u64) -> String {
+        let mut lines = vec![];
+        let mut visits = Visits::new(&self.machine);
+        for _ in 0..count {
+            if !visits.first(&self.machine) {
+                lines.push(self.looping());
+                return lines.join("\n");
+            }
+            match self.step_once() {
+                Ok(Some(entry)) => lines.push(entry.to_string()),
+                Ok(None) => break,
+                Err(fault) => {
+                    lines.push(format!("Fault: {}", fault));
+                    return lines.join("\n");
+                }
+            }
+        }
+        lines.push(self.location());
+        lines.join("\n")
+    }
+
+    fn resume(&mut self) -> String {
+        let mut visits = Visits::new(&self.machine);
+        let start = self.machine.steps();
+        loop {
+            let ip = self.machine.ip();
+            // resuming from a breakpoint shouldn't stop at it again right away
+            let hit = self.breakpoints.iter().position(|breakpoint| {
+                *breakpoint == Breakpoint::Address(ip) && self.machine.steps() != start
+            });
+            if let Some(hit) = hit {
+                return self.hit(hit);
+            }
+            if !visits.first(&self.machine) {
+                return self.looping();
+            }
+
+            let acc_before = self.machine.acc();
+            match self.step_once() {
+                Ok(Some(_)) => (),
+                Ok(None) => return self.location(),
+                Err(fault) => return format!("Fault: {}\n{}", fault, self.location()),
+            }
+            let acc = self.machine.acc();
+            let hit = self
+                .breakpoints
+                .iter()
+                .position(|breakpoint| match breakpoint {
+                    Breakpoint::Address(_) => false,
+                    Breakpoint::Acc(comparison, value) => {
+                        !comparison.holds(acc_before, *value) && comparison.holds(acc, *value)
+                    }
+                });
+            if let Some(hit) = hit {
+                return self.hit(hit);
+            }
+        }
+    }
+
+    fn hit(&self, breakpoint: usize) -> String {
+        format!(
+            "Breakpoint {} ({}) hit\n{}",
+            breakpoint + 1,
+            self.breakpoints[breakpoint],
+            self.location()
+        )
+    }
+
+    fn looping(&self) -> String {
+        format!(
+            "Stopped before executing {} a second time, the program loops\n{}",
+            self.machine.ip(),
+            self.location()
+        )
+    }
+
+    /// The next instruction, or that the program is done
+    fn location(&self) -> String {
+        match self.machine.code().get(self.machine.ip()) {
+            Some(instruction) => format!(
+                "=> {}: {}    acc = {}",
+                self.machine.ip(),
+                instruction,
+                self.machine.acc()
+            ),
+            None => format!("Program terminated, acc = {}", self.machine.acc()),
+        }
+    }
+
+    fn registers(&self) -> String {
+        format!(
+            "ip = {}, acc = {}, steps = {}{}",
+            self.machine.ip(),
+            self.machine.acc(),
+            self.machine.steps(),
+            if self.machine.is_terminated() {
+                " (terminated)"
+            } else {
+                ""
+            }
+        )
+    }
+
+    fn list(&self, from: Option<usize>, count: usize) -> String {
+        let code = self.machine.code();
+        let from = from.unwrap_or_else(|| self.machine.ip().saturating_sub(count / 2));
+        // the address just past the end is shown too, it's where the program terminates
+        let to = from.saturating_add(count).min(code.len() + 1);
+        (from..to)
+            .map(|address| {
+                let marker = if address == self.machine.ip() {
+                    "=>"
+                } else {
+                    "  "
+                };
+                let breakpoint = self.breakpoints.contains(&Breakpoint::Address(address));
+                let instruction = code.get(address).map_or_else(
+                    || String::from("<end>"),
+                    |instruction| instruction.to_string(),
+                );
+                format!(
+                    "{} {:>5}: {}{}",
+                    marker,
+                    address,
+                    instruction,
+                    if breakpoint { "    *" } else { "" }
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// The instructions executed since the debugger last started running, or since the code last
+/// changed, toggling code changes what it does so having been somewhere before says nothing then
+struct Visits {
+    executed: Vec<bool>,
+    revision: u64,
+}
+
+impl Visits {
+    fn new(machine: &Machine) -> Self {
+        Self {
+            executed: vec![false; machine.code().len()],
+            revision: machine.revision(),
+        }
+    }
+
+    /// Marks the next instruction of `machine` as executed, false if it already was
+    fn first(&mut self, machine: &Machine) -> bool {
+        if self.revision != machine.revision() {
+            *self = Self::new(machine);
+        }
+        match self.executed.get_mut(machine.ip()) {
+            Some(executed) => !std::mem::replace(executed, true),
+            None => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::testing::LOOPING;
+
+    fn run(debugger: &mut Debugger, command: &str) -> String {
+        debugger.execute(&command.parse().unwrap())
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = "jump 3".parse::<Command>().unwrap_err();
+        assert_eq!((error.column, error.found.as_str()), (Some(1), "jump"));
+
+        let error = "break acc => 3".parse::<Command>().unwrap_err();
+        assert_eq!(error.column, Some(11));
+
+        let error = "  step x".parse::<Command>().unwrap_err();
+        assert_eq!(error.column, Some(8));
+
+        let error = "patch 3".parse::<Command>().unwrap_err();
+        assert_eq!(error.column, Some(9));
+    }
+
+    #[test]
+    fn test_step_and_history() {
+        let mut debugger = Debugger::new(LOOPING.parse().unwrap());
+
+        let output = run(&mut debugger, "step 3");
+
+        assert_eq!(output.lines().count(), 4);
+        assert_eq!(output.lines().last(), Some("=> 6: acc +1    acc = 1"));
+        assert_eq!(
+            run(&mut debugger, "registers"),
+            "ip = 6, acc = 1, steps = 3"
+        );
+        assert_eq!(
+            run(&mut debugger, "history 1"),
+            "#2          2: jmp +4   acc = 1"
+        );
+    }
+
+    #[test]
+    fn test_continue_stops_at_loops() {
+        let mut debugger = Debugger::new(LOOPING.parse().unwrap());
+
+        let output = run(&mut debugger, "c");
+
+        assert!(output.starts_with("Stopped before executing 1 a second time"));
+        assert_eq!(debugger.machine().acc(), 5);
+    }
+
+    #[test]
+    fn test_step_stops_at_loops() {
+        let mut debugger = Debugger::new(LOOPING.parse().unwrap());
+
+        let output = run(&mut debugger, "s 100");
+
+        assert!(output
+            .lines()
+            .any(|line| line.starts_with("Stopped before executing 1 a second time")));
+        assert_eq!(debugger.machine().steps(), 7);
+    }
+
+    #[test]
+    fn test_address_breakpoints_stop_before_executing() {
+        let mut debugger = Debugger::new(LOOPING.parse().unwrap());
+        run(&mut debugger, "break 3");
+
+        assert!(run(&mut debugger, "c").starts_with("Breakpoint 1 (address 3) hit\n=> 3: acc +3"));
+        assert_eq!(debugger.machine().steps(), 5);
+
+        // continuing from the breakpoint runs past it, and it's hit before the loop is noticed
+        assert!(run(&mut debugger, "c").starts_with("Breakpoint 1 (address 3) hit"));
+        assert_eq!(debugger.machine().steps(), 11);
+
+        run(&mut debugger, "back 1");
+        assert!(run(&mut debugger, "c").starts_with("Breakpoint 1 (address 3) hit"));
+        assert_eq!(debugger.machine().steps(), 11);
+    }
+
+    #[test]
+    fn test_acc_breakpoint_triggers_on_change() {
+        let mut debugger = Debugger::new(LOOPING.parse().unwrap());
+        run(&mut debugger, "break acc > 1");
+
+        let output = run(&mut debugger, "continue");
+
+        assert!(output.starts_with("Breakpoint 1 (acc > 1) hit"));
+        assert_eq!((debugger.machine().ip(), debugger.machine().acc()), (7, 2));
+    }
+
+    #[test]
+    fn test_patch_and_reset() {
+        let mut debugger = Debugger::new(LOOPING.parse().unwrap());
+
+        assert_eq!(run(&mut debugger, "patch 7 nop -4"), "7: jmp -4 -> nop -4");
+        assert_eq!(
+            run(&mut debugger, "continue"),
+            "Program terminated, acc = 8"
+        );
+        run(&mut debugger, "reset");
+        assert_eq!(
+            run(&mut debugger, "registers"),
+            "ip = 0, acc = 0, steps = 0"
+        );
+        assert_eq!(run(&mut debugger, "history"), "Nothing executed yet");
+        assert_eq!(
+            run(&mut debugger, "patch 9 nop +0"),
+            "There's no instruction at 9"
+        );
+    }
+
+    #[test]
+    fn test_back_undoes_steps() {
+        let mut debugger = Debugger::new(LOOPING.parse().unwrap());
+        run(&mut debugger, "c");
+
+        assert_eq!(
+            run(&mut debugger, "back 3"),
+            "Stepped back 3 instructions\n=> 7: jmp -4    acc = 2"
+        );
+        assert_eq!(
+            run(&mut debugger, "history 1"),
+            "#3          6: acc +1   acc = 2"
+        );
+        assert_eq!(
+            run(&mut debugger, "back 100"),
+            "Stepped back 4 instructions\n=> 0: nop +0    acc = 0"
+        );
+    }
+
+    #[test]
+    fn test_snapshots_restore_patches_too() {
+        let mut debugger = Debugger::new(LOOPING.parse().unwrap());
+        run(&mut debugger, "step 3");
+        assert_eq!(run(&mut debugger, "snapshot"), "Snapshot 1 at step 3");
+        run(&mut debugger, "patch 7 nop -4");
+        run(&mut debugger, "continue");
+
+        assert_eq!(
+            run(&mut debugger, "restore 1"),
+            "Restored snapshot 1\n=> 6: acc +1    acc = 1"
+        );
+        assert_eq!(debugger.machine().code()[7].to_string(), "jmp -4");
+        assert_eq!(debugger.history().len(), 3);
+        assert_eq!(run(&mut debugger, "restore 2"), "There's no snapshot 2");
+    }
+
+    #[test]
+    fn test_trace() {
+        let path = std::env::temp_dir().join(format!("aoc2020-trace-{}.csv", std::process::id()));
+        let mut debugger = Debugger::new("acc +2\njmp +2\nacc +5\nacc -1".parse().unwrap());
+        run(&mut debugger, "continue");
+
+        let output = debugger.execute(&Command::Trace(path.clone()));
+
+        assert_eq!(output, format!("Wrote 3 steps to {}", path.display()));
+        let trace = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            trace.lines().collect::<Vec<_>>(),
+            vec![
+                "step,ip,instruction,acc",
+                "0,0,acc +2,2",
+                "1,1,jmp +2,2",
+                "2,3,acc -1,1"
+            ]
+        );
+        assert_eq!(
+            "trace  out/my trace.csv ".parse(),
+            Ok(Command::Trace(PathBuf::from("out/my trace.csv")))
+        );
+    }
+
+    #[test]
+    fn test_faults_are_reported() {
+        let mut debugger = Debugger::new("acc +1\njmp -5".parse().unwrap());
+
+        let output = run(&mut debugger, "continue");
+
+        assert!(output.starts_with("Fault: jmp -5 at 1 jumps to -4"));
+        assert_eq!(debugger.machine().ip(), 1);
+    }
+
+    #[test]
+    fn test_list_marks_ip_and_breakpoints() {
+        let mut debugger = Debugger::new("nop +0\nacc +1\njmp -2".parse().unwrap());
+        run(&mut debugger, "break 2");
+        run(&mut debugger, "step");
+
+        assert_eq!(
+            run(&mut debugger, "list 0 5"),
+            "       0: nop +0\n=>     1: acc +1\n       2: jmp -2    *\n       3: <end>"
+        );
+        assert_eq!(
+            run(&mut debugger, "list 2 18446744073709551615"),
+            "       2: jmp -2    *\n       3: <end>"
+        );
+    }
+}
@@ -0,0 +1,89 @@
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::Arc;
+
+use crate::vm::opcode::{Op, REGISTERS};
+
+/// One executed instruction, with enough to undo it
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Entry {
+    /// Number of instructions executed before this one
+    pub step: u64,
+    pub ip: usize,
+    pub instruction: Op,
+    pub registers_before: [i64; REGISTERS],
+    /// The accumulator after executing the instruction
+    pub acc: i64,
+    /// How long the output was before the instruction added to it
+    pub output_before: usize,
+    /// The instruction it toggled and what was there before, if it toggled one
+    pub toggled: Option<(usize, Op)>,
+}
+
+impl Entry {
+    pub const fn acc_before(&self) -> i64 {
+        self.registers_before[0]
+    }
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "#{:<6} {:>5}: {:<8} acc = {}",
+            self.step,
+            self.ip,
+            self.instruction.to_string(),
+            self.acc
+        )
+    }
+}
+
+/// The whole state of a machine at one point. Taking one doesn't copy the program, it's shared
+/// until either copy is patched. The journal of the steps that led to it is copied, so that
+/// rewinding after restoring goes back the way it came
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Snapshot {
+    pub(super) code: Arc<Vec<Op>>,
+    pub(super) journal: Option<Vec<Entry>>,
+    pub(super) registers: [i64; REGISTERS],
+    pub(super) output: Vec<i64>,
+    pub(super) halted: bool,
+    pub ip: usize,
+    pub steps: u64,
+}
+
+impl Snapshot {
+    pub const fn acc(&self) -> i64 {
+        self.registers[0]
+    }
+}
+
+///
+/// Writes `journal` as CSV, one line of step, ip, instruction and acc after it per entry
+/// example
+/// ```
+/// # use advent_of_code_2020::vm::journal::write_trace;
+/// # use advent_of_code_2020::vm::Machine;
+/// let mut machine = Machine::new("acc +2\njmp +2\nacc +5\nacc -1".parse().unwrap()).journaled();
+/// machine.run().unwrap();
+///
+/// let mut trace = vec![];
+/// write_trace(machine.journal(), &mut trace).unwrap();
+///
+/// assert_eq!(
+///     String::from_utf8(trace).unwrap(),
+///     "step,ip,instruction,acc\n0,0,acc +2,2\n1,1,jmp +2,2\n2,3,acc -1,1\n"
+/// );
+/// ```
+pub fn write_trace(journal: &[Entry], mut writer: impl Write) -> io::Result<()> {
+    writeln!(writer, "step,ip,instruction,acc")?;
+    for entry in journal {
+        writeln!(
+            writer,
+            "{},{},{},{}",
+            entry.step, entry.ip, entry.instruction, entry.acc
+        )?;
+    }
+    Ok(())
+}
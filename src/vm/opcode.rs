@@ -0,0 +1,564 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::ParseError;
+use crate::util::{parse_lines, words};
+use crate::vm::{Fault, Instruction, Machine};
+
+/// The most operands an instruction can have
+pub const MAX_OPERANDS: usize = 2;
+
+/// `acc`, and the registers `a` to `z` after it
+pub const REGISTERS: usize = 27;
+
+/// The register the handheld's `acc` instruction adds to
+pub const ACC: Operand = Operand::Register(0);
+
+/// A register, or a number written in the program
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Operand {
+    Register(usize),
+    Immediate(i64),
+}
+
+impl FromStr for Operand {
+    type Err = ParseError;
+
+    fn from_str(word: &str) -> Result<Self, ParseError> {
+        let mut chars = word.chars();
+        match (word, chars.next(), chars.next()) {
+            ("acc", _, _) => Ok(ACC),
+            (_, Some(name @ 'a'..='z'), None) => {
+                Ok(Self::Register(1 + (name as usize - 'a' as usize)))
+            }
+            _ => word
+                .parse()
+                .map(Self::Immediate)
+                .map_err(|_| ParseError::new("a register or a number", word)),
+        }
+    }
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Register(0) => write!(f, "acc"),
+            #[allow(clippy::cast_possible_truncation)]
+            Self::Register(register) => write!(f, "{}", (b'a' + *register as u8 - 1) as char),
+            Self::Immediate(value) => write!(f, "{:+}", value),
+        }
+    }
+}
+
+/// What an opcode accepts for one of its operands
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum OperandKind {
+    /// Written to, so it has to be a register
+    Register,
+    /// Only read, a register or a number
+    Value,
+}
+
+impl OperandKind {
+    pub const fn accepts(self, operand: Operand) -> bool {
+        matches!(
+            (self, operand),
+            (Self::Value, _) | (Self::Register, Operand::Register(_))
+        )
+    }
+}
+
+/// Where execution continues after an instruction
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Flow {
+    /// The instruction after this one
+    Next,
+    /// Relative to this instruction
+    Jump(i64),
+    /// Nowhere, the program is done
+    Halt,
+}
+
+/// Executes one instruction with its operands, which match the kinds the opcode declared
+pub type Execute = fn(&mut Machine, &[Operand]) -> Result<Flow, Fault>;
+
+/// One instruction of an [`OpcodeTable`]
+#[derive(Debug, Copy, Clone)]
+pub struct Opcode {
+    pub mnemonic: &'static str,
+    pub operands: &'static [OperandKind],
+    pub execute: Execute,
+    /// What `tgl` turns the instruction into, it stays the same if `None`
+    pub toggled: Option<&'static str>,
+}
+
+impl Opcode {
+    pub const fn new(
+        mnemonic: &'static str,
+        operands: &'static [OperandKind],
+        execute: Execute,
+    ) -> Self {
+        Self {
+            mnemonic,
+            operands,
+            execute,
+            toggled: None,
+        }
+    }
+
+    /// Makes `tgl` turn this opcode into `mnemonic`, operands it doesn't have are 0
+    pub const fn toggles_to(mut self, mnemonic: &'static str) -> Self {
+        self.toggled = Some(mnemonic);
+        self
+    }
+}
+
+///
+/// The instructions a [`Machine`] understands. New ones are registered with
+/// [`OpcodeTable::with`], by default it's only the handheld's `acc`, `jmp` and `nop`
+/// example
+/// ```
+/// # use advent_of_code_2020::vm::opcode::{Flow, Opcode, OpcodeTable, OperandKind};
+/// # use advent_of_code_2020::vm::Machine;
+/// let table = OpcodeTable::default()
+///     .with(Opcode::new(
+///         "dbl",
+///         &[OperandKind::Register],
+///         |machine, operands| {
+///             let doubled = machine.read(operands[0]) * 2;
+///             machine.write(operands[0], doubled);
+///             Ok(Flow::Next)
+///         },
+///     ))
+///     .unwrap();
+///
+/// let mut machine = Machine::load(table, "acc +3\ndbl acc\ndbl acc").unwrap();
+/// machine.run().unwrap();
+/// assert_eq!(machine.acc(), 12);
+/// ```
+#[derive(Debug, Clone)]
+pub struct OpcodeTable {
+    opcodes: Vec<Opcode>,
+}
+
+impl OpcodeTable {
+    /// A table without any instructions
+    pub const fn empty() -> Self {
+        Self { opcodes: vec![] }
+    }
+
+    /// The handheld game console of day 8, `jmp` and `nop` toggle into each other. They're the
+    /// first three opcodes, where [`Op::from`] an [`Instruction`] expects them
+    pub fn handheld() -> Self {
+        use OperandKind::Value;
+        Self::empty()
+            .register(Opcode::new("acc", &[Value], |machine, operands| {
+                let value = machine.read(operands[0]);
+                machine.update(ACC, value, i64::checked_add)
+            }))
+            .register(
+                Opcode::new("jmp", &[Value], |machine, operands| {
+                    Ok(Flow::Jump(machine.read(operands[0])))
+                })
+                .toggles_to("nop"),
+            )
+            .register(Opcode::new("nop", &[Value], |_, _| Ok(Flow::Next)).toggles_to("jmp"))
+    }
+
+    ///
+    /// The handheld's instructions, plus
+    /// * `set r x`, `add r x` and `mul r x`, on any register
+    /// * `jz x offset` and `jnz x offset`, jumping if `x` is or isn't 0
+    /// * `out x`, appending `x` to the output
+    /// * `hlt`, stopping the program
+    /// * `tgl x`, toggling the instruction `x` away from itself
+    pub fn extended() -> Self {
+        use OperandKind::{Register, Value};
+        Self::handheld()
+            .register(Opcode::new(
+                "set",
+                &[Register, Value],
+                |machine, operands| {
+                    let value = machine.read(operands[1]);
+                    machine.write(operands[0], value);
+                    Ok(Flow::Next)
+                },
+            ))
+            .register(Opcode::new(
+                "add",
+                &[Register, Value],
+                |machine, operands| {
+                    let value = machine.read(operands[1]);
+                    machine.update(operands[0], value, i64::checked_add)
+                },
+            ))
+            .register(Opcode::new(
+                "mul",
+                &[Register, Value],
+                |machine, operands| {
+                    let value = machine.read(operands[1]);
+                    machine.update(operands[0], value, i64::checked_mul)
+                },
+            ))
+            .register(
+                Opcode::new("jz", &[Value, Value], |machine, operands| {
+                    Ok(match machine.read(operands[0]) {
+                        0 => Flow::Jump(machine.read(operands[1])),
+                        _ => Flow::Next,
+                    })
+                })
+                .toggles_to("jnz"),
+            )
+            .register(
+                Opcode::new("jnz", &[Value, Value], |machine, operands| {
+                    Ok(match machine.read(operands[0]) {
+                        0 => Flow::Next,
+                        _ => Flow::Jump(machine.read(operands[1])),
+                    })
+                })
+                .toggles_to("jz"),
+            )
+            .register(Opcode::new("out", &[Value], |machine, operands| {
+                let value = machine.read(operands[0]);
+                machine.out(value);
+                Ok(Flow::Next)
+            }))
+            .register(Opcode::new("hlt", &[], |_, _| Ok(Flow::Halt)))
+            .register(Opcode::new("tgl", &[Value], |machine, operands| {
+                let offset = machine.read(operands[0]);
+                machine.toggle(offset);
+                Ok(Flow::Next)
+            }))
+    }
+
+    /// Adds `opcode`, replacing the one with the same mnemonic if there is one. It's an error if
+    /// it takes more than [`MAX_OPERANDS`] operands, an [`Op`] has no room for more
+    pub fn with(self, opcode: Opcode) -> Result<Self, TooManyOperands> {
+        if opcode.operands.len() > MAX_OPERANDS {
+            return Err(TooManyOperands {
+                mnemonic: opcode.mnemonic,
+                operands: opcode.operands.len(),
+            });
+        }
+        Ok(self.register(opcode))
+    }
+
+    /// [`OpcodeTable::with`] for opcodes that are known to fit
+    fn register(mut self, opcode: Opcode) -> Self {
+        match self.index(opcode.mnemonic) {
+            Some(index) => self.opcodes[index] = opcode,
+            None => self.opcodes.push(opcode),
+        }
+        self
+    }
+
+    pub fn get(&self, mnemonic: &str) -> Option<&Opcode> {
+        self.index(mnemonic).map(|index| &self.opcodes[index])
+    }
+
+    /// What executes `op`, which has to come from this table
+    pub(super) fn execute(&self, op: Op) -> Execute {
+        self.opcodes[op.opcode].execute
+    }
+
+    /// What `tgl` turns `op` into, `None` if it stays the same
+    pub(super) fn toggled(&self, op: Op) -> Option<Op> {
+        let mnemonic = self.opcodes[op.opcode].toggled?;
+        let opcode = self.index(mnemonic)?;
+        Some(Op {
+            opcode,
+            mnemonic,
+            kinds: self.opcodes[opcode].operands,
+            operands: op.operands,
+        })
+    }
+
+    pub fn mnemonics(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.opcodes.iter().map(|opcode| opcode.mnemonic)
+    }
+
+    fn index(&self, mnemonic: &str) -> Option<usize> {
+        self.opcodes
+            .iter()
+            .position(|opcode| opcode.mnemonic == mnemonic)
+    }
+
+    /// Parses one instruction, unknown mnemonics and operands that don't fit are errors
+    pub fn parse_op(&self, line: &str) -> Result<Op, ParseError> {
+        let words = words(line);
+        let (column, mnemonic) = *words
+            .first()
+            .ok_or_else(|| ParseError::new("an instruction", "nothing").at_column(1))?;
+        let opcode = self.index(mnemonic).ok_or_else(|| {
+            let mnemonics = self.mnemonics().collect::<Vec<_>>().join(", ");
+            ParseError::new(format!("one of {}", mnemonics), mnemonic).at_column(column)
+        })?;
+
+        let kinds = self.opcodes[opcode].operands;
+        if let Some((column, extra)) = words.get(kinds.len() + 1) {
+            return Err(ParseError::new("the end of the line", *extra).at_column(*column));
+        }
+        let mut operands = [Operand::Immediate(0); MAX_OPERANDS];
+        for (i, kind) in kinds.iter().enumerate() {
+            let (column, word) = *words.get(i + 1).ok_or_else(|| {
+                ParseError::new(format!("{} operands", kinds.len()), "nothing")
+                    .at_column(line.trim_end().len() + 2)
+            })?;
+            let operand = word.parse::<Operand>().map_err(|e| e.at_column(column))?;
+            if !kind.accepts(operand) {
+                return Err(ParseError::new("a register", word).at_column(column));
+            }
+            operands[i] = operand;
+        }
+        Ok(Op {
+            opcode,
+            mnemonic: self.opcodes[opcode].mnemonic,
+            kinds,
+            operands,
+        })
+    }
+
+    /// Parses a program, one instruction per line
+    pub fn parse(&self, input: &str) -> Result<Vec<Op>, ParseError> {
+        parse_lines(input, |line| self.parse_op(line))
+    }
+}
+
+impl Default for OpcodeTable {
+    fn default() -> Self {
+        Self::handheld()
+    }
+}
+
+/// An opcode that takes more operands than an [`Op`] has room for
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TooManyOperands {
+    pub mnemonic: &'static str,
+    pub operands: usize,
+}
+
+impl fmt::Display for TooManyOperands {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} takes {} operands, at most {} fit an instruction",
+            self.mnemonic, self.operands, MAX_OPERANDS
+        )
+    }
+}
+
+impl Error for TooManyOperands {}
+
+/// An instruction, as an index into the [`OpcodeTable`] it was parsed with. It knows how it's
+/// written, so it can be shown without the table
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Op {
+    pub opcode: usize,
+    pub mnemonic: &'static str,
+    /// What the opcode takes, one kind per operand
+    pub kinds: &'static [OperandKind],
+    /// Operands past the ones the opcode takes are 0
+    pub operands: [Operand; MAX_OPERANDS],
+}
+
+impl Op {
+    /// The operands the opcode takes
+    pub fn operands(&self) -> &[Operand] {
+        &self.operands[..self.kinds.len()]
+    }
+}
+
+impl From<Instruction> for Op {
+    /// The instruction on the handheld's table, or any table that starts with its opcodes
+    fn from(instruction: Instruction) -> Self {
+        let opcode = match instruction {
+            Instruction::Acc(_) => 0,
+            Instruction::Jmp(_) => 1,
+            Instruction::Nop(_) => 2,
+        };
+        Self {
+            opcode,
+            mnemonic: instruction.mnemonic(),
+            kinds: &[OperandKind::Value],
+            operands: [
+                Operand::Immediate(i64::from(instruction.argument())),
+                Operand::Immediate(0),
+            ],
+        }
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.mnemonic)?;
+        for operand in self.operands() {
+            write!(f, " {}", operand)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::testing::{programs, LOOPING};
+    use crate::vm::Exit;
+    use proptest::prelude::*;
+
+    fn extended(input: &str) -> Machine {
+        Machine::load(OpcodeTable::extended(), input).unwrap()
+    }
+
+    #[test]
+    fn test_unknown_opcodes_are_parse_errors() {
+        let error = OpcodeTable::default()
+            .parse("nop +0\n  mul a +2")
+            .unwrap_err();
+
+        assert_eq!((error.line, error.column), (Some(2), Some(3)));
+        assert_eq!(error.expected, "one of acc, jmp, nop");
+        assert_eq!(error.found, "mul");
+    }
+
+    #[test]
+    fn test_operand_errors() {
+        let table = OpcodeTable::extended();
+
+        let error = table.parse_op("set 3 +4").unwrap_err();
+        assert_eq!(
+            (error.expected.as_str(), error.column),
+            ("a register", Some(5))
+        );
+        let error = table.parse_op("add a four").unwrap_err();
+        assert_eq!(error.column, Some(7));
+        let error = table.parse_op("jnz a").unwrap_err();
+        assert_eq!(
+            (error.expected.as_str(), error.column),
+            ("2 operands", Some(7))
+        );
+        let error = table.parse_op("hlt now").unwrap_err();
+        assert_eq!(error.column, Some(5));
+    }
+
+    #[test]
+    fn test_listing_round_trips() {
+        let input = "set b -7\nmul b acc\njz z +2\nout +0\nhlt\ntgl -1\nnop +0";
+
+        assert_eq!(extended(input).listing(), input);
+    }
+
+    #[test]
+    fn test_registers_and_conditional_jumps() {
+        // 5! in acc
+        let mut machine = extended("set a +5\nacc +1\nmul acc a\nadd a -1\njnz a -2");
+
+        assert_eq!(machine.run_to_end(), Ok(Exit::Terminated));
+        assert_eq!((machine.acc(), machine.register("a")), (120, Some(0)));
+        assert_eq!(machine.register("ab"), None);
+    }
+
+    #[test]
+    fn test_halt_stops_for_good() {
+        let mut machine = extended("out +1\nhlt\nout +2");
+
+        assert_eq!(machine.run(), Ok(Exit::Halted));
+        assert_eq!(machine.step(), Ok(None));
+        assert_eq!((machine.output(), machine.ip()), (&[1][..], 1));
+    }
+
+    #[test]
+    fn test_toggles() {
+        // the jmp back to 0 becomes a nop, and the jz toggles into a jnz that now jumps
+        let mut machine = extended("tgl +2\ntgl +2\njmp -2\njz +1 +2\nout +9\nout +1");
+
+        assert_eq!(machine.run(), Ok(Exit::Terminated));
+        assert_eq!(machine.output(), &[1]);
+        assert_eq!(
+            machine.listing(),
+            "tgl +2\ntgl +2\nnop -2\njnz +1 +2\nout +9\nout +1"
+        );
+    }
+
+    #[test]
+    fn test_toggled_into_invalid_instructions_are_skipped() {
+        let table = OpcodeTable::extended()
+            .with(
+                Opcode::new("inc", &[OperandKind::Value], |machine, operands| {
+                    machine.update(ACC, machine.read(operands[0]), i64::checked_add)
+                })
+                .toggles_to("set"),
+            )
+            .unwrap();
+        let mut machine = Machine::load(table, "tgl +1\ninc +5\nacc +1").unwrap();
+
+        assert_eq!(machine.run(), Ok(Exit::Terminated));
+        assert_eq!(machine.acc(), 1);
+        assert_eq!(machine.listing(), "tgl +1\nset +5 +0\nacc +1");
+    }
+
+    #[test]
+    fn test_too_many_operands_are_rejected() {
+        use OperandKind::{Register, Value};
+        let error = OpcodeTable::extended()
+            .with(Opcode::new("sum", &[Register, Value, Value], |_, _| {
+                Ok(Flow::Next)
+            }))
+            .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "sum takes 3 operands, at most 2 fit an instruction"
+        );
+    }
+
+    #[test]
+    fn test_faults() {
+        let mut machine = extended("set a +1\nmul a +4611686018427387904\nmul a +2");
+        assert_eq!(machine.run(), Err(Fault::Overflow { address: 2 }));
+        assert_eq!(machine.register("a"), Some(4_611_686_018_427_387_904));
+
+        let mut machine = extended("jnz +1 -3");
+        let instruction = OpcodeTable::extended().parse_op("jnz +1 -3").unwrap();
+        assert_eq!(
+            machine.run(),
+            Err(Fault::JumpOutOfBounds {
+                address: 0,
+                instruction,
+                target: -3,
+                len: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_loops_start_over_after_toggles() {
+        let mut machine = Machine::load(OpcodeTable::default(), LOOPING).unwrap();
+        assert!(matches!(machine.run(), Ok(Exit::Loop(_))));
+        assert_eq!((machine.ip(), machine.acc()), (1, 5));
+
+        // without starting over the second visit of 0 would count as a loop
+        let mut machine = extended("jmp +2\nhlt\ntgl -2\njmp -3");
+        assert_eq!(machine.run(), Ok(Exit::Halted));
+        assert_eq!(machine.listing(), "nop +2\nhlt\ntgl -2\njmp -3");
+    }
+
+    #[test]
+    fn test_outputs_end_with_the_program() {
+        let mut machine = extended("out +1\nout +2\nhlt\nout +3");
+        assert_eq!(machine.outputs().collect::<Vec<_>>(), vec![Ok(1), Ok(2)]);
+
+        let mut machine = extended("out +1\njmp +0").step_limit(100);
+        assert_eq!(machine.outputs().count(), 1);
+    }
+
+    proptest! {
+        #[test]
+        fn test_instructions_are_the_handheld_ops(program in programs(1..20, 1)) {
+            let code = OpcodeTable::handheld().parse(&program.to_string()).unwrap();
+
+            let machine = Machine::new(program);
+            prop_assert_eq!(machine.code(), &code[..]);
+        }
+    }
+}
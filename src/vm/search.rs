@@ -0,0 +1,339 @@
+use std::collections::HashSet;
+use std::convert::TryFrom;
+
+use crate::vm::cfg::{successor, Patch, Repair};
+use crate::vm::{Exit, Instruction, Machine, Program};
+
+/// What a patched program has to do
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Goal {
+    Terminate,
+    /// Terminate with the accumulator at this value
+    Acc(i64),
+    /// Terminate after executing at most this many instructions
+    Within(u64),
+}
+
+impl Goal {
+    const fn accepts(self, run: &Run) -> bool {
+        match self {
+            Self::Terminate => true,
+            Self::Acc(acc) => run.acc == acc,
+            Self::Within(steps) => run.steps <= steps,
+        }
+    }
+}
+
+/// How many patches of every kind a repair may use
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct Budget {
+    /// `jmp`s turned into `nop`s and the other way around
+    pub flips: usize,
+    /// `acc` arguments changed to another value, they only help to reach [`Goal::Acc`]
+    pub acc_edits: usize,
+}
+
+impl Budget {
+    pub const fn flips(flips: usize) -> Self {
+        Self {
+            flips,
+            acc_edits: 0,
+        }
+    }
+
+    pub const fn acc_edits(mut self, acc_edits: usize) -> Self {
+        self.acc_edits = acc_edits;
+        self
+    }
+}
+
+/// A patched program that terminates
+#[derive(Debug, Clone)]
+struct Run {
+    patches: Vec<Patch>,
+    acc: i64,
+    steps: u64,
+    /// Addresses of the `acc` instructions it executed
+    accs: Vec<usize>,
+}
+
+///
+/// Every smallest set of patches within `budget` that makes `program` reach `goal`, each sorted
+/// by address. Empty if there's none, and a single empty set if the program needs no patches.
+///
+/// A patch that the patched program never executes doesn't change anything, so only sets where
+/// every patch is executed can be smallest. Those are found by flipping instructions the program
+/// executes one at a time, then the ones the patched program executes, and so on. The last flip
+/// has to send control somewhere the control flow graph says terminates, so only those few are
+/// run. That keeps two flips on the real input to a few thousand runs.
+///
+/// A terminating program executes every instruction at most once, so a single `acc` edit can
+/// always make up the difference to [`Goal::Acc`] when the new argument fits
+/// example
+/// ```
+/// # use advent_of_code_2020::vm::search::{search, Budget, Goal};
+/// # use advent_of_code_2020::vm::Instruction;
+/// let program = "jmp +0\njmp +0\nacc +3".parse().unwrap();
+///
+/// assert!(search(&program, Goal::Terminate, Budget::flips(1)).is_empty());
+///
+/// let repairs = search(&program, Goal::Acc(5), Budget::flips(2).acc_edits(1));
+/// assert_eq!(repairs.len(), 1);
+/// let replacements = repairs[0].iter().map(|patch| patch.replacement).collect::<Vec<_>>();
+/// assert_eq!(
+///     replacements,
+///     vec![Instruction::Nop(0), Instruction::Nop(0), Instruction::Acc(5)]
+/// );
+/// ```
+pub fn search(program: &Program, goal: Goal, budget: Budget) -> Vec<Vec<Patch>> {
+    // terminating runs by the number of flips they took
+    let mut terminating: Vec<Vec<Run>> = vec![];
+    for size in 0..=budget.flips + budget.acc_edits {
+        let mut found = vec![];
+        if size <= budget.flips {
+            terminating.push(terminating_flips(program, size));
+            found.extend(
+                terminating[size]
+                    .iter()
+                    .filter(|run| goal.accepts(run))
+                    .map(|run| run.patches.clone()),
+            );
+        }
+        if let Goal::Acc(target) = goal {
+            if budget.acc_edits > 0 && (1..=budget.flips + 1).contains(&size) {
+                for run in terminating[size - 1].iter().filter(|run| run.acc != target) {
+                    found.extend(run.accs.iter().filter_map(|address| {
+                        let mut patches = run.patches.clone();
+                        patches.push(acc_edit(program, *address, target - run.acc)?);
+                        Some(patches)
+                    }));
+                }
+            }
+        }
+
+        if !found.is_empty() {
+            for patches in &mut found {
+                patches.sort_by_key(|patch| patch.address);
+            }
+            found.sort_by_key(|patches| {
+                patches
+                    .iter()
+                    .map(|patch| patch.address)
+                    .collect::<Vec<_>>()
+            });
+            return found;
+        }
+    }
+    vec![]
+}
+
+/// Changes the `acc` at `address` to add `difference` more, `None` if that doesn't fit
+fn acc_edit(program: &Program, address: usize, difference: i64) -> Option<Patch> {
+    let original = program[address];
+    let argument = i64::from(original.argument()) + difference;
+    let replacement = Instruction::Acc(i32::try_from(argument).ok()?);
+    Some(Patch {
+        address,
+        original,
+        replacement,
+    })
+}
+
+/// Every set of `count` flips that makes the program terminate and that it executes all of
+fn terminating_flips(program: &Program, count: usize) -> Vec<Run> {
+    let mut found = vec![];
+    extend(program, &mut vec![], count, &mut HashSet::new(), &mut found);
+    found
+}
+
+fn extend(
+    program: &Program,
+    patches: &mut Vec<Patch>,
+    remaining: usize,
+    seen: &mut HashSet<Vec<usize>>,
+    found: &mut Vec<Run>,
+) {
+    if remaining == 0 {
+        found.extend(run(program, patches));
+        return;
+    }
+
+    let repair = Repair::new(program);
+    for address in repair.reachable() {
+        let original = program[*address];
+        let replacement = original.flipped();
+        if replacement == original || patches.iter().any(|patch| patch.address == *address) {
+            continue;
+        }
+        // the last flip has to lead somewhere that terminates, or there's no point running it
+        if remaining == 1
+            && !successor(program, *address, replacement)
+                .is_some_and(|target| repair.is_terminating(target))
+        {
+            continue;
+        }
+        // the same flips in another order give the same program
+        let mut addresses = patches
+            .iter()
+            .map(|patch| patch.address)
+            .chain(Some(*address))
+            .collect::<Vec<_>>();
+        addresses.sort_unstable();
+        if !seen.insert(addresses) {
+            continue;
+        }
+
+        let mut patched = program.clone();
+        patched.patch(*address, replacement);
+        patches.push(Patch {
+            address: *address,
+            original,
+            replacement,
+        });
+        extend(&patched, patches, remaining - 1, seen, found);
+        patches.pop();
+    }
+}
+
+fn run(program: &Program, patches: &[Patch]) -> Option<Run> {
+    let mut machine = Machine::new(program.clone()).profiled();
+    if machine.run() != Ok(Exit::Terminated) {
+        return None;
+    }
+    let coverage = machine.coverage()?;
+    let accs = program
+        .instructions()
+        .iter()
+        .enumerate()
+        .filter(|(address, instruction)| {
+            matches!(instruction, Instruction::Acc(_)) && !coverage.is_dead(*address)
+        })
+        .map(|(address, _)| address)
+        .collect();
+    Some(Run {
+        patches: patches.to_vec(),
+        acc: machine.acc(),
+        steps: machine.steps(),
+        accs,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::answers::expected;
+    use crate::solver::Part;
+    use crate::vm::testing::{programs, LOOPING};
+    use itertools::Itertools;
+    use proptest::prelude::*;
+
+    fn addresses(repairs: &[Vec<Patch>]) -> Vec<Vec<usize>> {
+        repairs
+            .iter()
+            .map(|patches| patches.iter().map(|patch| patch.address).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_single_flip_agrees_with_repair() {
+        let program = LOOPING.parse().unwrap();
+
+        let repairs = search(&program, Goal::Terminate, Budget::flips(1));
+
+        assert_eq!(repairs, vec![vec![Repair::new(&program).patch().unwrap()]]);
+    }
+
+    #[test]
+    fn test_terminating_program_needs_nothing() {
+        let program = "nop +0\nacc +1".parse().unwrap();
+
+        assert_eq!(
+            search(&program, Goal::Terminate, Budget::default()),
+            vec![vec![]]
+        );
+    }
+
+    #[test]
+    fn test_acc_edits() {
+        let program = LOOPING.parse().unwrap();
+
+        let repairs = search(&program, Goal::Acc(9), Budget::flips(1).acc_edits(1));
+
+        // fixing the loop gives 8, then any of the accs that run can add 1
+        assert_eq!(
+            addresses(&repairs),
+            vec![vec![1, 7], vec![6, 7], vec![7, 8]]
+        );
+        assert_eq!(repairs[2][1].replacement, Instruction::Acc(7));
+        assert!(search(&program, Goal::Acc(9), Budget::flips(1)).is_empty());
+    }
+
+    #[test]
+    fn test_steps_goal_prefers_shortcuts() {
+        // flipping 3 runs everything, flipping 0 as well jumps straight to it
+        let program = "nop +3\nacc +1\nacc +1\njmp -3".parse().unwrap();
+
+        let repairs = search(&program, Goal::Within(3), Budget::flips(2));
+
+        assert_eq!(addresses(&repairs), vec![vec![0, 3]]);
+        assert_eq!(
+            addresses(&search(&program, Goal::Within(4), Budget::flips(2))),
+            vec![vec![3]]
+        );
+    }
+
+    #[test]
+    fn test_two_flips_on_the_real_input() {
+        let program = include_str!("../../input/2020/day8.txt").parse().unwrap();
+        let part2 = expected(8, Part::Two).parse().unwrap();
+
+        // nothing terminates that fast, so every pair of flips has to be ruled out
+        assert!(search(&program, Goal::Within(10), Budget::flips(2)).is_empty());
+        assert_eq!(
+            search(&program, Goal::Acc(part2), Budget::flips(2)).len(),
+            1
+        );
+    }
+
+    fn goal() -> impl Strategy<Value = Goal> {
+        prop_oneof![
+            Just(Goal::Terminate),
+            (-5..5_i64).prop_map(Goal::Acc),
+            (0..10_u64).prop_map(Goal::Within),
+        ]
+    }
+
+    /// The smallest sets of up to `k` flips, by trying every combination
+    fn every_combination(program: &Program, goal: Goal, k: usize) -> Vec<Vec<usize>> {
+        let flippable = (0..program.len())
+            .filter(|address| program[*address].flipped() != program[*address])
+            .collect::<Vec<_>>();
+        for size in 0..=k {
+            let found = flippable
+                .iter()
+                .copied()
+                .combinations(size)
+                .filter(|addresses| {
+                    let mut patched = program.clone();
+                    for address in addresses {
+                        patched.patch(*address, program[*address].flipped());
+                    }
+                    run(&patched, &[]).is_some_and(|run| goal.accepts(&run))
+                })
+                .collect::<Vec<_>>();
+            if !found.is_empty() {
+                return found;
+            }
+        }
+        vec![]
+    }
+
+    proptest! {
+        #[test]
+        fn test_agrees_with_trying_every_combination(program in programs(1..10, 0), goal in goal()) {
+            let repairs = search(&program, goal, Budget::flips(2));
+
+            prop_assert_eq!(addresses(&repairs), every_combination(&program, goal, 2));
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use crate::vm::{Cycle, Exit, Fault, Instruction, Program};
+
+/// `next` of a jump out of the program, it faults when it's executed
+const FAULT: usize = usize::MAX;
+
+/// An instruction lowered to what it does: add `delta` to the accumulator and continue at `next`
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+struct Slot {
+    delta: i64,
+    next: usize,
+}
+
+///
+/// A [`Program`] compiled to direct threaded code. Every instruction is decoded once up front,
+/// into how much it adds to the accumulator and the address it continues at, so running is a
+/// loop without any matching on instructions. It runs exactly like a
+/// [`Machine`](crate::vm::Machine), stopping at the same place with the same result
+/// example
+/// ```
+/// # use advent_of_code_2020::vm::threaded::ThreadedCode;
+/// # use advent_of_code_2020::vm::Exit;
+/// let mut code = ThreadedCode::compile(&"acc +2\njmp +2\nacc +5\nacc -1".parse().unwrap());
+///
+/// assert_eq!(code.run(), Ok(Exit::Terminated));
+/// assert_eq!((code.ip(), code.acc(), code.steps()), (4, 1, 3));
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ThreadedCode {
+    program: Program,
+    slots: Vec<Slot>,
+    ip: usize,
+    acc: i64,
+    steps: u64,
+    step_limit: Option<u64>,
+}
+
+impl ThreadedCode {
+    pub fn compile(program: &Program) -> Self {
+        let slots = (0..program.len())
+            .map(|address| lower(program, address))
+            .collect();
+        Self {
+            program: program.clone(),
+            slots,
+            ip: 0,
+            acc: 0,
+            steps: 0,
+            step_limit: None,
+        }
+    }
+
+    /// Makes [`ThreadedCode::run`] give up after `step_limit` steps in total
+    pub const fn step_limit(mut self, step_limit: u64) -> Self {
+        self.step_limit = Some(step_limit);
+        self
+    }
+
+    pub const fn ip(&self) -> usize {
+        self.ip
+    }
+
+    pub const fn acc(&self) -> i64 {
+        self.acc
+    }
+
+    /// Number of instructions executed so far
+    pub const fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    pub const fn program(&self) -> &Program {
+        &self.program
+    }
+
+    /// Replaces an instruction and recompiles only that one, returning the old instruction
+    ///
+    /// Panics if `address` is outside the program
+    pub fn patch(&mut self, address: usize, instruction: Instruction) -> Instruction {
+        let old = self.program.patch(address, instruction);
+        self.slots[address] = lower(&self.program, address);
+        old
+    }
+
+    /// Goes back to the start, keeping patches
+    pub fn reset(&mut self) {
+        self.ip = 0;
+        self.acc = 0;
+        self.steps = 0;
+    }
+
+    /// Runs until the program terminates, is about to execute an instruction for the second time,
+    /// or runs out of steps, the same as [`Machine::run`](crate::vm::Machine::run)
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn run(&mut self) -> Result<Exit, Fault> {
+        let limit = self.step_limit.unwrap_or(u64::MAX);
+        // the step and accumulator of the first visit of every address, and the order of visits
+        let mut visits: Vec<Option<(u64, i64)>> = vec![None; self.slots.len()];
+        let mut trace = vec![];
+        loop {
+            let visit = match visits.get_mut(self.ip) {
+                None => return Ok(Exit::Terminated),
+                Some(visit) => visit,
+            };
+            if let Some((step, acc)) = *visit {
+                let start = trace.len() - (self.steps - step) as usize;
+                return Ok(Exit::Loop(Cycle {
+                    addresses: trace.split_off(start),
+                    entered_at_step: step,
+                    acc_at_entry: acc,
+                }));
+            }
+            *visit = Some((self.steps, self.acc));
+            if self.steps >= limit {
+                return Ok(Exit::OutOfSteps);
+            }
+
+            let slot = self.slots[self.ip];
+            if slot.next == FAULT {
+                return Err(fault(&self.program, self.ip));
+            }
+            trace.push(self.ip);
+            self.acc += slot.delta;
+            self.ip = slot.next;
+            self.steps += 1;
+        }
+    }
+}
+
+#[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+fn lower(program: &Program, address: usize) -> Slot {
+    match program[address] {
+        Instruction::Acc(argument) => Slot {
+            delta: i64::from(argument),
+            next: address + 1,
+        },
+        Instruction::Nop(_) => Slot {
+            delta: 0,
+            next: address + 1,
+        },
+        Instruction::Jmp(offset) => {
+            let target = address as i64 + i64::from(offset);
+            Slot {
+                delta: 0,
+                next: if (0..=program.len() as i64).contains(&target) {
+                    target as usize
+                } else {
+                    FAULT
+                },
+            }
+        }
+    }
+}
+
+/// The fault of the jump at `address`, which lowered to [`FAULT`]
+#[allow(clippy::cast_possible_wrap)]
+fn fault(program: &Program, address: usize) -> Fault {
+    let instruction = program[address];
+    Fault::JumpOutOfBounds {
+        address,
+        instruction: instruction.into(),
+        target: address as i64 + i64::from(instruction.argument()),
+        len: program.len(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::testing::{programs, LOOPING};
+    use crate::vm::Machine;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_patch_recompiles_the_instruction() {
+        let mut code = ThreadedCode::compile(&LOOPING.parse().unwrap());
+        assert!(matches!(code.run(), Ok(Exit::Loop(_))));
+
+        assert_eq!(code.patch(7, Instruction::Nop(-4)), Instruction::Jmp(-4));
+        code.reset();
+        assert_eq!(code.run(), Ok(Exit::Terminated));
+        assert_eq!(code.acc(), 8);
+    }
+
+    #[test]
+    fn test_agrees_on_a_large_program() {
+        // a million instructions that hop over each other, and then loop back
+        let len: usize = 1_000_000;
+        let instructions = (0..len)
+            .map(|address| match address % 4 {
+                _ if address == len - 1 => Instruction::Jmp(-(len as i32 - 2)),
+                0 => Instruction::Jmp(2),
+                1 => Instruction::Acc(-1),
+                _ => Instruction::Acc(1),
+            })
+            .collect::<Vec<_>>();
+        let program = Program::from(instructions);
+
+        let mut machine = Machine::new(program.clone());
+        let mut code = ThreadedCode::compile(&program);
+
+        assert_eq!(code.run(), machine.run());
+        assert_eq!(
+            (code.ip(), code.acc(), code.steps()),
+            (machine.ip(), machine.acc(), machine.steps())
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn test_agrees_with_the_interpreter(program in programs(0..30, 2), limit in prop::option::of(0..40_u64)) {
+            let mut machine = Machine::new(program.clone());
+            let mut code = ThreadedCode::compile(&program);
+            if let Some(limit) = limit {
+                machine = machine.step_limit(limit);
+                code = code.step_limit(limit);
+            }
+
+            prop_assert_eq!(code.run(), machine.run());
+            prop_assert_eq!(
+                (code.ip(), code.acc(), code.steps()),
+                (machine.ip(), machine.acc(), machine.steps())
+            );
+        }
+
+        #[test]
+        fn test_patches_agree_with_the_interpreter(program in programs(0..30, 2), address in any::<prop::sample::Index>()) {
+            prop_assume!(!program.is_empty());
+            let address = address.index(program.len());
+            let flipped = program[address].flipped();
+
+            let mut code = ThreadedCode::compile(&program);
+            code.patch(address, flipped);
+            let mut patched = program.clone();
+            patched.patch(address, flipped);
+            let mut machine = Machine::new(patched);
+
+            prop_assert_eq!(code.run(), machine.run());
+            prop_assert_eq!((code.ip(), code.acc()), (machine.ip(), machine.acc()));
+        }
+    }
+}